@@ -12,13 +12,26 @@ export CARGO_TARGET_DIR=/ci/cache/target
 # used when pointing stm32-metapac to a CI-built one.
 export CARGO_NET_GIT_FETCH_WITH_CLI=true
 
-cargo test --manifest-path ./embassy-executor/Cargo.toml --features metadata-name
+cargo test --manifest-path ./embassy-executor/Cargo.toml --features metadata-name,join-handle,stats,metadata-size,watchdog,executor-shared,task-local,coop,alloc
+cargo test --manifest-path ./embassy-executor/Cargo.toml --features watchdog
+cargo test --manifest-path ./embassy-executor/Cargo.toml --features arch-std,executor-thread --test test_macro
+cargo test --manifest-path ./embassy-executor/Cargo.toml --features arch-std,executor-thread,executor-shared --test test_shared
+cargo test --manifest-path ./embassy-executor/Cargo.toml --features arch-std,executor-thread,task-local --test test_task_local_threads
+cargo test --manifest-path ./embassy-executor/Cargo.toml --features arch-std,executor-thread,reactor-epoll --test test_reactor --test test_macro
 cargo test --manifest-path ./embassy-futures/Cargo.toml
+cargo test --manifest-path ./embassy-futures/Cargo.toml --features futures-set
 cargo test --manifest-path ./embassy-sync/Cargo.toml
+cargo test --manifest-path ./embassy-sync/Cargo.toml --features time
+cargo test --manifest-path ./embassy-sync/Cargo.toml --features coop --lib
+RUSTFLAGS="--cfg loom" cargo test --manifest-path ./embassy-sync/Cargo.toml --test loom --release
 cargo test --manifest-path ./embassy-embedded-hal/Cargo.toml
+cargo test --manifest-path ./embassy-embedded-hal/Cargo.toml --features time
 cargo test --manifest-path ./embassy-hal-internal/Cargo.toml
-cargo test --manifest-path ./embassy-time/Cargo.toml --features mock-driver,embassy-time-queue-utils/generic-queue-8
+cargo test --manifest-path ./embassy-time/Cargo.toml --features mock-driver,high-res,chrono,embassy-time-queue-utils/generic-queue-8
+cargo test --manifest-path ./embassy-time/Cargo.toml --features sim-driver
 cargo test --manifest-path ./embassy-time-driver/Cargo.toml
+cargo test --manifest-path ./embassy-time-queue-utils/Cargo.toml
+cargo test --manifest-path ./embassy-time-queue-utils/Cargo.toml --features pairing-heap-queue
 
 cargo test --manifest-path ./embassy-boot/Cargo.toml
 cargo test --manifest-path ./embassy-boot/Cargo.toml --features ed25519-dalek
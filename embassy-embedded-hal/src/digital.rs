@@ -0,0 +1,352 @@
+//! Debouncing and gesture recognition for digital inputs.
+//!
+//! [`Debouncer`] filters out the bounces of a mechanical switch or the glitches of a noisy input,
+//! and timestamps the edges of the input, for example to measure pulse widths. [`Button`]
+//! recognizes presses, long presses and double clicks of a push button.
+//!
+//! Both work with any input implementing the `embedded-hal` [`InputPin`] and the
+//! `embedded-hal-async` [`Wait`] traits, such as the GPIO `Input` of the Embassy HALs.
+use embassy_time::{Duration, Instant, TimeoutError, with_deadline, with_timeout};
+use embedded_hal_1::digital::{InputPin, PinState};
+use embedded_hal_async::digital::Wait;
+
+/// A debounced edge of an input.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct Edge {
+    /// The level of the input after the edge.
+    #[cfg_attr(feature = "defmt", defmt(Debug2Format))]
+    pub level: PinState,
+    /// When the input first changed to this level, before settling.
+    pub at: Instant,
+}
+
+/// Debounces a digital input.
+///
+/// A change of the input level is only reported once the input has kept its new level for the
+/// debounce duration, so bounces and glitches shorter than that are ignored.
+///
+/// The level is first read when waiting for a change, so the input should be stable then.
+pub struct Debouncer<P> {
+    pin: P,
+    debounce: Duration,
+    level: Option<PinState>,
+}
+
+impl<P: InputPin + Wait> Debouncer<P> {
+    /// Create a new debouncer for `pin`, ignoring changes shorter than `debounce`.
+    pub fn new(pin: P, debounce: Duration) -> Self {
+        Self {
+            pin,
+            debounce,
+            level: None,
+        }
+    }
+
+    /// Get the debounced level of the input, reading it if it wasn't known yet.
+    pub fn level(&mut self) -> Result<PinState, P::Error> {
+        match self.level {
+            Some(level) => Ok(level),
+            None => {
+                let level = PinState::from(self.pin.is_high()?);
+                self.level = Some(level);
+                Ok(level)
+            }
+        }
+    }
+
+    /// Wait for the debounced level of the input to change.
+    ///
+    /// ## Cancel safety
+    /// This is cancel safe: if the future is dropped while the input is settling, the change is
+    /// reported by the next call, but with a later timestamp.
+    pub async fn wait_for_change(&mut self) -> Result<Edge, P::Error> {
+        loop {
+            let level = self.level()?;
+            match level {
+                PinState::High => self.pin.wait_for_low().await?,
+                PinState::Low => self.pin.wait_for_high().await?,
+            }
+            let at = Instant::now();
+
+            // Wait for the input to settle.
+            while let Ok(result) = with_timeout(self.debounce, self.pin.wait_for_any_edge()).await {
+                result?;
+            }
+
+            let new_level = PinState::from(self.pin.is_high()?);
+            if new_level != level {
+                self.level = Some(new_level);
+                return Ok(Edge { level: new_level, at });
+            }
+        }
+    }
+
+    /// Wait for the debounced level of the input to be `level`, returning immediately if it
+    /// already is.
+    pub async fn wait_for_level(&mut self, level: PinState) -> Result<(), P::Error> {
+        while self.level()? != level {
+            self.wait_for_change().await?;
+        }
+        Ok(())
+    }
+
+    /// Wait for a debounced rising edge.
+    pub async fn wait_for_rising_edge(&mut self) -> Result<Edge, P::Error> {
+        self.wait_for_level(PinState::Low).await?;
+        self.wait_for_change().await
+    }
+
+    /// Wait for a debounced falling edge.
+    pub async fn wait_for_falling_edge(&mut self) -> Result<Edge, P::Error> {
+        self.wait_for_level(PinState::High).await?;
+        self.wait_for_change().await
+    }
+
+    /// Wait for a complete pulse at `level`, returning its width.
+    ///
+    /// The width is measured between the first changes of the input at both edges, so it isn't
+    /// skewed by the debounce duration.
+    pub async fn wait_for_pulse(&mut self, level: PinState) -> Result<Duration, P::Error> {
+        self.wait_for_level(!level).await?;
+        let start = self.wait_for_change().await?;
+        let end = self.wait_for_change().await?;
+        Ok(end.at - start.at)
+    }
+
+    /// Get a mutable reference to the input.
+    pub fn inner_mut(&mut self) -> &mut P {
+        &mut self.pin
+    }
+
+    /// Return the input.
+    pub fn into_inner(self) -> P {
+        self.pin
+    }
+}
+
+/// An event recognized by a [`Button`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum ButtonEvent {
+    /// The button was pressed and released, without a second press following.
+    Press,
+    /// The button has been held for the long press duration.
+    LongPress,
+    /// The button was pressed twice in a row.
+    DoubleClick,
+}
+
+/// Configuration of a [`Button`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct ButtonConfig {
+    /// Level of the input while the button is pressed. Defaults to low, for a button to ground
+    /// with a pull-up.
+    pub pressed_level: PinState,
+    /// How long the button must be held for a long press. Defaults to 1 s.
+    pub long_press: Duration,
+    /// Maximum time between releasing the button and pressing it again for a double click.
+    /// Defaults to 300 ms. A press is only reported once this time has elapsed, or right away if
+    /// this is zero, disabling double clicks.
+    pub double_click: Duration,
+}
+
+impl Default for ButtonConfig {
+    fn default() -> Self {
+        Self {
+            pressed_level: PinState::Low,
+            long_press: Duration::from_secs(1),
+            double_click: Duration::from_millis(300),
+        }
+    }
+}
+
+/// Recognizes presses, long presses and double clicks of a push button.
+pub struct Button<P> {
+    input: Debouncer<P>,
+    config: ButtonConfig,
+}
+
+impl<P: InputPin + Wait> Button<P> {
+    /// Create a new button from a debounced input.
+    pub fn new(input: Debouncer<P>, config: ButtonConfig) -> Self {
+        Self { input, config }
+    }
+
+    /// Wait for the next event of the button.
+    ///
+    /// A button held after a long press or a double click must be released before the next event.
+    pub async fn next(&mut self) -> Result<ButtonEvent, P::Error> {
+        let pressed = self.config.pressed_level;
+        let released = !pressed;
+
+        self.input.wait_for_level(released).await?;
+        let press = self.input.wait_for_change().await?;
+
+        let release = match with_deadline(press.at + self.config.long_press, self.input.wait_for_change()).await {
+            Ok(release) => release?,
+            Err(TimeoutError) => return Ok(ButtonEvent::LongPress),
+        };
+        if self.config.double_click == Duration::from_ticks(0) {
+            return Ok(ButtonEvent::Press);
+        }
+
+        match with_deadline(release.at + self.config.double_click, self.input.wait_for_change()).await {
+            Ok(result) => {
+                result?;
+                Ok(ButtonEvent::DoubleClick)
+            }
+            Err(TimeoutError) => Ok(ButtonEvent::Press),
+        }
+    }
+
+    /// Get a mutable reference to the debounced input.
+    pub fn inner_mut(&mut self) -> &mut Debouncer<P> {
+        &mut self.input
+    }
+
+    /// Return the debounced input.
+    pub fn into_inner(self) -> Debouncer<P> {
+        self.input
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    extern crate std;
+
+    use core::convert::Infallible;
+
+    use embassy_time::Timer;
+
+    use super::*;
+
+    /// An input following a script of `(millisecond, is_high)` changes, starting low.
+    struct ScriptedPin(&'static [(u64, bool)]);
+
+    impl ScriptedPin {
+        fn is_high_at(&self, at: Instant) -> bool {
+            self.0
+                .iter()
+                .take_while(|(ms, _)| Instant::from_millis(*ms) <= at)
+                .last()
+                .is_some_and(|(_, high)| *high)
+        }
+
+        async fn wait_until(&self, high: bool) {
+            while self.is_high_at(Instant::now()) != high {
+                let now = Instant::now();
+                match self.0.iter().find(|(ms, _)| Instant::from_millis(*ms) > now) {
+                    Some((ms, _)) => Timer::at(Instant::from_millis(*ms)).await,
+                    None => core::future::pending().await,
+                }
+            }
+        }
+    }
+
+    impl embedded_hal_1::digital::ErrorType for ScriptedPin {
+        type Error = Infallible;
+    }
+
+    impl InputPin for ScriptedPin {
+        fn is_high(&mut self) -> Result<bool, Infallible> {
+            Ok(self.is_high_at(Instant::now()))
+        }
+
+        fn is_low(&mut self) -> Result<bool, Infallible> {
+            Ok(!self.is_high_at(Instant::now()))
+        }
+    }
+
+    impl Wait for ScriptedPin {
+        async fn wait_for_high(&mut self) -> Result<(), Infallible> {
+            self.wait_until(true).await;
+            Ok(())
+        }
+
+        async fn wait_for_low(&mut self) -> Result<(), Infallible> {
+            self.wait_until(false).await;
+            Ok(())
+        }
+
+        async fn wait_for_rising_edge(&mut self) -> Result<(), Infallible> {
+            self.wait_until(false).await;
+            self.wait_until(true).await;
+            Ok(())
+        }
+
+        async fn wait_for_falling_edge(&mut self) -> Result<(), Infallible> {
+            self.wait_until(true).await;
+            self.wait_until(false).await;
+            Ok(())
+        }
+
+        async fn wait_for_any_edge(&mut self) -> Result<(), Infallible> {
+            let high = self.is_high_at(Instant::now());
+            self.wait_until(!high).await;
+            Ok(())
+        }
+    }
+
+    #[embassy_executor::test(mock_time)]
+    async fn debounces_edges() {
+        // Bounces when rising, then a glitch, then a clean falling edge.
+        let pin = ScriptedPin(&[
+            (10, true),
+            (11, false),
+            (12, true),
+            (50, false),
+            (52, true),
+            (100, false),
+        ]);
+        let mut input = Debouncer::new(pin, Duration::from_millis(5));
+
+        let edge = input.wait_for_change().await.unwrap();
+        assert_eq!(edge.level, PinState::High);
+        assert_eq!(edge.at, Instant::from_millis(10));
+        assert_eq!(Instant::now(), Instant::from_millis(17));
+
+        let edge = input.wait_for_change().await.unwrap();
+        assert_eq!(edge.level, PinState::Low);
+        assert_eq!(edge.at, Instant::from_millis(100));
+    }
+
+    #[embassy_executor::test(mock_time)]
+    async fn measures_pulses() {
+        let pin = ScriptedPin(&[(10, true), (11, false), (12, true), (40, false)]);
+        let mut input = Debouncer::new(pin, Duration::from_millis(5));
+
+        let width = input.wait_for_pulse(PinState::High).await.unwrap();
+        assert_eq!(width, Duration::from_millis(30));
+    }
+
+    #[embassy_executor::test(mock_time)]
+    async fn recognizes_gestures() {
+        let pin = ScriptedPin(&[
+            // Starts released, with the pull-up.
+            (0, true),
+            // Press.
+            (100, false),
+            (200, true),
+            // Long press.
+            (1000, false),
+            (3000, true),
+            // Double click, then a press.
+            (4000, false),
+            (4100, true),
+            (4200, false),
+            (4300, true),
+            (5000, false),
+            (5100, true),
+        ]);
+        let mut button = Button::new(Debouncer::new(pin, Duration::from_millis(10)), ButtonConfig::default());
+
+        assert_eq!(button.next().await, Ok(ButtonEvent::Press));
+        assert_eq!(Instant::now(), Instant::from_millis(500));
+        assert_eq!(button.next().await, Ok(ButtonEvent::LongPress));
+        assert_eq!(Instant::now(), Instant::from_millis(2000));
+        assert_eq!(button.next().await, Ok(ButtonEvent::DoubleClick));
+        assert_eq!(button.next().await, Ok(ButtonEvent::Press));
+    }
+}
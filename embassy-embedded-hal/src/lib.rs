@@ -4,6 +4,8 @@
 #![doc = include_str!("../README.md")]
 
 pub mod adapter;
+#[cfg(feature = "time")]
+pub mod digital;
 pub mod flash;
 pub mod shared_bus;
 
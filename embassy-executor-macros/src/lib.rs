@@ -37,6 +37,15 @@ use macros::*;
 ///     // Function body
 /// }
 /// ```
+///
+/// Warning at compile time if the task's storage, mostly consisting of its future, is bigger than 4096 bytes:
+///
+/// ``` rust
+/// #[embassy_executor::task(size_warning = 4096)]
+/// async fn mytask() {
+///     // Function body
+/// }
+/// ```
 #[proc_macro_attribute]
 pub fn task(args: TokenStream, item: TokenStream) -> TokenStream {
     task::run(args.into(), item.into()).into()
@@ -176,6 +185,55 @@ pub fn main_std(args: TokenStream, item: TokenStream) -> TokenStream {
     main::run(args.into(), item.into(), &main::ARCH_STD).into()
 }
 
+/// Declares an async test, running the function body as a task on a fresh std executor.
+///
+/// The test finishes once the function body returns. Helper tasks spawned by the test are not
+/// awaited, and any that are still running at that point are leaked along with the executor, so
+/// they can't be spawned again by later tests unless their `pool_size` allows it.
+///
+/// With the `mock_time` argument, the test runs with embassy-time's `MockDriver`, which must be
+/// enabled with the `mock-driver` feature of `embassy-time`. The driver is reset before the test
+/// runs, and whenever all tasks are idle, time is advanced to the next scheduled timer. Timeouts
+/// therefore complete instantly and deterministically.
+///
+/// With the `sim_time` argument, the test runs with embassy-time's `SimDriver` instead, enabled
+/// with the `sim-driver` feature of `embassy-time`. The driver is reset before the test runs, and
+/// whenever all tasks are idle, `SimDriver::advance_to_next_wake()` is called. The wakes performed
+/// during the test can be inspected with `SimDriver::timeline()`.
+///
+/// Tests using `mock_time` or `sim_time` don't run in parallel with each other, as they share the
+/// global driver.
+///
+/// The following restrictions apply:
+///
+/// * The function may accept at most 1 parameter, an `embassy_executor::Spawner` handle that it can use to spawn additional tasks.
+/// * The function must be declared `async`.
+/// * The function must not use generics.
+/// * The function must not return a value.
+///
+/// ## Examples
+///
+/// ``` rust
+/// #[embassy_executor::test]
+/// async fn my_test(spawner: embassy_executor::Spawner) {
+///     // Test body
+/// }
+/// ```
+///
+/// Using the mock time driver:
+///
+/// ``` rust
+/// #[embassy_executor::test(mock_time)]
+/// async fn times_out() {
+///     let result = embassy_time::with_timeout(embassy_time::Duration::from_secs(60), core::future::pending::<()>()).await;
+///     assert!(result.is_err());
+/// }
+/// ```
+#[proc_macro_attribute]
+pub fn test_std(args: TokenStream, item: TokenStream) -> TokenStream {
+    test::run(args.into(), item.into()).into()
+}
+
 /// Creates a new `executor` instance and declares an application entry point for WASM spawning the corresponding function body as an async task.
 ///
 /// The following restrictions apply:
@@ -1,2 +1,3 @@
 pub mod main;
 pub mod task;
+pub mod test;
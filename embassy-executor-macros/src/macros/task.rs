@@ -3,8 +3,9 @@ use std::str::FromStr;
 use darling::FromMeta;
 use darling::export::NestedMeta;
 use proc_macro2::{Span, TokenStream};
-use quote::{format_ident, quote};
+use quote::{format_ident, quote, quote_spanned};
 use syn::visit::{self, Visit};
+use syn::spanned::Spanned;
 use syn::{Expr, ExprLit, Lit, LitInt, ReturnType, Type, Visibility};
 
 use crate::util::*;
@@ -16,6 +17,9 @@ struct Args {
     /// Use this to override the `embassy_executor` crate path. Defaults to `::embassy_executor`.
     #[darling(default)]
     embassy_executor: Option<syn::Expr>,
+    /// Emit a warning if the task's storage is bigger than this many bytes.
+    #[darling(default)]
+    size_warning: Option<syn::Expr>,
 }
 
 pub fn run(args: TokenStream, item: TokenStream) -> TokenStream {
@@ -51,6 +55,8 @@ pub fn run(args: TokenStream, item: TokenStream) -> TokenStream {
         .embassy_executor
         .unwrap_or(Expr::Verbatim(TokenStream::from_str("::embassy_executor").unwrap()));
 
+    let size_warning = args.size_warning;
+
     let returns_impl_trait = match &f.sig.output {
         ReturnType::Type(_, ty) => matches!(**ty, Type::ImplTrait(_)),
         _ => false,
@@ -193,6 +199,31 @@ pub fn run(args: TokenStream, item: TokenStream) -> TokenStream {
         quote!(_spawn_async_fn)
     };
 
+    // A warning can't be emitted directly from a const evaluation, so pick between two impls based
+    // on the size, one of which is deprecated.
+    let size_check = match &size_warning {
+        Some(limit) => {
+            let note = format!(
+                "task `{}` is bigger than its `size_warning` of {} bytes",
+                task_ident,
+                quote!(#limit)
+            );
+            let check = quote_spanned!(limit.span()=> check);
+            quote! {
+                struct __TaskSizeCheck<const EXCEEDED: bool>;
+                impl __TaskSizeCheck<true> {
+                    #[deprecated(note = #note)]
+                    const fn check() {}
+                }
+                impl __TaskSizeCheck<false> {
+                    const fn check() {}
+                }
+                const _: () = __TaskSizeCheck::<{ TASK_SIZE > #limit }>::#check();
+            }
+        }
+        None => quote!(),
+    };
+
     #[cfg(feature = "nightly")]
     let mut task_outer_body = quote! {
         trait _EmbassyInternalTaskTrait {
@@ -209,6 +240,9 @@ pub fn run(args: TokenStream, item: TokenStream) -> TokenStream {
 
         const POOL_SIZE: usize = #pool_size;
         static POOL: #embassy_executor::raw::TaskPool<<() as _EmbassyInternalTaskTrait>::Fut, POOL_SIZE> = #embassy_executor::raw::TaskPool::new();
+        #[allow(unused)]
+        const TASK_SIZE: usize = ::core::mem::size_of::<#embassy_executor::raw::TaskStorage<<() as _EmbassyInternalTaskTrait>::Fut>>();
+        #size_check
         unsafe { POOL.#spawn(move || <() as _EmbassyInternalTaskTrait>::construct(#(#full_args,)*)) }
     };
     #[cfg(not(feature = "nightly"))]
@@ -226,6 +260,9 @@ pub fn run(args: TokenStream, item: TokenStream) -> TokenStream {
             {#embassy_executor::_export::task_pool_size::<_, _, _, POOL_SIZE>(#task_inner_ident)},
             {#embassy_executor::_export::task_pool_align::<_, _, _, POOL_SIZE>(#task_inner_ident)},
         > = unsafe { ::core::mem::transmute(#embassy_executor::_export::task_pool_new::<_, _, _, POOL_SIZE>(#task_inner_ident)) };
+        #[allow(unused)]
+        const TASK_SIZE: usize = #embassy_executor::_export::task_pool_size::<_, _, _, POOL_SIZE>(#task_inner_ident) / POOL_SIZE;
+        #size_check
         unsafe { __task_pool_get(#task_inner_ident).#spawn(move || #task_inner_ident(#(#full_args,)*)) }
     };
 
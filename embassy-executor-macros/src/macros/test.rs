@@ -0,0 +1,151 @@
+use darling::FromMeta;
+use darling::export::NestedMeta;
+use proc_macro2::TokenStream;
+use quote::quote;
+use syn::{ReturnType, Type};
+
+use crate::util::*;
+
+#[derive(Debug, FromMeta, Default)]
+struct Args {
+    #[darling(default)]
+    mock_time: bool,
+    #[darling(default)]
+    sim_time: bool,
+}
+
+pub fn run(args: TokenStream, item: TokenStream) -> TokenStream {
+    let mut errors = TokenStream::new();
+
+    // If any of the steps for this macro fail, we still want to expand to an item that is as close
+    // to the expected output as possible. This helps out IDEs such that completions and other
+    // related features keep working.
+    let f: ItemFn = match syn::parse2(item.clone()) {
+        Ok(x) => x,
+        Err(e) => return token_stream_with_error(item, e),
+    };
+
+    let args = match NestedMeta::parse_meta_list(args) {
+        Ok(x) => x,
+        Err(e) => return token_stream_with_error(item, e),
+    };
+
+    let args = match Args::from_list(&args) {
+        Ok(x) => x,
+        Err(e) => {
+            errors.extend(e.write_errors());
+            Args::default()
+        }
+    };
+
+    let fargs = f.sig.inputs.clone();
+
+    if f.sig.asyncness.is_none() {
+        error(&mut errors, &f.sig, "test function must be async");
+    }
+    if !f.sig.generics.params.is_empty() {
+        error(&mut errors, &f.sig, "test function must not be generic");
+    }
+    if f.sig.generics.where_clause.is_some() {
+        error(&mut errors, &f.sig, "test function must not have `where` clauses");
+    }
+    if f.sig.abi.is_some() {
+        error(&mut errors, &f.sig, "test function must not have an ABI qualifier");
+    }
+    if f.sig.variadic.is_some() {
+        error(&mut errors, &f.sig, "test function must not be variadic");
+    }
+    match &f.sig.output {
+        ReturnType::Default => {}
+        ReturnType::Type(_, ty) => match &**ty {
+            Type::Tuple(tuple) if tuple.elems.is_empty() => {}
+            _ => error(
+                &mut errors,
+                &f.sig,
+                "test function must either not return a value or return `()`",
+            ),
+        },
+    }
+
+    if fargs.len() > 1 {
+        error(
+            &mut errors,
+            &f.sig,
+            "test function must have at most 1 argument: the spawner.",
+        );
+    }
+
+    if args.mock_time && args.sim_time {
+        error(&mut errors, &f.sig, "`mock_time` and `sim_time` can't be used together");
+    }
+
+    let spawner_arg = if fargs.is_empty() { quote!() } else { quote!(spawner) };
+
+    let on_idle = if args.sim_time {
+        quote! {
+            let _time_driver = ::embassy_executor::_test::lock_time_driver();
+            let driver = ::embassy_time::SimDriver::get();
+            driver.reset();
+            let on_idle = move || driver.advance_to_next_wake();
+        }
+    } else if args.mock_time {
+        quote! {
+            let _time_driver = ::embassy_executor::_test::lock_time_driver();
+            let driver = ::embassy_time::MockDriver::get();
+            driver.reset();
+            let on_idle = move || match driver.next_alarm() {
+                Some(at) => {
+                    driver.advance(at.duration_since(::embassy_time::Instant::now()));
+                    true
+                }
+                None => false,
+            };
+        }
+    } else {
+        quote! {
+            let on_idle = || false;
+        }
+    };
+
+    let name = &f.sig.ident;
+    let f_body = f.body;
+
+    let mut test_attrs = TokenStream::new();
+    for attr in f.attrs {
+        test_attrs.extend(quote!(#attr));
+    }
+
+    let mut test_body = quote! {
+        static __EMBASSY_TEST_DONE: ::core::sync::atomic::AtomicBool = ::core::sync::atomic::AtomicBool::new(false);
+
+        #[::embassy_executor::task()]
+        #[allow(clippy::future_not_send)]
+        async fn __embassy_test(#fargs) {
+            async move { #f_body }.await;
+            __EMBASSY_TEST_DONE.store(true, ::core::sync::atomic::Ordering::Release);
+        }
+
+        #on_idle
+        ::embassy_executor::_test::run(
+            |spawner| spawner.spawn(__embassy_test(#spawner_arg).unwrap()),
+            || __EMBASSY_TEST_DONE.load(::core::sync::atomic::Ordering::Acquire),
+            on_idle,
+        );
+    };
+
+    if !errors.is_empty() {
+        test_body = TokenStream::new();
+    }
+
+    let result = quote! {
+        #[test]
+        #test_attrs
+        fn #name() {
+            #test_body
+        }
+
+        #errors
+    };
+
+    result
+}
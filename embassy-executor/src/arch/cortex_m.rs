@@ -42,6 +42,48 @@ fn __pender(context: *mut ()) {
     }
 }
 
+/// Core identification and cross-core wakeups through the SIO of the RP2040 and RP235x.
+///
+/// Writing to the FIFO raises the SIO FIFO interrupt of the other core, which wakes it up from
+/// `WFE`. `embassy-rp`'s `multicore` module handles that interrupt on both cores, with its
+/// `executor-shared-sio` feature, discarding the values it doesn't expect, such as `WAKE_TOKEN`.
+#[cfg(feature = "rp-sio")]
+pub(crate) mod sio {
+    use core::ptr;
+
+    const SIO_CPUID: *const u32 = 0xd000_0000_usize as _;
+    #[cfg(feature = "executor-shared-sio")]
+    const SIO_FIFO_ST: *const u32 = 0xd000_0050_usize as _;
+    #[cfg(feature = "executor-shared-sio")]
+    const SIO_FIFO_WR: *mut u32 = 0xd000_0054_usize as _;
+    /// `FIFO_ST.RDY`: the FIFO to the other core isn't full.
+    #[cfg(feature = "executor-shared-sio")]
+    const FIFO_ST_RDY: u32 = 1 << 1;
+
+    /// Value written to the FIFO to wake up the other core.
+    #[cfg(feature = "executor-shared-sio")]
+    const WAKE_TOKEN: u32 = 0x5745_4b45;
+
+    /// The number of the core running this.
+    pub(crate) fn core_id() -> u32 {
+        // Safety: reading CPUID has no side effect.
+        unsafe { ptr::read_volatile(SIO_CPUID) }
+    }
+
+    /// Wake up the other core.
+    #[cfg(feature = "executor-shared-sio")]
+    pub(crate) fn pend_other_core() {
+        // Safety: writing to the FIFO only raises the interrupt of the other core, and it's only
+        // written when it isn't full, so the values already in it are kept.
+        unsafe {
+            // A full FIFO means the other core hasn't read it yet, so it will wake up anyway.
+            if ptr::read_volatile(SIO_FIFO_ST) & FIFO_ST_RDY != 0 {
+                ptr::write_volatile(SIO_FIFO_WR, WAKE_TOKEN);
+            }
+        }
+    }
+}
+
 #[cfg(feature = "executor-thread")]
 pub use thread::*;
 #[cfg(feature = "executor-thread")]
@@ -107,6 +149,26 @@ mod thread {
                 };
             }
         }
+
+        /// Run the executor, also polling the `Send` tasks of a [`SharedExecutor`](raw::SharedExecutor).
+        ///
+        /// Run one executor per core with the same `SharedExecutor` to balance its tasks between
+        /// the cores. On RP2040 and RP235x, the `executor-shared-sio` feature wakes up the other
+        /// core through the SIO FIFO. Otherwise, this is the same as [`run()`](Self::run).
+        ///
+        /// This function never returns.
+        #[cfg(feature = "executor-shared")]
+        pub fn run_shared(&'static mut self, shared: &'static raw::SharedExecutor, init: impl FnOnce(Spawner)) -> ! {
+            self.inner.attach_shared(shared);
+            init(self.inner.spawner());
+
+            loop {
+                unsafe {
+                    self.inner.poll();
+                    asm!("wfe");
+                };
+            }
+        }
     }
 }
 
@@ -6,10 +6,13 @@ pub use thread::*;
 #[cfg(feature = "executor-thread")]
 mod thread {
     use std::marker::PhantomData;
+    #[cfg(not(feature = "reactor-epoll"))]
     use std::sync::{Condvar, Mutex};
 
-    pub use embassy_executor_macros::main_std as main;
+    pub use embassy_executor_macros::{main_std as main, test_std as test};
 
+    #[cfg(feature = "reactor-epoll")]
+    use crate::reactor::Reactor as Signaler;
     use crate::{Spawner, raw};
 
     #[unsafe(export_name = "__pender")]
@@ -28,7 +31,10 @@ mod thread {
     impl Executor {
         /// Create a new Executor.
         pub fn new() -> Self {
+            #[cfg(not(feature = "reactor-epoll"))]
             let signaler = Box::leak(Box::new(Signaler::new()));
+            #[cfg(feature = "reactor-epoll")]
+            let signaler = Box::leak(Box::new(Signaler::new().expect("failed to create the epoll reactor")));
             Self {
                 inner: raw::Executor::new(signaler as *mut Signaler as *mut ()),
                 not_send: PhantomData,
@@ -55,6 +61,27 @@ mod thread {
         ///
         /// This function never returns.
         pub fn run(&'static mut self, init: impl FnOnce(Spawner)) -> ! {
+            #[cfg(feature = "reactor-epoll")]
+            self.signaler.enter();
+            init(self.inner.spawner());
+
+            loop {
+                unsafe { self.inner.poll() };
+                self.signaler.wait()
+            }
+        }
+
+        /// Run the executor, also polling the `Send` tasks of a [`SharedExecutor`](raw::SharedExecutor).
+        ///
+        /// Run one executor per thread with the same `SharedExecutor` to balance its tasks
+        /// between the threads. Otherwise, this is the same as [`run()`](Self::run).
+        ///
+        /// This function never returns.
+        #[cfg(feature = "executor-shared")]
+        pub fn run_shared(&'static mut self, shared: &'static raw::SharedExecutor, init: impl FnOnce(Spawner)) -> ! {
+            self.inner.attach_shared(shared);
+            #[cfg(feature = "reactor-epoll")]
+            self.signaler.enter();
             init(self.inner.spawner());
 
             loop {
@@ -64,11 +91,52 @@ mod thread {
         }
     }
 
+    /// Implementation details for the `test` macro.
+    /// Do not use. Not covered by semver guarantees.
+    #[doc(hidden)]
+    pub mod _test {
+        use std::sync::{Mutex, MutexGuard};
+
+        use super::Executor;
+        use crate::Spawner;
+
+        /// Run a test on a fresh executor until `done` returns true.
+        ///
+        /// `on_idle` is called whenever no task is ready to run, and returns whether it made
+        /// progress (e.g. by advancing mock time). If it didn't, the executor sleeps until woken.
+        pub fn run(init: impl FnOnce(Spawner), mut done: impl FnMut() -> bool, mut on_idle: impl FnMut() -> bool) {
+            // Tasks may still reference the executor after the test returns, so it must live forever.
+            let executor = Box::leak(Box::new(Executor::new()));
+            #[cfg(feature = "reactor-epoll")]
+            executor.signaler.enter();
+            init(executor.inner.spawner());
+
+            loop {
+                unsafe { executor.inner.poll() };
+                if done() {
+                    return;
+                }
+                if !executor.signaler.take() && !on_idle() {
+                    executor.signaler.wait();
+                }
+            }
+        }
+
+        /// Serializes tests using the global mock or simulated time driver.
+        pub fn lock_time_driver() -> MutexGuard<'static, ()> {
+            static LOCK: Mutex<()> = Mutex::new(());
+            // The driver is reset by the next test, so the lock stays usable after a test panicked.
+            LOCK.lock().unwrap_or_else(|e| e.into_inner())
+        }
+    }
+
+    #[cfg(not(feature = "reactor-epoll"))]
     struct Signaler {
         mutex: Mutex<bool>,
         condvar: Condvar,
     }
 
+    #[cfg(not(feature = "reactor-epoll"))]
     impl Signaler {
         fn new() -> Self {
             Self {
@@ -85,6 +153,12 @@ mod thread {
             *signaled = false;
         }
 
+        /// Consume a pending signal without waiting, returning whether there was one.
+        fn take(&self) -> bool {
+            let mut signaled = self.mutex.lock().unwrap();
+            core::mem::replace(&mut *signaled, false)
+        }
+
         fn signal(&self) {
             let mut signaled = self.mutex.lock().unwrap();
             *signaled = true;
@@ -0,0 +1,111 @@
+//! Cooperative scheduling budget.
+//!
+//! A task that keeps finding work ready, for example receiving from a channel that is always
+//! full, never returns `Pending` from its poll. It starves every other task on its executor,
+//! even with the `scheduler-priority` or `scheduler-deadline` features, since the executor can only
+//! switch tasks between polls.
+//!
+//! With the `coop` feature, each task is given a budget of operations at the start of every poll.
+//! Futures taking part in budgeting (such as the ones of `embassy-sync` and `embassy-net`, with
+//! `embassy-sync`'s `coop` feature) consume a unit of budget each time they are ready. Once the
+//! budget is exhausted, they wake the task and return `Pending` instead, so that the executor
+//! polls other tasks before coming back to it.
+//!
+//! The budget is set per executor, with [`Spawner::set_coop_budget()`](crate::Spawner::set_coop_budget),
+//! and defaults to [`DEFAULT_BUDGET`]. A task spawned in a `SharedExecutor` gets the budget of
+//! the executor polling it.
+//!
+//! Futures consume budget through the `__embassy_coop_poll_proceed` function exported by the
+//! executor, usually via `embassy_sync::coop::poll_proceed()`. A future that turns out not to be
+//! ready gives the unit back with `__embassy_coop_refund`, so a task waiting on something never
+//! runs out of budget.
+
+use core::cell::Cell;
+use core::task::Waker;
+
+use critical_section::Mutex;
+
+use crate::raw;
+
+/// Number of operations a task may do in a single poll, unless set otherwise for its executor.
+pub const DEFAULT_BUDGET: u8 = 128;
+
+/// Consume a unit of the current task's budget, returning whether the task may proceed.
+///
+/// Wakers not created by the Embassy executor always proceed.
+#[unsafe(no_mangle)]
+fn __embassy_coop_poll_proceed(waker: &Waker) -> bool {
+    match raw::try_task_from_waker(waker) {
+        Some(task) => task.metadata().budget.consume(),
+        None => true,
+    }
+}
+
+/// Give back a unit of the current task's budget, consumed by an operation that didn't complete.
+#[unsafe(no_mangle)]
+fn __embassy_coop_refund(waker: &Waker) {
+    if let Some(task) = raw::try_task_from_waker(waker) {
+        task.metadata().budget.refund()
+    }
+}
+
+/// Remaining budget of a task, `None` if unconstrained.
+pub(crate) struct TaskBudget {
+    remaining: Mutex<Cell<Option<u8>>>,
+}
+
+impl TaskBudget {
+    pub(crate) const fn new() -> Self {
+        Self {
+            remaining: Mutex::new(Cell::new(None)),
+        }
+    }
+
+    pub(crate) fn reset(&self, budget: Option<u8>) {
+        critical_section::with(|cs| self.remaining.borrow(cs).set(budget))
+    }
+
+    fn consume(&self) -> bool {
+        critical_section::with(|cs| {
+            let cell = self.remaining.borrow(cs);
+            match cell.get() {
+                None => true,
+                Some(0) => false,
+                Some(n) => {
+                    cell.set(Some(n - 1));
+                    true
+                }
+            }
+        })
+    }
+
+    fn refund(&self) {
+        critical_section::with(|cs| {
+            let cell = self.remaining.borrow(cs);
+            cell.set(cell.get().map(|n| n.saturating_add(1)));
+        })
+    }
+}
+
+/// Budget given to the tasks of an executor, `None` if unconstrained.
+pub(crate) struct ExecutorBudget {
+    budget: Mutex<Cell<Option<u8>>>,
+}
+
+impl ExecutorBudget {
+    pub(crate) const fn new() -> Self {
+        Self {
+            budget: Mutex::new(Cell::new(Some(DEFAULT_BUDGET))),
+        }
+    }
+
+    pub(crate) fn get(&self) -> Option<u8> {
+        critical_section::with(|cs| self.budget.borrow(cs).get())
+    }
+
+    pub(crate) fn set(&self, budget: Option<u8>) {
+        // A budget of 0 would never let the task proceed.
+        let budget = budget.map(|b| b.max(1));
+        critical_section::with(|cs| self.budget.borrow(cs).set(budget))
+    }
+}
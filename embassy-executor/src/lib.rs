@@ -7,6 +7,9 @@
 //! ## Feature flags
 #![doc = document_features::document_features!(feature_label = r#"<span class="stab portability"><code>{feature}</code></span>"#)]
 
+#[cfg(feature = "alloc")]
+extern crate alloc;
+
 // This mod MUST go first, so that the others see its macros.
 pub(crate) mod fmt;
 
@@ -58,6 +61,29 @@ pub use spawner::*;
 mod metadata;
 pub use metadata::*;
 
+#[cfg(feature = "stats")]
+pub mod stats;
+
+#[cfg(feature = "metadata-size")]
+pub mod memory;
+
+#[cfg(feature = "watchdog")]
+pub mod watchdog;
+
+#[cfg(feature = "task-local")]
+pub mod task_local;
+
+#[cfg(feature = "coop")]
+pub mod coop;
+
+#[cfg(all(
+    feature = "reactor-epoll",
+    not(all(feature = "arch-std", feature = "executor-thread"))
+))]
+compile_error!("`reactor-epoll` requires the `arch-std` and `executor-thread` features.");
+#[cfg(feature = "reactor-epoll")]
+pub mod reactor;
+
 /// Implementation details for embassy macros.
 /// Do not use. Used for macros and HALs only. Not covered by semver guarantees.
 #[doc(hidden)]
@@ -0,0 +1,110 @@
+//! Task memory usage.
+//!
+//! Each task's memory is the size of its [`TaskStorage`](crate::raw::TaskStorage), which mostly
+//! consists of the task's future. A task pool reserves this memory once per slot, whether or not
+//! the slots are in use. With the `metadata-size` feature, the executor records the size and pool
+//! of every task it spawns, so that they can be inspected at runtime:
+//!
+//! ```rust,ignore
+//! for task in embassy_executor::memory::tasks() {
+//!     info!(
+//!         "{}: {} bytes, pool {}/{} spawned",
+//!         task.name.unwrap_or("unnamed"),
+//!         task.size,
+//!         task.pool_spawned,
+//!         task.pool_capacity,
+//!     );
+//! }
+//! ```
+//!
+//! To catch big futures at build time, use the `size_warning` argument of
+//! [`#[embassy_executor::task]`](crate::task).
+
+use core::cell::Cell;
+
+use critical_section::Mutex;
+
+use crate::raw::{TaskHeader, TaskRef};
+
+/// Memory usage of a task, yielded by [`tasks()`].
+#[derive(Debug, Clone, Copy)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct TaskMemory {
+    /// The task's ID. See [`SpawnToken::id()`](crate::SpawnToken::id).
+    pub id: u32,
+    /// The task's name, see [`Metadata::name()`](crate::Metadata::name).
+    pub name: Option<&'static str>,
+    /// Size of the task's storage in bytes.
+    pub size: usize,
+    /// Whether the task is currently spawned.
+    pub spawned: bool,
+    /// A unique ID of the pool the task belongs to, shared by all tasks of the pool.
+    pub pool_id: usize,
+    /// Number of tasks the pool can hold. This is 1 for a task spawned from a standalone `TaskStorage`.
+    pub pool_capacity: usize,
+    /// Number of tasks of the pool that are currently spawned.
+    pub pool_spawned: usize,
+}
+
+/// Returns an iterator over all tasks that have been spawned so far, on any executor.
+///
+/// Tasks that have exited are included. A task storage that has been spawned multiple times
+/// (e.g. a slot in a task pool) appears only once. Slots of a pool that have never been spawned
+/// are not listed, but are accounted for in [`TaskMemory::pool_capacity`].
+pub fn tasks() -> impl Iterator<Item = TaskMemory> {
+    crate::raw::trace::TASK_TRACKER.iter().map(|task: TaskRef| {
+        let metadata = task.metadata();
+        let layout = metadata.layout();
+        TaskMemory {
+            id: task.id(),
+            name: metadata.name(),
+            size: layout.size,
+            spawned: task.header().state.is_spawned(),
+            pool_id: layout.pool,
+            pool_capacity: layout.pool_len,
+            pool_spawned: (0..layout.pool_len)
+                .filter(|i| {
+                    // Safety: the pool is a `'static` array of `pool_len` task storages of `size` bytes,
+                    // each starting with its `TaskHeader`.
+                    let header = unsafe { &*((layout.pool + i * layout.size) as *const TaskHeader) };
+                    header.state.is_spawned()
+                })
+                .count(),
+        }
+    })
+}
+
+/// Where a task's storage lives.
+#[derive(Clone, Copy)]
+pub(crate) struct TaskLayout {
+    /// Size of the task's storage.
+    pub size: usize,
+    /// Address of the first storage of the pool.
+    pub pool: usize,
+    /// Number of storages in the pool.
+    pub pool_len: usize,
+}
+
+pub(crate) struct TaskLayoutCell {
+    layout: Mutex<Cell<TaskLayout>>,
+}
+
+impl TaskLayoutCell {
+    pub(crate) const fn new() -> Self {
+        Self {
+            layout: Mutex::new(Cell::new(TaskLayout {
+                size: 0,
+                pool: 0,
+                pool_len: 0,
+            })),
+        }
+    }
+
+    pub(crate) fn get(&self) -> TaskLayout {
+        critical_section::with(|cs| self.layout.borrow(cs).get())
+    }
+
+    pub(crate) fn set(&self, layout: TaskLayout) {
+        critical_section::with(|cs| self.layout.borrow(cs).set(layout))
+    }
+}
@@ -20,6 +20,16 @@ pub struct Metadata {
     priority: AtomicU8,
     #[cfg(feature = "scheduler-deadline")]
     deadline: raw::Deadline,
+    #[cfg(feature = "stats")]
+    pub(crate) stats: crate::stats::TaskStatsCell,
+    #[cfg(feature = "metadata-size")]
+    layout: crate::memory::TaskLayoutCell,
+    #[cfg(feature = "task-local")]
+    pub(crate) locals: crate::task_local::TaskLocals,
+    #[cfg(feature = "coop")]
+    pub(crate) budget: crate::coop::TaskBudget,
+    #[cfg(feature = "watchdog")]
+    pub(crate) watchdog: crate::watchdog::TaskWatchdog,
 }
 
 impl Metadata {
@@ -33,6 +43,16 @@ impl Metadata {
             // will be lazily initalized in `initialize_impl`
             #[cfg(feature = "scheduler-deadline")]
             deadline: raw::Deadline::new_unset(),
+            #[cfg(feature = "stats")]
+            stats: crate::stats::TaskStatsCell::new(),
+            #[cfg(feature = "metadata-size")]
+            layout: crate::memory::TaskLayoutCell::new(),
+            #[cfg(feature = "task-local")]
+            locals: crate::task_local::TaskLocals::new(),
+            #[cfg(feature = "coop")]
+            budget: crate::coop::TaskBudget::new(),
+            #[cfg(feature = "watchdog")]
+            watchdog: crate::watchdog::TaskWatchdog::new(),
         }
     }
 
@@ -47,6 +67,12 @@ impl Metadata {
         // a set deadline will ALWAYS be scheduled BEFORE a task WITHOUT a set deadline
         #[cfg(feature = "scheduler-deadline")]
         self.unset_deadline();
+
+        #[cfg(feature = "stats")]
+        self.stats.reset();
+
+        #[cfg(feature = "watchdog")]
+        self.watchdog.reset();
     }
 
     /// Get the metadata for the current task.
@@ -75,6 +101,45 @@ impl Metadata {
         critical_section::with(|cs| self.name.borrow(cs).set(Some(name)))
     }
 
+    /// Get the size of this task's storage in bytes.
+    ///
+    /// This is the memory used by the task, mostly consisting of its future.
+    ///
+    /// NOTE: this takes a critical section.
+    #[cfg(feature = "metadata-size")]
+    pub fn size(&self) -> usize {
+        self.layout().size
+    }
+
+    #[cfg(feature = "metadata-size")]
+    pub(crate) fn layout(&self) -> crate::memory::TaskLayout {
+        self.layout.get()
+    }
+
+    #[cfg(feature = "metadata-size")]
+    pub(crate) fn set_layout(&self, layout: crate::memory::TaskLayout) {
+        self.layout.set(layout)
+    }
+
+    /// Get this task's runtime statistics.
+    ///
+    /// NOTE: this takes a critical section.
+    #[cfg(feature = "stats")]
+    pub fn stats(&self) -> crate::stats::TaskStats {
+        self.stats.get()
+    }
+
+    /// Set the longest time this task may go without being polled, in ticks, before the
+    /// [watchdog](crate::watchdog) stops being fed. `None` (the default) doesn't watch the task.
+    ///
+    /// The time starts counting when this is called.
+    ///
+    /// NOTE: this takes a critical section.
+    #[cfg(feature = "watchdog")]
+    pub fn set_progress_timeout(&self, ticks: Option<u64>) {
+        self.watchdog.set_timeout(ticks)
+    }
+
     /// Get this task's priority.
     #[cfg(feature = "scheduler-priority")]
     pub fn priority(&self) -> u8 {
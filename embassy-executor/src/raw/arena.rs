@@ -0,0 +1,271 @@
+use core::alloc::Layout;
+use core::cell::RefCell;
+use core::future::Future;
+use core::mem::{self, MaybeUninit};
+use core::ptr::NonNull;
+
+use critical_section::Mutex;
+
+#[cfg(feature = "join-handle")]
+use super::JoinHandle;
+use super::{AvailableTask, TaskHeader, TaskStorage};
+use crate::{SpawnError, SpawnToken};
+
+/// Storage for tasks allocated at runtime from memory given to the arena.
+///
+/// A [`TaskPool`](super::TaskPool) reserves room for `N` instances of a single task at compile time.
+/// A `TaskArena` instead allocates the storage of each task it spawns from its memory, so that a
+/// single region of RAM can hold a varying number of tasks of any type.
+///
+/// Memory is added with [`add_memory()`](Self::add_memory), or taken from the global allocator
+/// when created with [`with_heap()`](Self::with_heap) (requires the `alloc` feature). Spawning
+/// fails with [`SpawnError::ArenaFull`] once there isn't enough memory left.
+///
+/// ```rust,ignore
+/// use core::mem::MaybeUninit;
+///
+/// use embassy_executor::raw::TaskArena;
+/// use static_cell::StaticCell;
+///
+/// static ARENA: TaskArena = TaskArena::new();
+/// static MEMORY: StaticCell<[MaybeUninit<u8>; 16 * 1024]> = StaticCell::new();
+///
+/// ARENA.add_memory(MEMORY.init([MaybeUninit::uninit(); 16 * 1024]));
+///
+/// loop {
+///     let socket = listener.accept().await;
+///     match ARENA.spawn(|| handle_connection(socket)) {
+///         Ok(token) => spawner.spawn(token),
+///         Err(e) => warn!("dropping connection: {}", e),
+///     }
+/// }
+/// ```
+///
+/// Once a task has finished running, the memory holding it goes back to the arena, and is reused
+/// by the next task spawned that fits in it. Blocks of memory are never merged or split again
+/// after they have held a task, because wakers of the finished task may still point to it, so an
+/// arena spawning tasks of very different sizes may have to be given some extra memory.
+pub struct TaskArena {
+    state: Mutex<RefCell<ArenaState>>,
+}
+
+struct ArenaState {
+    /// Blocks that have held a task.
+    blocks: Option<NonNull<Block>>,
+    /// Memory that has never held a task.
+    regions: Option<NonNull<Region>>,
+    /// Size of the chunks to allocate from the heap when out of memory, 0 to never allocate.
+    #[cfg(feature = "alloc")]
+    chunk_size: usize,
+}
+
+// Safety: blocks and regions belong to the arena, and are only accessed in a critical section.
+unsafe impl Send for ArenaState {}
+
+/// Memory holding a task, placed right before its `TaskStorage`.
+struct Block {
+    next: Option<NonNull<Block>>,
+    storage: NonNull<TaskHeader>,
+    /// Address of the end of the block.
+    end: usize,
+}
+
+/// Memory that has never held a task, placed at its start. Blocks are taken from its end.
+struct Region {
+    next: Option<NonNull<Region>>,
+    /// Address of the end of the free memory.
+    end: usize,
+}
+
+impl TaskArena {
+    /// Create an arena without any memory.
+    pub const fn new() -> Self {
+        Self {
+            state: Mutex::new(RefCell::new(ArenaState {
+                blocks: None,
+                regions: None,
+                #[cfg(feature = "alloc")]
+                chunk_size: 0,
+            })),
+        }
+    }
+
+    /// Create an arena taking memory from the global allocator as needed, in chunks of at least
+    /// `chunk_size` bytes.
+    ///
+    /// Allocated memory is never freed, but is reused by the arena once the tasks in it finish.
+    #[cfg(feature = "alloc")]
+    pub const fn with_heap(chunk_size: usize) -> Self {
+        Self {
+            state: Mutex::new(RefCell::new(ArenaState {
+                blocks: None,
+                regions: None,
+                chunk_size: if chunk_size == 0 { 1 } else { chunk_size },
+            })),
+        }
+    }
+
+    /// Give `memory` to the arena, to allocate tasks from.
+    pub fn add_memory(&self, memory: &'static mut [MaybeUninit<u8>]) {
+        let (ptr, len) = (memory.as_mut_ptr().cast::<u8>(), memory.len());
+        critical_section::with(|cs| unsafe { self.state.borrow_ref_mut(cs).add_region(ptr, len) })
+    }
+
+    /// Try to spawn a task in the arena.
+    ///
+    /// See [`TaskStorage::spawn()`] for details. This fails with [`SpawnError::ArenaFull`] if the
+    /// arena doesn't have enough free memory for the task.
+    pub fn spawn<F: Future + 'static>(&self, future: impl FnOnce() -> F) -> Result<SpawnToken<impl Sized>, SpawnError> {
+        Ok(self.claim::<F>()?.initialize(future))
+    }
+
+    /// Try to spawn a task in the arena, returning a [`JoinHandle`] to it along with the spawn token.
+    ///
+    /// See [`TaskStorage::spawn_joinable()`] for details. The memory used by the task goes back to
+    /// the arena once the task has exited and its handle has been joined or dropped.
+    #[cfg(feature = "join-handle")]
+    pub fn spawn_joinable<F: Future + 'static>(
+        &self,
+        future: impl FnOnce() -> F,
+    ) -> Result<(SpawnToken<impl Sized>, JoinHandle<F::Output>), SpawnError> {
+        Ok(self.claim::<F>()?.initialize_joinable(future))
+    }
+
+    fn claim<F: Future + 'static>(&self) -> Result<AvailableTask<F>, SpawnError> {
+        let layout = Layout::new::<TaskStorage<F>>();
+        critical_section::with(|cs| {
+            let mut state = self.state.borrow_ref_mut(cs);
+            if let Some(task) = state.reuse(layout) {
+                return Ok(task);
+            }
+            let storage = state.carve(layout).ok_or(SpawnError::ArenaFull)?;
+            // Safety: the block was just carved, nothing else refers to it.
+            let task = unsafe { &*storage.as_ptr().cast::<TaskStorage<F>>() };
+            Ok(unwrap!(AvailableTask::claim(task)))
+        })
+    }
+}
+
+impl Default for TaskArena {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl ArenaState {
+    /// # Safety
+    ///
+    /// `ptr` must be valid for `len` bytes forever, and not used by anything else.
+    unsafe fn add_region(&mut self, ptr: *mut u8, len: usize) {
+        let offset = ptr.align_offset(mem::align_of::<Region>());
+        if offset.saturating_add(mem::size_of::<Region>()) > len {
+            return;
+        }
+        let region = ptr.add(offset).cast::<Region>();
+        region.write(Region {
+            next: self.regions,
+            end: ptr.addr() + len,
+        });
+        self.regions = NonNull::new(region);
+    }
+
+    /// Claim the free block wasting the least memory for a task of `layout`.
+    fn reuse<F: Future + 'static>(&mut self, layout: Layout) -> Option<AvailableTask<F>> {
+        let mut best: Option<(&'static TaskStorage<F>, usize)> = None;
+        for block in self.blocks() {
+            let start = block.storage.as_ptr().addr();
+            if start % layout.align() != 0 || block.end - start < layout.size() {
+                continue;
+            }
+            // Safety: blocks hold a `TaskHeader` at the start of a `TaskStorage` that fits `F`.
+            let task = unsafe { &*block.storage.as_ptr().cast::<TaskStorage<F>>() };
+            let waste = block.end - start - layout.size();
+            if !task.raw.state.is_spawned() && best.is_none_or(|(_, w)| waste < w) {
+                best = Some((task, waste));
+            }
+        }
+        if let Some(task) = best.and_then(|(task, _)| AvailableTask::claim(task)) {
+            return Some(task);
+        }
+
+        // A finished task woken by a stale waker can't be claimed until its executor dequeues it.
+        self.blocks().find_map(|block| {
+            let start = block.storage.as_ptr().addr();
+            if start % layout.align() != 0 || block.end - start < layout.size() {
+                return None;
+            }
+            AvailableTask::claim(unsafe { &*block.storage.as_ptr().cast::<TaskStorage<F>>() })
+        })
+    }
+
+    fn blocks(&self) -> impl Iterator<Item = &'static Block> {
+        let mut next = self.blocks;
+        core::iter::from_fn(move || {
+            // Safety: blocks are valid forever, and immutable once in the list.
+            let block = unsafe { next?.as_ref() };
+            next = block.next;
+            Some(block)
+        })
+    }
+
+    /// Take a new block for a task of `layout` from the free memory, returning its storage with
+    /// an initialized `TaskHeader`.
+    fn carve(&mut self, layout: Layout) -> Option<NonNull<TaskHeader>> {
+        loop {
+            let mut next = self.regions;
+            while let Some(region) = next {
+                let region = region.as_ptr();
+                // Safety: regions are valid forever, and only accessed in a critical section.
+                unsafe {
+                    let end = (*region).end;
+                    let start = region.addr() + mem::size_of::<Region>();
+                    let storage = end.checked_sub(layout.size()).map(|a| a & !(layout.align() - 1));
+                    let block = storage.and_then(|s| s.checked_sub(mem::size_of::<Block>()));
+                    let block = block.map(|a| a & !(mem::align_of::<Block>() - 1));
+                    if let (Some(storage), Some(block)) = (storage, block.filter(|&b| b >= start)) {
+                        (*region).end = block;
+                        let storage = region.cast::<u8>().with_addr(storage).cast::<TaskHeader>();
+                        storage.write(TaskHeader::new());
+                        let block = region.cast::<u8>().with_addr(block).cast::<Block>();
+                        block.write(Block {
+                            next: self.blocks,
+                            storage: NonNull::new_unchecked(storage),
+                            end,
+                        });
+                        self.blocks = NonNull::new(block);
+                        return NonNull::new(storage);
+                    }
+                    next = (*region).next;
+                }
+            }
+
+            if !self.grow(layout) {
+                return None;
+            }
+        }
+    }
+
+    /// Add a chunk of heap memory big enough for a task of `layout`, returning whether it could.
+    #[cfg(feature = "alloc")]
+    fn grow(&mut self, layout: Layout) -> bool {
+        if self.chunk_size == 0 {
+            return false;
+        }
+        let needed = mem::size_of::<Region>() + mem::size_of::<Block>() + layout.size() + layout.align();
+        let Ok(chunk) = Layout::from_size_align(self.chunk_size.max(needed), mem::align_of::<Region>()) else {
+            return false;
+        };
+        let ptr = unsafe { alloc::alloc::alloc(chunk) };
+        if ptr.is_null() {
+            return false;
+        }
+        // Safety: the chunk is never deallocated.
+        unsafe { self.add_region(ptr, chunk.size()) };
+        true
+    }
+
+    #[cfg(not(feature = "alloc"))]
+    fn grow(&mut self, _layout: Layout) -> bool {
+        false
+    }
+}
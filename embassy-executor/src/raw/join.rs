@@ -0,0 +1,206 @@
+use core::cell::Cell;
+use core::future::poll_fn;
+use core::marker::PhantomData;
+use core::task::{Context, Poll, Waker};
+
+use critical_section::Mutex;
+
+use super::{TaskRef, wake_task};
+
+/// A [`JoinHandle`] exists for the task.
+const HANDLE: u8 = 1 << 0;
+/// Cancellation was requested.
+const CANCEL: u8 = 1 << 1;
+/// The task's future has been dropped, either because it completed or because it was cancelled.
+const EXITED: u8 = 1 << 2;
+/// The task's output is stored and has not been taken yet.
+const OUTPUT: u8 = 1 << 3;
+
+/// Per-task state shared between a task and its [`JoinHandle`].
+pub(crate) struct JoinState {
+    flags: Mutex<Cell<u8>>,
+    waker: Mutex<Cell<Option<Waker>>>,
+}
+
+impl JoinState {
+    pub(crate) const fn new() -> Self {
+        Self {
+            flags: Mutex::new(Cell::new(0)),
+            waker: Mutex::new(Cell::new(None)),
+        }
+    }
+
+    /// Reset the state for a freshly claimed task.
+    pub(crate) fn reset(&self, handle: bool) {
+        critical_section::with(|cs| {
+            self.flags.borrow(cs).set(if handle { HANDLE } else { 0 });
+            self.waker.borrow(cs).set(None);
+        })
+    }
+
+    pub(crate) fn cancel_requested(&self) -> bool {
+        critical_section::with(|cs| self.flags.borrow(cs).get() & CANCEL != 0)
+    }
+
+    /// Mark the task as exited, after its future has been dropped.
+    ///
+    /// If a handle is still waiting for the task, `store_output` is called to stash the output
+    /// for it, and `true` is returned: the task must then stay spawned until the handle is released.
+    pub(crate) fn exit(&self, store_output: Option<impl FnOnce()>) -> bool {
+        let (keep, waker) = critical_section::with(|cs| {
+            let flags = self.flags.borrow(cs);
+            if flags.get() & HANDLE == 0 {
+                return (false, None);
+            }
+            let mut f = flags.get() | EXITED;
+            if let Some(store_output) = store_output {
+                store_output();
+                f |= OUTPUT;
+            }
+            flags.set(f);
+            (true, self.waker.borrow(cs).take())
+        });
+        if let Some(waker) = waker {
+            waker.wake();
+        }
+        keep
+    }
+
+    /// Request cancellation. Returns `false` if the task has already exited.
+    fn cancel(&self) -> bool {
+        critical_section::with(|cs| {
+            let flags = self.flags.borrow(cs);
+            if flags.get() & EXITED != 0 {
+                return false;
+            }
+            flags.set(flags.get() | CANCEL);
+            true
+        })
+    }
+
+    fn is_exited(&self) -> bool {
+        critical_section::with(|cs| self.flags.borrow(cs).get() & EXITED != 0)
+    }
+
+    /// Wait for the task to exit. Resolves to whether an output was left for the caller to take.
+    fn poll_exit(&self, cx: &mut Context<'_>) -> Poll<bool> {
+        critical_section::with(|cs| {
+            let flags = self.flags.borrow(cs);
+            if flags.get() & EXITED == 0 {
+                self.waker.borrow(cs).set(Some(cx.waker().clone()));
+                return Poll::Pending;
+            }
+            let output = flags.get() & OUTPUT != 0;
+            flags.set(flags.get() & !OUTPUT);
+            Poll::Ready(output)
+        })
+    }
+
+    /// Detach the handle. Returns whether the task has exited, and whether it left an output behind.
+    fn release(&self) -> (bool, bool) {
+        critical_section::with(|cs| {
+            let flags = self.flags.borrow(cs);
+            let f = flags.get();
+            flags.set(f & !(HANDLE | OUTPUT));
+            self.waker.borrow(cs).set(None);
+            (f & EXITED != 0, f & OUTPUT != 0)
+        })
+    }
+}
+
+/// Error returned by [`JoinHandle::join()`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum JoinError {
+    /// The task was cancelled before it completed.
+    Cancelled,
+}
+
+impl core::fmt::Display for JoinError {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self {
+            JoinError::Cancelled => write!(f, "Task was cancelled"),
+        }
+    }
+}
+
+impl core::error::Error for JoinError {}
+
+/// Handle to a task spawned with [`TaskStorage::spawn_joinable()`](super::TaskStorage::spawn_joinable)
+/// or [`TaskPool::spawn_joinable()`](super::TaskPool::spawn_joinable).
+///
+/// The handle can be used to wait for the task's output, or to cancel it.
+///
+/// While the handle exists, the task's storage stays reserved even after the task has finished,
+/// so that its output can be retrieved. The storage becomes available for spawning again once the
+/// task has exited and the handle has been joined or dropped. Dropping the handle does not cancel the task.
+pub struct JoinHandle<T> {
+    task: TaskRef,
+    take_output: unsafe fn(TaskRef) -> T,
+    _phantom: PhantomData<T>,
+}
+
+unsafe impl<T: Send> Send for JoinHandle<T> {}
+unsafe impl<T: Send> Sync for JoinHandle<T> {}
+
+impl<T> JoinHandle<T> {
+    pub(crate) fn new(task: TaskRef, take_output: unsafe fn(TaskRef) -> T) -> Self {
+        Self {
+            task,
+            take_output,
+            _phantom: PhantomData,
+        }
+    }
+
+    /// Wait for the task to exit, returning its output.
+    ///
+    /// Returns [`JoinError::Cancelled`] if the task was cancelled before completing.
+    pub async fn join(self) -> Result<T, JoinError> {
+        let state = &self.task.header().join;
+        if poll_fn(|cx| state.poll_exit(cx)).await {
+            // Safety: the output was stored by the task, and the `OUTPUT` flag cleared above
+            // ensures it is taken only once.
+            Ok(unsafe { (self.take_output)(self.task) })
+        } else {
+            Err(JoinError::Cancelled)
+        }
+    }
+
+    /// Cancel the task.
+    ///
+    /// The task's future is dropped the next time the executor polls it, without being polled again.
+    /// Use [`join()`](Self::join) to wait until this has happened. If the task has already
+    /// completed, this does nothing and [`join()`](Self::join) still returns its output.
+    pub fn cancel(&self) {
+        if self.task.header().join.cancel() {
+            // The task is either still running on an executor, or its spawn token hasn't been
+            // spawned yet and it's already run-queued, in which case this is a no-op.
+            wake_task(self.task);
+        }
+    }
+
+    /// Returns whether the task has exited, either by completing or by being cancelled.
+    pub fn is_finished(&self) -> bool {
+        self.task.header().join.is_exited()
+    }
+}
+
+impl<T> Drop for JoinHandle<T> {
+    fn drop(&mut self) {
+        let (exited, output) = self.task.header().join.release();
+        if output {
+            // Safety: the `OUTPUT` flag was set, so the output is initialized and not yet taken.
+            drop(unsafe { (self.take_output)(self.task) });
+        }
+        if exited {
+            // The task kept its storage reserved for us. Release it.
+            self.task.header().state.despawn();
+        }
+    }
+}
+
+impl<T> core::fmt::Debug for JoinHandle<T> {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.debug_struct("JoinHandle").field("task", &self.task).finish()
+    }
+}
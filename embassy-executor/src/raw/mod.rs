@@ -26,8 +26,14 @@ pub(crate) mod util;
 #[cfg_attr(feature = "turbowakers", path = "waker_turbo.rs")]
 mod waker;
 
+#[cfg(feature = "task-arena")]
+mod arena;
 #[cfg(feature = "scheduler-deadline")]
 mod deadline;
+#[cfg(feature = "join-handle")]
+mod join;
+#[cfg(feature = "executor-shared")]
+mod shared;
 
 use core::future::Future;
 use core::marker::PhantomData;
@@ -39,16 +45,24 @@ use core::sync::atomic::AtomicPtr;
 use core::sync::atomic::Ordering;
 use core::task::{Context, Poll, Waker};
 
+#[cfg(feature = "task-arena")]
+pub use arena::TaskArena;
 #[cfg(feature = "scheduler-deadline")]
 pub(crate) use deadline::Deadline;
 use embassy_executor_timer_queue::TimerQueueItem;
+#[cfg(feature = "join-handle")]
+pub use join::{JoinError, JoinHandle};
 #[cfg(feature = "arch-avr")]
 use portable_atomic::AtomicPtr;
+#[cfg(feature = "executor-shared")]
+pub use shared::SharedExecutor;
 
 use self::run_queue::{RunQueue, RunQueueItem};
 use self::state::State;
 use self::util::{SyncUnsafeCell, UninitCell};
 pub use self::waker::task_from_waker;
+#[cfg(feature = "coop")]
+pub(crate) use self::waker::try_task_from_waker;
 use super::SpawnToken;
 use crate::{Metadata, SpawnError};
 
@@ -96,6 +110,9 @@ extern "Rust" fn __embassy_time_queue_item_from_waker(waker: &Waker) -> &'static
 /// - 4: A run-queued task exits - `TaskStorage::poll -> Poll::Ready`
 /// - 5: Task is dequeued. The task's future is not polled, because exiting the task replaces its `poll_fn`.
 /// - 6: A task is waken when it is not spawned - `wake_task -> State::run_enqueue`
+///
+/// With the `join-handle` feature, a task that exits while a `JoinHandle` to it exists stays `SPAWNED`
+/// (transition 4 is skipped) until the handle is joined or dropped, which then despawns it.
 pub(crate) struct TaskHeader {
     pub(crate) state: State,
     pub(crate) run_queue_item: RunQueueItem,
@@ -108,10 +125,32 @@ pub(crate) struct TaskHeader {
 
     pub(crate) metadata: Metadata,
 
-    #[cfg(feature = "rtos-trace")]
+    #[cfg(feature = "join-handle")]
+    join: join::JoinState,
+
+    #[cfg(feature = "_task-tracker")]
     all_tasks_next: AtomicPtr<TaskHeader>,
 }
 
+impl TaskHeader {
+    const fn new() -> Self {
+        Self {
+            state: State::new(),
+            run_queue_item: RunQueueItem::new(),
+            executor: AtomicPtr::new(core::ptr::null_mut()),
+            // Note: this is lazily initialized so that a static `TaskStorage` will go in `.bss`
+            poll_fn: SyncUnsafeCell::new(None),
+
+            timer_queue_item: TimerQueueItem::new(),
+            metadata: Metadata::new(),
+            #[cfg(feature = "join-handle")]
+            join: join::JoinState::new(),
+            #[cfg(feature = "_task-tracker")]
+            all_tasks_next: AtomicPtr::new(core::ptr::null_mut()),
+        }
+    }
+}
+
 /// This is essentially a `&'static TaskStorage<F>` where the type of the future has been erased.
 #[derive(Debug, Clone, Copy, PartialEq)]
 pub struct TaskRef {
@@ -168,6 +207,92 @@ impl TaskRef {
     pub fn id(&self) -> u32 {
         self.as_ptr() as u32
     }
+
+    /// Run the task, making it the [current task](current_task) until it returns.
+    ///
+    /// Safety: the task must be spawned, and dequeued by the executor running it.
+    unsafe fn run(self) {
+        #[cfg(feature = "task-local")]
+        let _current = CurrentTask::enter(self);
+        self.header().poll_fn.get().unwrap_unchecked()(self);
+    }
+}
+
+// With `std`, each thread can run an executor, so it has its own current task.
+#[cfg(all(feature = "task-local", any(feature = "arch-std", feature = "arch-wasm")))]
+std::thread_local! {
+    static CURRENT_TASK: core::cell::Cell<*mut TaskHeader> = const { core::cell::Cell::new(core::ptr::null_mut()) };
+}
+
+// Without `std`, each core has its own current task. Executors running in interrupts preempt
+// each other, but restore the current task when they're done, so a variable per core is enough.
+#[cfg(all(feature = "task-local", not(any(feature = "arch-std", feature = "arch-wasm"))))]
+static CURRENT_TASK: [AtomicPtr<TaskHeader>; CORES] = [const { AtomicPtr::new(core::ptr::null_mut()) }; CORES];
+
+#[cfg(all(feature = "task-local", feature = "rp-sio"))]
+const CORES: usize = 2;
+#[cfg(all(
+    feature = "task-local",
+    not(any(feature = "arch-std", feature = "arch-wasm", feature = "rp-sio"))
+))]
+const CORES: usize = 1;
+
+/// The slot of `CURRENT_TASK` of the core running this.
+#[cfg(all(feature = "task-local", not(any(feature = "arch-std", feature = "arch-wasm"))))]
+fn current_task_slot() -> &'static AtomicPtr<TaskHeader> {
+    #[cfg(feature = "rp-sio")]
+    return &CURRENT_TASK[crate::arch::sio::core_id() as usize];
+    #[cfg(not(feature = "rp-sio"))]
+    return &CURRENT_TASK[0];
+}
+
+#[cfg(feature = "task-local")]
+fn get_current_task() -> *mut TaskHeader {
+    #[cfg(any(feature = "arch-std", feature = "arch-wasm"))]
+    return CURRENT_TASK.with(|current| current.get());
+    #[cfg(not(any(feature = "arch-std", feature = "arch-wasm")))]
+    return current_task_slot().load(Ordering::Relaxed);
+}
+
+#[cfg(feature = "task-local")]
+fn set_current_task(task: *mut TaskHeader) {
+    #[cfg(any(feature = "arch-std", feature = "arch-wasm"))]
+    CURRENT_TASK.with(|current| current.set(task));
+    #[cfg(not(any(feature = "arch-std", feature = "arch-wasm")))]
+    current_task_slot().store(task, Ordering::Relaxed);
+}
+
+/// Returns the task being run by an executor in the current context, if any.
+///
+/// Without `std`, this is tracked per core, which on RP2040 and RP235x requires the `rp-sio`
+/// feature. Other multi-core chips may run executors on a single core only.
+#[cfg(feature = "task-local")]
+pub(crate) fn current_task() -> Option<TaskRef> {
+    let task = get_current_task();
+    // Safety: the pointer was obtained from a `TaskRef`.
+    (!task.is_null()).then(|| unsafe { TaskRef::from_ptr(task) })
+}
+
+/// Makes a task the current one until dropped, then restores the previous one.
+#[cfg(feature = "task-local")]
+struct CurrentTask(*mut TaskHeader);
+
+#[cfg(feature = "task-local")]
+impl CurrentTask {
+    fn enter(task: TaskRef) -> Self {
+        // Executors running in interrupts may preempt this, but restore the current task before
+        // returning, so this doesn't need to be atomic.
+        let previous = get_current_task();
+        set_current_task(task.as_ptr().cast_mut());
+        Self(previous)
+    }
+}
+
+#[cfg(feature = "task-local")]
+impl Drop for CurrentTask {
+    fn drop(&mut self) {
+        set_current_task(self.0);
+    }
 }
 
 /// Raw storage in which a task can be spawned.
@@ -189,10 +314,12 @@ impl TaskRef {
 pub struct TaskStorage<F: Future + 'static> {
     raw: TaskHeader,
     future: UninitCell<F>, // Valid if STATE_SPAWNED
+    #[cfg(feature = "join-handle")]
+    output: UninitCell<F::Output>, // Valid if a `JoinHandle` has yet to take it
 }
 
 unsafe fn poll_exited(_p: TaskRef) {
-    // Nothing to do, the task is already !SPAWNED and dequeued.
+    // Nothing to do, the task has already exited and is dequeued.
 }
 
 impl<F: Future + 'static> TaskStorage<F> {
@@ -201,19 +328,10 @@ impl<F: Future + 'static> TaskStorage<F> {
     /// Create a new TaskStorage, in not-spawned state.
     pub const fn new() -> Self {
         Self {
-            raw: TaskHeader {
-                state: State::new(),
-                run_queue_item: RunQueueItem::new(),
-                executor: AtomicPtr::new(core::ptr::null_mut()),
-                // Note: this is lazily initialized so that a static `TaskStorage` will go in `.bss`
-                poll_fn: SyncUnsafeCell::new(None),
-
-                timer_queue_item: TimerQueueItem::new(),
-                metadata: Metadata::new(),
-                #[cfg(feature = "rtos-trace")]
-                all_tasks_next: AtomicPtr::new(core::ptr::null_mut()),
-            },
+            raw: TaskHeader::new(),
             future: UninitCell::uninit(),
+            #[cfg(feature = "join-handle")]
+            output: UninitCell::uninit(),
         }
     }
 
@@ -238,32 +356,38 @@ impl<F: Future + 'static> TaskStorage<F> {
         }
     }
 
+    /// Try to spawn the task, returning a [`JoinHandle`] to it along with the spawn token.
+    ///
+    /// The handle can be used to wait for the task's output or to cancel it. While it exists,
+    /// this `TaskStorage` can't be spawned again, even if the task has finished running.
+    ///
+    /// See [`TaskStorage::spawn()`] for details.
+    #[cfg(feature = "join-handle")]
+    pub fn spawn_joinable(
+        &'static self,
+        future: impl FnOnce() -> F,
+    ) -> Result<(SpawnToken<impl Sized>, JoinHandle<F::Output>), SpawnError> {
+        let task = AvailableTask::claim(self);
+        match task {
+            Some(task) => Ok(task.initialize_joinable(future)),
+            None => Err(SpawnError::Busy),
+        }
+    }
+
     unsafe fn poll(p: TaskRef) {
         let this = &*p.as_ptr().cast::<TaskStorage<F>>();
 
+        #[cfg(feature = "join-handle")]
+        if this.raw.join.cancel_requested() {
+            this.exit(p, None);
+            return;
+        }
+
         let future = Pin::new_unchecked(this.future.as_mut());
         let waker = waker::from_task(p);
         let mut cx = Context::from_waker(&waker);
         match future.poll(&mut cx) {
-            Poll::Ready(_) => {
-                #[cfg(feature = "_any_trace")]
-                let exec_ptr: *const SyncExecutor = this.raw.executor.load(Ordering::Relaxed);
-
-                // As the future has finished and this function will not be called
-                // again, we can safely drop the future here.
-                this.future.drop_in_place();
-
-                // We replace the poll_fn with a despawn function, so that the task is cleaned up
-                // when the executor polls it next.
-                this.raw.poll_fn.set(Some(poll_exited));
-
-                // Make sure we despawn last, so that other threads can only spawn the task
-                // after we're done with it.
-                this.raw.state.despawn();
-
-                #[cfg(feature = "_any_trace")]
-                trace::task_end(exec_ptr, &p);
-            }
+            Poll::Ready(output) => this.exit(p, Some(output)),
             Poll::Pending => {}
         }
 
@@ -272,6 +396,55 @@ impl<F: Future + 'static> TaskStorage<F> {
         mem::forget(waker);
     }
 
+    /// Clean up after the task's future has completed with `output`, or was cancelled.
+    unsafe fn exit(&self, p: TaskRef, output: Option<F::Output>) {
+        #[cfg(feature = "_any_trace")]
+        let exec_ptr: *const SyncExecutor = self.raw.executor.load(Ordering::Relaxed);
+
+        // As the future has finished and this function will not be called
+        // again, we can safely drop the future here.
+        self.future.drop_in_place();
+
+        // We replace the poll_fn with a despawn function, so that the task is cleaned up
+        // when the executor polls it next.
+        self.raw.poll_fn.set(Some(poll_exited));
+
+        // An exited task can't make progress anymore, so stop watching it.
+        #[cfg(feature = "watchdog")]
+        self.raw.metadata.watchdog.reset();
+
+        // If a `JoinHandle` is waiting for the output, hand it over. The handle then despawns
+        // the task once it's done with it.
+        #[cfg(feature = "join-handle")]
+        let keep_spawned = self
+            .raw
+            .join
+            .exit(output.map(|output| move || self.output.write_in_place(|| output)));
+        #[cfg(not(feature = "join-handle"))]
+        let keep_spawned = {
+            drop(output);
+            false
+        };
+
+        // Make sure we despawn last, so that other threads can only spawn the task
+        // after we're done with it.
+        if !keep_spawned {
+            self.raw.state.despawn();
+        }
+
+        #[cfg(feature = "_any_trace")]
+        trace::task_end(exec_ptr, &p);
+        #[cfg(not(feature = "_any_trace"))]
+        let _ = p;
+    }
+
+    /// Take the output stored for a `JoinHandle`.
+    #[cfg(feature = "join-handle")]
+    unsafe fn take_output(p: TaskRef) -> F::Output {
+        let this = &*p.as_ptr().cast::<TaskStorage<F>>();
+        this.output.as_mut_ptr().read()
+    }
+
     #[doc(hidden)]
     #[allow(dead_code)]
     fn _assert_sync(self) {
@@ -297,6 +470,14 @@ impl<F: Future + 'static> AvailableTask<F> {
     fn initialize_impl<S>(self, future: impl FnOnce() -> F) -> SpawnToken<S> {
         unsafe {
             self.task.raw.metadata.reset();
+            #[cfg(feature = "metadata-size")]
+            self.task.raw.metadata.set_layout(crate::memory::TaskLayout {
+                size: mem::size_of::<TaskStorage<F>>(),
+                pool: self.task as *const _ as usize,
+                pool_len: 1,
+            });
+            #[cfg(feature = "join-handle")]
+            self.task.raw.join.reset(false);
             self.task.raw.poll_fn.set(Some(TaskStorage::<F>::poll));
             self.task.future.write_in_place(future);
 
@@ -311,6 +492,19 @@ impl<F: Future + 'static> AvailableTask<F> {
         self.initialize_impl::<F>(future)
     }
 
+    /// Initialize the [`TaskStorage`] to run the given future, returning a [`JoinHandle`] to the task.
+    #[cfg(feature = "join-handle")]
+    pub fn initialize_joinable(self, future: impl FnOnce() -> F) -> (SpawnToken<F>, JoinHandle<F::Output>) {
+        let task = self.task;
+        let token = self.initialize_impl::<F>(future);
+        // The token hasn't been spawned yet, so the task can't have exited already.
+        task.raw.join.reset(true);
+        (
+            token,
+            JoinHandle::new(TaskRef::new(task), TaskStorage::<F>::take_output),
+        )
+    }
+
     /// Initialize the [`TaskStorage`] to run the given future.
     ///
     /// # Safety
@@ -365,11 +559,25 @@ impl<F: Future + 'static, const N: usize> TaskPool<F, N> {
 
     fn spawn_impl<T>(&'static self, future: impl FnOnce() -> F) -> Result<SpawnToken<T>, SpawnError> {
         match self.pool.iter().find_map(AvailableTask::claim) {
-            Some(task) => Ok(task.initialize_impl::<T>(future)),
+            Some(task) => {
+                let token = task.initialize_impl::<T>(future);
+                self.record_layout(token.raw_task);
+                Ok(token)
+            }
             None => Err(SpawnError::Busy),
         }
     }
 
+    /// Record that `task` belongs to this pool.
+    fn record_layout(&'static self, _task: TaskRef) {
+        #[cfg(feature = "metadata-size")]
+        _task.metadata().set_layout(crate::memory::TaskLayout {
+            size: mem::size_of::<TaskStorage<F>>(),
+            pool: self.pool.as_ptr() as usize,
+            pool_len: N,
+        });
+    }
+
     /// Try to spawn a task in the pool.
     ///
     /// See [`TaskStorage::spawn()`] for details.
@@ -381,6 +589,25 @@ impl<F: Future + 'static, const N: usize> TaskPool<F, N> {
         self.spawn_impl::<F>(future)
     }
 
+    /// Try to spawn a task in the pool, returning a [`JoinHandle`] to it along with the spawn token.
+    ///
+    /// See [`TaskStorage::spawn_joinable()`] for details. The slot used by the task becomes free
+    /// again once the task has exited and its handle has been joined or dropped.
+    #[cfg(feature = "join-handle")]
+    pub fn spawn_joinable(
+        &'static self,
+        future: impl FnOnce() -> F,
+    ) -> Result<(SpawnToken<impl Sized>, JoinHandle<F::Output>), SpawnError> {
+        match self.pool.iter().find_map(AvailableTask::claim) {
+            Some(task) => {
+                let (token, handle) = task.initialize_joinable(future);
+                self.record_layout(token.raw_task);
+                Ok((token, handle))
+            }
+            None => Err(SpawnError::Busy),
+        }
+    }
+
     /// Like spawn(), but allows the task to be send-spawned if the args are Send even if
     /// the future is !Send.
     ///
@@ -417,16 +644,52 @@ impl Pender {
 pub(crate) struct SyncExecutor {
     run_queue: RunQueue,
     pender: Pender,
+    #[cfg(feature = "stats")]
+    pub(crate) stats: crate::stats::ExecutorStatsCell,
+    #[cfg(feature = "watchdog")]
+    pub(crate) watchdog: crate::watchdog::ExecutorWatchdog,
+    #[cfg(feature = "executor-shared")]
+    pub(crate) shared: shared::WorkerLink,
+    #[cfg(feature = "coop")]
+    pub(crate) coop: crate::coop::ExecutorBudget,
 }
 
 impl SyncExecutor {
-    pub(crate) fn new(pender: Pender) -> Self {
+    pub(crate) const fn new(pender: Pender) -> Self {
         Self {
             run_queue: RunQueue::new(),
             pender,
+            #[cfg(feature = "stats")]
+            stats: crate::stats::ExecutorStatsCell::new(),
+            #[cfg(feature = "watchdog")]
+            watchdog: crate::watchdog::ExecutorWatchdog::new(),
+            #[cfg(feature = "executor-shared")]
+            shared: shared::WorkerLink::new(false),
+            #[cfg(feature = "coop")]
+            coop: crate::coop::ExecutorBudget::new(),
         }
     }
 
+    /// Create the inner executor of a [`SharedExecutor`]. It has no pender of its own.
+    #[cfg(feature = "executor-shared")]
+    pub(crate) const fn new_shared() -> Self {
+        let mut this = Self::new(Pender(core::ptr::null_mut()));
+        this.shared = shared::WorkerLink::new(true);
+        this
+    }
+
+    #[inline(always)]
+    fn pend(&self) {
+        #[cfg(feature = "executor-shared")]
+        if self.shared.is_shared {
+            // Safety: only the inner executor of a `SharedExecutor` is marked as shared.
+            unsafe { SharedExecutor::from_inner(self) }.pend();
+            return;
+        }
+
+        self.pender.pend();
+    }
+
     /// Enqueue a task in the task queue
     ///
     /// # Safety
@@ -439,7 +702,7 @@ impl SyncExecutor {
         trace::task_ready_begin(self, &task);
 
         if self.run_queue.enqueue(task, l) {
-            self.pender.pend();
+            self.pend();
         }
     }
 
@@ -464,18 +727,21 @@ impl SyncExecutor {
         trace::poll_start(self);
 
         self.run_queue.dequeue_all(|p| {
-            let task = p.header();
-
             #[cfg(feature = "_any_trace")]
             trace::task_exec_begin(self, &p);
 
             // Run the task
-            task.poll_fn.get().unwrap_unchecked()(p);
+            p.run();
 
             #[cfg(feature = "_any_trace")]
             trace::task_exec_end(self, &p);
         });
 
+        #[cfg(feature = "executor-shared")]
+        if let Some(shared) = self.shared.get() {
+            shared.poll(self);
+        }
+
         #[cfg(feature = "_any_trace")]
         trace::executor_idle(self)
     }
@@ -582,10 +848,37 @@ impl Executor {
         super::Spawner::new(self)
     }
 
+    /// Attach this executor to a [`SharedExecutor`].
+    ///
+    /// From then on, each [`poll()`](Self::poll) also polls the shared tasks that are ready to
+    /// run, after this executor's own tasks, and the pender is also called when shared tasks are woken.
+    ///
+    /// # Panics
+    ///
+    /// Panics if this executor is already attached to a `SharedExecutor`.
+    #[cfg(feature = "executor-shared")]
+    pub fn attach_shared(&'static self, shared: &'static SharedExecutor) {
+        shared.attach(&self.inner)
+    }
+
     /// Get a unique ID for this Executor.
     pub fn id(&'static self) -> usize {
         &self.inner as *const SyncExecutor as usize
     }
+
+    /// Get the runtime statistics of this Executor.
+    #[cfg(feature = "stats")]
+    pub fn stats(&'static self) -> crate::stats::ExecutorStats {
+        self.inner.stats.get()
+    }
+
+    /// Set the number of operations each task may do in a single poll, or `None` for no limit.
+    ///
+    /// See the [`coop`](crate::coop) module.
+    #[cfg(feature = "coop")]
+    pub fn set_coop_budget(&'static self, budget: Option<u8>) {
+        self.inner.coop.set(budget)
+    }
 }
 
 /// Wake a task by `TaskRef`.
@@ -78,6 +78,14 @@ impl RunQueue {
         )
     }
 
+    /// Empty the queue, returning the tasks in it, most recently enqueued first.
+    ///
+    /// The tasks are still marked as run-queued.
+    #[cfg(feature = "executor-shared")]
+    pub(crate) fn take_all(&self) -> cordyceps::Stack<TaskHeader> {
+        self.stack.take_all()
+    }
+
     /// # Standard atomic runqueue
     ///
     /// Empty the queue, then call `on_task` for each task that was in the queue.
@@ -157,14 +165,14 @@ impl RunQueue {
 /// atomic state does not require a cs...
 #[cfg(target_has_atomic = "ptr")]
 #[inline(always)]
-fn run_dequeue(taskref: &TaskRef) {
+pub(crate) fn run_dequeue(taskref: &TaskRef) {
     taskref.header().state.run_dequeue();
 }
 
 /// ...while non-atomic state does
 #[cfg(not(target_has_atomic = "ptr"))]
 #[inline(always)]
-fn run_dequeue(taskref: &TaskRef) {
+pub(crate) fn run_dequeue(taskref: &TaskRef) {
     critical_section::with(|cs| {
         taskref.header().state.run_dequeue(cs);
     })
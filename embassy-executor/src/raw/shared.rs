@@ -0,0 +1,268 @@
+use core::cell::{Cell, UnsafeCell};
+use core::ptr;
+#[cfg(feature = "executor-shared-sio")]
+use core::sync::atomic::AtomicU32;
+use core::sync::atomic::Ordering;
+
+use cordyceps::Stack;
+use critical_section::{CriticalSection, Mutex};
+
+use super::run_queue::run_dequeue;
+#[cfg(feature = "_any_trace")]
+use super::trace;
+use super::{AtomicPtr, SyncExecutor, TaskHeader, TaskRef};
+use crate::SendSpawner;
+
+/// Run queue shared by multiple executors.
+///
+/// Tasks spawned in a `SharedExecutor` are not pinned to an executor: they are polled by whichever
+/// of the attached executors (the "workers") is free first. Attach an executor with
+/// [`Executor::attach_shared()`](super::Executor::attach_shared), or use the `run_shared()` method of
+/// the thread-mode executors. A worker polls its own tasks first, then the shared tasks that are
+/// ready to run. Each worker runs on its own thread or core, so the shared tasks are balanced
+/// between them automatically.
+///
+/// Since shared tasks move between threads, they can only be spawned with a [`SendSpawner`],
+/// so they must be `Send`. A task is never polled by two workers at the same time.
+///
+/// When a shared task is woken, all workers are pended, and the first one to poll takes it.
+/// Workers are pended with their own pender, so no extra signalling is needed between threads.
+/// On RP2040 and RP235x, enable the `executor-shared-sio` feature to wake up the worker of the
+/// other core through the SIO FIFO instead. This requires `embassy-rp`'s `multicore` module, with
+/// its own `executor-shared-sio` feature, to handle the FIFO interrupt of both cores.
+///
+/// Shared tasks are polled in the order they became ready, regardless of the
+/// `scheduler-priority` and `scheduler-deadline` features.
+///
+/// ```rust,ignore
+/// static SHARED: SharedExecutor = SharedExecutor::new();
+///
+/// // On each core or thread:
+/// executor.run_shared(&SHARED, |spawner| { /* spawn pinned tasks */ });
+///
+/// // Anywhere:
+/// SHARED.spawner().spawn(my_send_task().unwrap());
+/// ```
+///
+/// Tasks running in a `SharedExecutor` can't use
+/// [`Spawner::for_current_executor()`](crate::Spawner::for_current_executor), which panics, as
+/// it would allow spawning tasks that aren't `Send`. Use [`SendSpawner::for_current_executor()`]
+/// instead.
+#[repr(C)]
+pub struct SharedExecutor {
+    /// The executor shared tasks are spawned in. Its run queue receives tasks as they are woken.
+    ///
+    /// Must be the first field, so that `SharedExecutor` can be found from it.
+    pub(crate) inner: SyncExecutor,
+    /// Tasks taken from `inner`'s run queue, oldest first, waiting for a worker.
+    ready: Mutex<UnsafeCell<Tasks>>,
+    /// Tasks taken from `ready` while another worker was polling them.
+    deferred: Mutex<UnsafeCell<Tasks>>,
+    /// Head of the list of workers, linked by `WorkerLink::next`.
+    workers: AtomicPtr<SyncExecutor>,
+}
+
+/// A `cordyceps::Stack` of tasks, only accessed in a critical section.
+struct Tasks(Stack<TaskHeader>);
+
+// Safety: the tasks of the stack are only accessed while holding the critical section.
+unsafe impl Send for Tasks {}
+
+// Safety: all state is accessed atomically or in a critical section, and the tasks
+// spawned in a `SharedExecutor` are `Send`.
+unsafe impl Sync for SharedExecutor {}
+
+impl SharedExecutor {
+    /// Create a new shared executor.
+    pub const fn new() -> Self {
+        Self {
+            inner: SyncExecutor::new_shared(),
+            ready: Mutex::new(UnsafeCell::new(Tasks(Stack::new()))),
+            deferred: Mutex::new(UnsafeCell::new(Tasks(Stack::new()))),
+            workers: AtomicPtr::new(ptr::null_mut()),
+        }
+    }
+
+    /// Get a spawner that spawns tasks in this executor.
+    pub fn spawner(&'static self) -> SendSpawner {
+        SendSpawner::new(&self.inner)
+    }
+
+    /// Get a unique ID for this executor.
+    pub fn id(&'static self) -> usize {
+        &self.inner as *const SyncExecutor as usize
+    }
+
+    /// # Safety
+    ///
+    /// `inner` must be the `inner` field of a `SharedExecutor`.
+    pub(crate) unsafe fn from_inner(inner: &SyncExecutor) -> &Self {
+        &*(inner as *const SyncExecutor as *const Self)
+    }
+
+    pub(crate) fn attach(&'static self, worker: &'static SyncExecutor) {
+        let link = &worker.shared;
+        // Not all targets have atomic compare-and-swap, and attaching is rare.
+        critical_section::with(|_| {
+            if !link.shared.load(Ordering::Relaxed).is_null() {
+                panic!("executor is already attached to a SharedExecutor");
+            }
+            link.shared.store((self as *const Self).cast_mut(), Ordering::Release);
+            link.next.store(self.workers.load(Ordering::Relaxed), Ordering::Relaxed);
+            #[cfg(feature = "executor-shared-sio")]
+            link.core.store(crate::arch::sio::core_id(), Ordering::Relaxed);
+            self.workers
+                .store((worker as *const SyncExecutor).cast_mut(), Ordering::Release);
+        });
+
+        // Tasks may have been spawned before any worker was attached.
+        worker.pender.pend();
+    }
+
+    /// Pend all workers.
+    pub(crate) fn pend(&self) {
+        let mut worker = self.workers.load(Ordering::Acquire);
+        // Safety: workers are `'static` and never detached.
+        while let Some(w) = unsafe { worker.as_ref() } {
+            w.shared.pend(w);
+            worker = w.shared.next.load(Ordering::Acquire);
+        }
+    }
+
+    fn is_running(&self, cs: CriticalSection, task: TaskRef) -> bool {
+        let mut worker = self.workers.load(Ordering::Acquire);
+        // Safety: workers are `'static` and never detached.
+        while let Some(w) = unsafe { worker.as_ref() } {
+            if w.shared.current.borrow(cs).get() == Some(task) {
+                return true;
+            }
+            worker = w.shared.next.load(Ordering::Acquire);
+        }
+        false
+    }
+
+    /// Take the next ready task that no other worker is polling, and mark it as polled by `worker`.
+    fn take(&self, worker: &SyncExecutor, refill: &mut bool) -> Option<TaskRef> {
+        critical_section::with(|cs| {
+            // Safety: `ready` and `deferred` are only accessed in this module, in a critical section,
+            // and no reference to them outlives it.
+            let ready = unsafe { &mut (*self.ready.borrow(cs).get()).0 };
+            let deferred = unsafe { &mut (*self.deferred.borrow(cs).get()).0 };
+            loop {
+                let task = match ready.pop() {
+                    Some(task) => task,
+                    None if *refill => {
+                        *refill = false;
+                        // The run queue is newest first, reverse it.
+                        let mut taken = self.inner.run_queue.take_all();
+                        while let Some(task) = taken.pop() {
+                            ready.push(task);
+                        }
+                        continue;
+                    }
+                    None => return None,
+                };
+                if self.is_running(cs, task) {
+                    // Woken while being polled, it will be made ready again when the poll is done.
+                    deferred.push(task);
+                } else {
+                    worker.shared.current.borrow(cs).set(Some(task));
+                    return Some(task);
+                }
+            }
+        })
+    }
+
+    /// Mark `worker` as done polling its current task.
+    fn done(&self, worker: &SyncExecutor) {
+        let resumed = critical_section::with(|cs| {
+            worker.shared.current.borrow(cs).set(None);
+            // Safety: see `take`.
+            let ready = unsafe { &mut (*self.ready.borrow(cs).get()).0 };
+            let deferred = unsafe { &mut (*self.deferred.borrow(cs).get()).0 };
+            let resumed = !deferred.is_empty();
+            while let Some(task) = deferred.pop() {
+                ready.push(task);
+            }
+            resumed
+        });
+        if resumed {
+            self.pend();
+        }
+    }
+
+    /// Poll the shared tasks that are ready to run, on `worker`.
+    ///
+    /// The run queue is taken at most once, so tasks waking themselves can't keep the
+    /// worker from returning to its own tasks.
+    ///
+    /// # Safety
+    ///
+    /// Same as [`SyncExecutor::poll`], for `worker`.
+    pub(crate) unsafe fn poll(&self, worker: &'static SyncExecutor) {
+        let mut refill = true;
+        while let Some(task) = self.take(worker, &mut refill) {
+            run_dequeue(&task);
+
+            #[cfg(feature = "_any_trace")]
+            trace::task_exec_begin(worker, &task);
+
+            // Run the task
+            task.run();
+
+            #[cfg(feature = "_any_trace")]
+            trace::task_exec_end(worker, &task);
+
+            self.done(worker);
+        }
+    }
+}
+
+impl Default for SharedExecutor {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Link between an executor and the `SharedExecutor` it works for.
+pub(crate) struct WorkerLink {
+    /// Whether this is the `inner` executor of a `SharedExecutor`.
+    pub(crate) is_shared: bool,
+    /// The `SharedExecutor` this executor is attached to, if any.
+    pub(crate) shared: AtomicPtr<SharedExecutor>,
+    /// The next worker of the `SharedExecutor`.
+    next: AtomicPtr<SyncExecutor>,
+    /// The shared task this worker is polling.
+    current: Mutex<Cell<Option<TaskRef>>>,
+    /// The core this worker runs on.
+    #[cfg(feature = "executor-shared-sio")]
+    core: AtomicU32,
+}
+
+impl WorkerLink {
+    pub(crate) const fn new(is_shared: bool) -> Self {
+        Self {
+            is_shared,
+            shared: AtomicPtr::new(ptr::null_mut()),
+            next: AtomicPtr::new(ptr::null_mut()),
+            current: Mutex::new(Cell::new(None)),
+            #[cfg(feature = "executor-shared-sio")]
+            core: AtomicU32::new(u32::MAX),
+        }
+    }
+
+    /// Pend `worker`, the executor this is the link of.
+    fn pend(&self, worker: &SyncExecutor) {
+        #[cfg(feature = "executor-shared-sio")]
+        if self.core.load(Ordering::Relaxed) != crate::arch::sio::core_id() {
+            crate::arch::sio::pend_other_core();
+            return;
+        }
+        worker.pender.pend();
+    }
+
+    pub(crate) fn get(&self) -> Option<&'static SharedExecutor> {
+        // Safety: only ever set to a `&'static SharedExecutor`.
+        unsafe { self.shared.load(Ordering::Acquire).as_ref() }
+    }
+}
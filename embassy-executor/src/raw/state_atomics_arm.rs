@@ -62,6 +62,12 @@ impl State {
         self.spawned.store(false, Ordering::Relaxed);
     }
 
+    /// Return whether the task is spawned.
+    #[cfg(any(feature = "metadata-size", feature = "task-arena", feature = "watchdog"))]
+    pub fn is_spawned(&self) -> bool {
+        self.spawned.load(Ordering::Relaxed)
+    }
+
     /// Mark the task as run-queued if it's spawned and isn't already run-queued. Run the given
     /// function if the task was successfully marked.
     #[inline(always)]
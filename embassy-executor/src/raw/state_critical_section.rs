@@ -55,6 +55,12 @@ impl State {
         self.update(|s| *s &= !STATE_SPAWNED);
     }
 
+    /// Return whether the task is spawned.
+    #[cfg(any(feature = "metadata-size", feature = "task-arena", feature = "watchdog"))]
+    pub fn is_spawned(&self) -> bool {
+        self.update(|s| *s & STATE_SPAWNED != 0)
+    }
+
     /// Mark the task as run-queued if it's spawned and isn't already run-queued. Run the given
     /// function if the task was successfully marked.
     #[inline(always)]
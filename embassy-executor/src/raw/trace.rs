@@ -95,7 +95,7 @@ use crate::spawner::{SpawnError, SpawnToken, Spawner};
 /// This static provides access to the global task tracker which maintains
 /// a list of all tasks in the system. It's automatically updated by the
 /// task lifecycle hooks in the trace module.
-#[cfg(feature = "rtos-trace")]
+#[cfg(feature = "_task-tracker")]
 pub(crate) static TASK_TRACKER: TaskTracker = TaskTracker::new();
 
 /// A thread-safe tracker for all tasks in the system
@@ -103,19 +103,25 @@ pub(crate) static TASK_TRACKER: TaskTracker = TaskTracker::new();
 /// This struct uses an intrusive linked list approach to track all tasks
 /// without additional memory allocations. It maintains a global list of
 /// tasks that can be traversed to find all currently existing tasks.
-#[cfg(feature = "rtos-trace")]
+///
+/// The list is terminated by [`TaskTracker::END`] rather than null, so that a null
+/// `all_tasks_next` means the task is not in the list yet.
+#[cfg(feature = "_task-tracker")]
 pub(crate) struct TaskTracker {
     head: AtomicPtr<TaskHeader>,
 }
 
-#[cfg(feature = "rtos-trace")]
+#[cfg(feature = "_task-tracker")]
 impl TaskTracker {
+    /// Sentinel marking the end of the list.
+    const END: *mut TaskHeader = core::ptr::NonNull::dangling().as_ptr();
+
     /// Creates a new empty task tracker
     ///
     /// Initializes a tracker with no tasks in its list.
     pub const fn new() -> Self {
         Self {
-            head: AtomicPtr::new(core::ptr::null_mut()),
+            head: AtomicPtr::new(Self::END),
         }
     }
 
@@ -127,8 +133,13 @@ impl TaskTracker {
     ///
     /// # Arguments
     /// * `task` - The task reference to add to the tracker
+    ///
+    /// Tasks already in the tracker (because they have been spawned before) are not added again.
     pub fn add(&self, task: TaskRef) {
         let task_ptr = task.as_ptr();
+        if unsafe { !(*task_ptr).all_tasks_next.load(Ordering::Relaxed).is_null() } {
+            return;
+        }
 
         loop {
             let current_head = self.head.load(Ordering::Acquire);
@@ -159,13 +170,26 @@ impl TaskTracker {
         F: FnMut(TaskRef),
     {
         let mut current = self.head.load(Ordering::Acquire);
-        while !current.is_null() {
+        while current != Self::END {
             let task = unsafe { TaskRef::from_ptr(current) };
             f(task);
 
             current = unsafe { (*current).all_tasks_next.load(Ordering::Acquire) };
         }
     }
+
+    /// Returns an iterator over the tasks in the tracker
+    pub fn iter(&self) -> impl Iterator<Item = TaskRef> + 'static {
+        let mut current = self.head.load(Ordering::Acquire);
+        core::iter::from_fn(move || {
+            if current == Self::END {
+                return None;
+            }
+            let task = unsafe { TaskRef::from_ptr(current) };
+            current = unsafe { (*current).all_tasks_next.load(Ordering::Acquire) };
+            Some(task)
+        })
+    }
 }
 
 #[cfg(feature = "trace")]
@@ -234,6 +258,8 @@ pub(crate) fn poll_start(executor: &SyncExecutor) {
     unsafe {
         _embassy_trace_poll_start(executor as *const _ as u32)
     }
+    #[cfg(feature = "stats")]
+    executor.stats.poll_start();
 }
 
 #[inline]
@@ -256,7 +282,7 @@ pub(crate) fn task_new(executor: &SyncExecutor, task: &TaskRef) {
         rtos_trace::trace::task_send_info(task.id(), info);
     }
 
-    #[cfg(feature = "rtos-trace")]
+    #[cfg(feature = "_task-tracker")]
     TASK_TRACKER.add(*task);
 }
 
@@ -286,6 +312,12 @@ pub(crate) fn task_exec_begin(executor: &SyncExecutor, task: &TaskRef) {
     }
     #[cfg(feature = "rtos-trace")]
     rtos_trace::trace::task_exec_begin(task.as_ptr() as u32);
+    #[cfg(feature = "stats")]
+    executor.stats.task_exec_begin();
+    #[cfg(feature = "watchdog")]
+    executor.watchdog.task_exec_begin();
+    #[cfg(feature = "coop")]
+    task.metadata().budget.reset(executor.coop.get());
 }
 
 #[inline]
@@ -296,6 +328,10 @@ pub(crate) fn task_exec_end(executor: &SyncExecutor, task: &TaskRef) {
     }
     #[cfg(feature = "rtos-trace")]
     rtos_trace::trace::task_exec_end();
+    #[cfg(feature = "stats")]
+    executor.stats.task_exec_end(task);
+    #[cfg(feature = "watchdog")]
+    executor.watchdog.task_exec_end(executor as *const _ as usize, task);
 }
 
 #[inline]
@@ -306,6 +342,10 @@ pub(crate) fn executor_idle(executor: &SyncExecutor) {
     }
     #[cfg(feature = "rtos-trace")]
     rtos_trace::trace::system_idle();
+    #[cfg(feature = "stats")]
+    executor.stats.executor_idle();
+    #[cfg(feature = "watchdog")]
+    executor.watchdog.executor_idle(executor as *const _ as usize);
 }
 
 /// Returns an iterator over all active tasks in the system
@@ -327,7 +367,7 @@ fn get_all_active_tasks() -> impl Iterator<Item = TaskRef> + 'static {
         type Item = TaskRef;
 
         fn next(&mut self) -> Option<Self::Item> {
-            if self.current.is_null() {
+            if self.current == TaskTracker::END {
                 return None;
             }
 
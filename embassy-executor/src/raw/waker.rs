@@ -20,6 +20,16 @@ pub(crate) unsafe fn from_task(p: TaskRef) -> Waker {
     Waker::from_raw(RawWaker::new(p.as_ptr() as _, &VTABLE))
 }
 
+/// Get a task pointer from a waker, or `None` if it's not created by the Embassy executor.
+#[cfg(feature = "coop")]
+pub(crate) fn try_task_from_waker(waker: &Waker) -> Option<TaskRef> {
+    if !core::ptr::eq(waker.vtable(), &VTABLE) {
+        return None;
+    }
+    // safety: our wakers are always created with `TaskRef::as_ptr`
+    Some(unsafe { TaskRef::from_ptr(waker.data() as *const TaskHeader) })
+}
+
 /// Get a task pointer from a waker.
 ///
 /// This can be used as an optimization in wait queues to store task pointers
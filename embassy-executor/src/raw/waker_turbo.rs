@@ -7,6 +7,12 @@ pub(crate) unsafe fn from_task(p: TaskRef) -> Waker {
     Waker::from_turbo_ptr(NonNull::new_unchecked(p.as_ptr() as _))
 }
 
+/// Get a task pointer from a waker. Turbo wakers are always created by the Embassy executor.
+#[cfg(feature = "coop")]
+pub(crate) fn try_task_from_waker(waker: &Waker) -> Option<TaskRef> {
+    Some(task_from_waker(waker))
+}
+
 /// Get a task pointer from a waker.
 ///
 /// This can be used as an optimization in wait queues to store task pointers
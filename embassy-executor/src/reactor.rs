@@ -0,0 +1,411 @@
+//! I/O reactor for the `arch-std` executor on Linux.
+//!
+//! With the `reactor-epoll` feature, the std [`Executor`](crate::Executor) waits for work with
+//! `epoll` instead of parking its thread, so that tasks can wait for file descriptors (sockets,
+//! pipes, serial ports, TUN/TAP devices...) to become ready without any extra thread.
+//!
+//! Wrap a file descriptor in [`Async`] to use it from tasks:
+//!
+//! ```rust,ignore
+//! use embassy_executor::reactor::Async;
+//! use embedded_io_async::{Read, Write};
+//!
+//! #[embassy_executor::task]
+//! async fn echo(stream: std::net::TcpStream) {
+//!     let mut stream = Async::new(stream).unwrap();
+//!     let mut buf = [0; 64];
+//!     loop {
+//!         let n = stream.read(&mut buf).await.unwrap();
+//!         stream.write_all(&buf[..n]).await.unwrap();
+//!     }
+//! }
+//! ```
+//!
+//! Each executor has its own `epoll` instance. A file descriptor is registered with the one of the
+//! executor that first waits for it, and its events are only dispatched while that executor runs.
+
+use std::cell::Cell;
+use std::future::poll_fn;
+use std::io;
+use std::os::fd::{AsRawFd, RawFd};
+use std::sync::{Arc, Mutex};
+use std::task::{Context, Poll, Waker, ready};
+
+/// Key of the executor's eventfd in the `epoll` instance.
+const NOTIFY_KEY: u64 = u64::MAX;
+
+std::thread_local! {
+    static CURRENT: Cell<Option<&'static Reactor>> = const { Cell::new(None) };
+}
+
+fn check(res: libc::c_int) -> io::Result<libc::c_int> {
+    if res == -1 {
+        Err(io::Error::last_os_error())
+    } else {
+        Ok(res)
+    }
+}
+
+/// An `epoll` instance, with an eventfd to wake up the executor waiting on it.
+pub(crate) struct Reactor {
+    epoll: RawFd,
+    notify: RawFd,
+    sources: Mutex<Vec<Option<Arc<Source>>>>,
+}
+
+impl Reactor {
+    pub(crate) fn new() -> io::Result<Self> {
+        let epoll = check(unsafe { libc::epoll_create1(libc::EPOLL_CLOEXEC) })?;
+        let notify = check(unsafe { libc::eventfd(0, libc::EFD_CLOEXEC | libc::EFD_NONBLOCK) })?;
+        let mut event = libc::epoll_event {
+            events: libc::EPOLLIN as u32,
+            u64: NOTIFY_KEY,
+        };
+        check(unsafe { libc::epoll_ctl(epoll, libc::EPOLL_CTL_ADD, notify, &mut event) })?;
+        Ok(Self {
+            epoll,
+            notify,
+            sources: Mutex::new(Vec::new()),
+        })
+    }
+
+    /// Make this the reactor of the current thread, used by the [`Async`] values polled on it.
+    pub(crate) fn enter(&'static self) {
+        CURRENT.with(|c| c.set(Some(self)))
+    }
+
+    fn current() -> io::Result<&'static Reactor> {
+        CURRENT.with(|c| c.get()).ok_or_else(|| {
+            io::Error::other("`Async` must be polled from a task running on an `embassy_executor::Executor`")
+        })
+    }
+
+    /// Wake up the thread waiting on this reactor.
+    pub(crate) fn signal(&self) {
+        let one = 1u64;
+        // If the counter is about to overflow, the thread is already signaled.
+        unsafe { libc::write(self.notify, &one as *const u64 as *const libc::c_void, 8) };
+    }
+
+    /// Wait until signaled, dispatching I/O events in the meantime.
+    pub(crate) fn wait(&self) {
+        while !self.poll_events(-1) {}
+    }
+
+    /// Dispatch pending I/O events without waiting, and consume a pending signal, returning whether
+    /// there was one.
+    pub(crate) fn take(&self) -> bool {
+        self.poll_events(0)
+    }
+
+    fn poll_events(&self, timeout: libc::c_int) -> bool {
+        let mut events = [libc::epoll_event { events: 0, u64: 0 }; 32];
+        let n = loop {
+            match check(unsafe { libc::epoll_wait(self.epoll, events.as_mut_ptr(), events.len() as _, timeout) }) {
+                Ok(n) => break n as usize,
+                Err(e) if e.kind() == io::ErrorKind::Interrupted => continue,
+                Err(e) => panic!("epoll_wait failed: {}", e),
+            }
+        };
+
+        let mut signaled = false;
+        for event in &events[..n] {
+            let (key, flags) = (event.u64, event.events);
+            if key == NOTIFY_KEY {
+                let mut count = 0u64;
+                unsafe { libc::read(self.notify, &mut count as *mut u64 as *mut libc::c_void, 8) };
+                signaled = true;
+            } else if let Some(source) = self.sources.lock().unwrap().get(key as usize).cloned().flatten() {
+                // The key may have been reused since the event was queued. This only causes a spurious wakeup.
+                source.dispatch(flags);
+            }
+        }
+        signaled
+    }
+
+    fn register(&'static self, fd: RawFd) -> io::Result<Registration> {
+        let source = Arc::new(Source {
+            state: Mutex::new(State {
+                // Unknown until the first operation fails with `WouldBlock`.
+                readable: true,
+                writable: true,
+                read_waker: None,
+                write_waker: None,
+            }),
+        });
+
+        let key = {
+            let mut sources = self.sources.lock().unwrap();
+            let key = match sources.iter().position(Option::is_none) {
+                Some(key) => key,
+                None => {
+                    sources.push(None);
+                    sources.len() - 1
+                }
+            };
+            sources[key] = Some(source.clone());
+            key
+        };
+
+        let mut event = libc::epoll_event {
+            events: (libc::EPOLLIN | libc::EPOLLOUT | libc::EPOLLRDHUP | libc::EPOLLET) as u32,
+            u64: key as u64,
+        };
+        if let Err(e) = check(unsafe { libc::epoll_ctl(self.epoll, libc::EPOLL_CTL_ADD, fd, &mut event) }) {
+            self.sources.lock().unwrap()[key] = None;
+            return Err(e);
+        }
+
+        Ok(Registration {
+            reactor: self,
+            key,
+            source,
+        })
+    }
+
+    fn deregister(&self, fd: RawFd, key: usize) {
+        unsafe { libc::epoll_ctl(self.epoll, libc::EPOLL_CTL_DEL, fd, core::ptr::null_mut()) };
+        self.sources.lock().unwrap()[key] = None;
+    }
+}
+
+/// Readiness of a registered file descriptor.
+struct Source {
+    state: Mutex<State>,
+}
+
+struct State {
+    readable: bool,
+    writable: bool,
+    read_waker: Option<Waker>,
+    write_waker: Option<Waker>,
+}
+
+impl Source {
+    fn dispatch(&self, flags: u32) {
+        // Errors and hang-ups are reported to both directions, so the next operation returns them.
+        let closed = (libc::EPOLLERR | libc::EPOLLHUP) as u32;
+        let readable = flags & (libc::EPOLLIN | libc::EPOLLRDHUP) as u32 != 0 || flags & closed != 0;
+        let writable = flags & libc::EPOLLOUT as u32 != 0 || flags & closed != 0;
+
+        let mut state = self.state.lock().unwrap();
+        let mut wakers = [None, None];
+        if readable {
+            state.readable = true;
+            wakers[0] = state.read_waker.take();
+        }
+        if writable {
+            state.writable = true;
+            wakers[1] = state.write_waker.take();
+        }
+        drop(state);
+
+        for waker in wakers.into_iter().flatten() {
+            waker.wake();
+        }
+    }
+
+    fn poll_ready(&self, cx: &mut Context<'_>, read: bool) -> Poll<()> {
+        let mut state = self.state.lock().unwrap();
+        let state = &mut *state;
+        let (ready, waker) = if read {
+            (&mut state.readable, &mut state.read_waker)
+        } else {
+            (&mut state.writable, &mut state.write_waker)
+        };
+        if *ready {
+            *ready = false;
+            Poll::Ready(())
+        } else {
+            match waker {
+                Some(w) if w.will_wake(cx.waker()) => {}
+                _ => *waker = Some(cx.waker().clone()),
+            }
+            Poll::Pending
+        }
+    }
+}
+
+struct Registration {
+    reactor: &'static Reactor,
+    key: usize,
+    source: Arc<Source>,
+}
+
+/// A file descriptor waited for with the executor's reactor.
+///
+/// The file descriptor is put in non-blocking mode. Operations that would block wait until the
+/// file descriptor is ready instead.
+///
+/// Implements [`embedded_io_async::Read`] and [`embedded_io_async::Write`] if `T` implements
+/// [`std::io::Read`] and [`std::io::Write`].
+pub struct Async<T: AsRawFd> {
+    io: Option<T>,
+    registration: Mutex<Option<Registration>>,
+}
+
+impl<T: AsRawFd> Async<T> {
+    /// Wrap `io`, putting it in non-blocking mode.
+    ///
+    /// It is registered in the reactor of the executor that first waits for it.
+    pub fn new(io: T) -> io::Result<Self> {
+        let fd = io.as_raw_fd();
+        let flags = check(unsafe { libc::fcntl(fd, libc::F_GETFL) })?;
+        check(unsafe { libc::fcntl(fd, libc::F_SETFL, flags | libc::O_NONBLOCK) })?;
+        Ok(Self {
+            io: Some(io),
+            registration: Mutex::new(None),
+        })
+    }
+
+    /// Get a reference to the inner I/O object.
+    pub fn get_ref(&self) -> &T {
+        self.io.as_ref().unwrap()
+    }
+
+    /// Get a mutable reference to the inner I/O object.
+    pub fn get_mut(&mut self) -> &mut T {
+        self.io.as_mut().unwrap()
+    }
+
+    /// Unregister the file descriptor and return the inner I/O object.
+    ///
+    /// The file descriptor is left in non-blocking mode.
+    pub fn into_inner(mut self) -> T {
+        self.deregister();
+        self.io.take().unwrap()
+    }
+
+    fn source(&self) -> io::Result<Arc<Source>> {
+        let mut registration = self.registration.lock().unwrap();
+        if registration.is_none() {
+            *registration = Some(Reactor::current()?.register(self.get_ref().as_raw_fd())?);
+        }
+        Ok(registration.as_ref().unwrap().source.clone())
+    }
+
+    fn deregister(&mut self) {
+        if let Some(registration) = self.registration.get_mut().unwrap().take() {
+            let fd = self.get_ref().as_raw_fd();
+            registration.reactor.deregister(fd, registration.key);
+        }
+    }
+
+    /// Poll until the file descriptor may be readable.
+    ///
+    /// This may return `Ready` spuriously, retry the operation and poll again if it returns
+    /// [`WouldBlock`](io::ErrorKind::WouldBlock).
+    pub fn poll_readable(&self, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        self.source()?.poll_ready(cx, true).map(Ok)
+    }
+
+    /// Poll until the file descriptor may be writable.
+    ///
+    /// This may return `Ready` spuriously, retry the operation and poll again if it returns
+    /// [`WouldBlock`](io::ErrorKind::WouldBlock).
+    pub fn poll_writable(&self, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        self.source()?.poll_ready(cx, false).map(Ok)
+    }
+
+    /// Wait until the file descriptor may be readable.
+    pub async fn readable(&self) -> io::Result<()> {
+        poll_fn(|cx| self.poll_readable(cx)).await
+    }
+
+    /// Wait until the file descriptor may be writable.
+    pub async fn writable(&self) -> io::Result<()> {
+        poll_fn(|cx| self.poll_writable(cx)).await
+    }
+
+    /// Run a read operation, waiting for the file descriptor to be readable while it
+    /// returns [`WouldBlock`](io::ErrorKind::WouldBlock).
+    pub async fn read_with<R>(&self, mut op: impl FnMut(&T) -> io::Result<R>) -> io::Result<R> {
+        poll_fn(|cx| {
+            loop {
+                match op(self.get_ref()) {
+                    Err(e) if e.kind() == io::ErrorKind::WouldBlock => {}
+                    res => return Poll::Ready(res),
+                }
+                ready!(self.poll_readable(cx))?;
+            }
+        })
+        .await
+    }
+
+    /// Same as [`read_with()`](Self::read_with), with mutable access to the I/O object.
+    pub async fn read_with_mut<R>(&mut self, mut op: impl FnMut(&mut T) -> io::Result<R>) -> io::Result<R> {
+        poll_fn(|cx| {
+            loop {
+                match op(self.get_mut()) {
+                    Err(e) if e.kind() == io::ErrorKind::WouldBlock => {}
+                    res => return Poll::Ready(res),
+                }
+                ready!(self.poll_readable(cx))?;
+            }
+        })
+        .await
+    }
+
+    /// Run a write operation, waiting for the file descriptor to be writable while it
+    /// returns [`WouldBlock`](io::ErrorKind::WouldBlock).
+    pub async fn write_with<R>(&self, mut op: impl FnMut(&T) -> io::Result<R>) -> io::Result<R> {
+        poll_fn(|cx| {
+            loop {
+                match op(self.get_ref()) {
+                    Err(e) if e.kind() == io::ErrorKind::WouldBlock => {}
+                    res => return Poll::Ready(res),
+                }
+                ready!(self.poll_writable(cx))?;
+            }
+        })
+        .await
+    }
+
+    /// Same as [`write_with()`](Self::write_with), with mutable access to the I/O object.
+    pub async fn write_with_mut<R>(&mut self, mut op: impl FnMut(&mut T) -> io::Result<R>) -> io::Result<R> {
+        poll_fn(|cx| {
+            loop {
+                match op(self.get_mut()) {
+                    Err(e) if e.kind() == io::ErrorKind::WouldBlock => {}
+                    res => return Poll::Ready(res),
+                }
+                ready!(self.poll_writable(cx))?;
+            }
+        })
+        .await
+    }
+}
+
+impl<T: AsRawFd> Drop for Async<T> {
+    fn drop(&mut self) {
+        if self.io.is_some() {
+            self.deregister();
+        }
+    }
+}
+
+impl<T: AsRawFd> AsRawFd for Async<T> {
+    fn as_raw_fd(&self) -> RawFd {
+        self.get_ref().as_raw_fd()
+    }
+}
+
+impl<T: AsRawFd> embedded_io_async::ErrorType for Async<T> {
+    type Error = io::Error;
+}
+
+impl<T: AsRawFd + io::Read> embedded_io_async::Read for Async<T> {
+    async fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        self.read_with_mut(|io| io.read(buf)).await
+    }
+}
+
+impl<T: AsRawFd + io::Write> embedded_io_async::Write for Async<T> {
+    async fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.write_with_mut(|io| io.write(buf)).await
+    }
+
+    async fn flush(&mut self) -> io::Result<()> {
+        self.write_with_mut(|io| io.flush()).await
+    }
+}
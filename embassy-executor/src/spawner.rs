@@ -64,6 +64,10 @@ pub enum SpawnError {
     /// running at a time. You may allow multiple instances to run in parallel with
     /// `#[embassy_executor::task(pool_size = 4)]`, at the cost of higher RAM usage.
     Busy,
+    /// The [`TaskArena`](crate::raw::TaskArena) the task was spawned in doesn't have enough free
+    /// memory for it.
+    #[cfg(feature = "task-arena")]
+    ArenaFull,
 }
 
 impl core::fmt::Debug for SpawnError {
@@ -79,6 +83,11 @@ impl core::fmt::Display for SpawnError {
                 f,
                 "Busy - Too many instances of this task are already running. Check the `pool_size` attribute of the task."
             ),
+            #[cfg(feature = "task-arena")]
+            SpawnError::ArenaFull => write!(
+                f,
+                "ArenaFull - Not enough free memory in the task arena. Give it more memory with `add_memory`."
+            ),
         }
     }
 }
@@ -91,6 +100,11 @@ impl defmt::Format for SpawnError {
                 f,
                 "Busy - Too many instances of this task are already running. Check the `pool_size` attribute of the task."
             ),
+            #[cfg(feature = "task-arena")]
+            SpawnError::ArenaFull => defmt::write!(
+                f,
+                "ArenaFull - Not enough free memory in the task arena. Give it more memory with `add_memory`."
+            ),
         }
     }
 }
@@ -140,7 +154,9 @@ impl Spawner {
     ///
     /// # Panics
     ///
-    /// Panics if the current executor is not an Embassy executor.
+    /// Panics if the current executor is not an Embassy executor, or if the current task runs in a
+    /// [`SharedExecutor`](crate::raw::SharedExecutor): its tasks move between threads or cores, so
+    /// the tasks spawned from them must be `Send`. Use `SendSpawner::for_current_executor()` there.
     pub unsafe fn for_current_executor() -> impl Future<Output = Self> {
         poll_fn(|cx| {
             let task = raw::task_from_waker(cx.waker());
@@ -151,6 +167,12 @@ impl Spawner {
                     .as_ref()
                     .unwrap_unchecked()
             };
+            #[cfg(feature = "executor-shared")]
+            if executor.shared.is_shared {
+                panic!(
+                    "Spawner::for_current_executor() can't be used in a SharedExecutor, use SendSpawner::for_current_executor() instead"
+                );
+            }
             let executor = unsafe { raw::Executor::wrap(executor) };
             Poll::Ready(Self::new(executor))
         })
@@ -176,6 +198,19 @@ impl Spawner {
     pub fn executor_id(&self) -> usize {
         self.executor.id()
     }
+
+    /// Return the runtime statistics of this Spawner's Executor.
+    #[cfg(feature = "stats")]
+    pub fn executor_stats(&self) -> crate::stats::ExecutorStats {
+        self.executor.stats()
+    }
+
+    /// Set the number of operations each task of this Spawner's Executor may do in a single
+    /// poll, or `None` for no limit. See the [`coop`](crate::coop) module.
+    #[cfg(feature = "coop")]
+    pub fn set_coop_budget(&self, budget: Option<u8>) {
+        self.executor.set_coop_budget(budget)
+    }
 }
 
 /// Handle to spawn tasks into an executor from any thread.
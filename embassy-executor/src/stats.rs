@@ -0,0 +1,200 @@
+//! Runtime statistics.
+//!
+//! With the `stats` feature, the executor measures how long every poll of every task takes,
+//! and how much time each executor spends idle, using ticks of the `embassy-time-driver`.
+//! This is enough to build a "top"-like console:
+//!
+//! ```rust,ignore
+//! embassy_executor::stats::for_each_task(|task| {
+//!     info!(
+//!         "{}: {} polls, {} ticks total, {} ticks max",
+//!         task.name.unwrap_or("unnamed"),
+//!         task.stats.polls,
+//!         task.stats.total_ticks,
+//!         task.stats.max_ticks,
+//!     );
+//! });
+//! info!("load: {}%", spawner.executor_stats().load_percent());
+//! ```
+//!
+//! Durations are measured between the executor starting and finishing the poll of a task.
+//! If the executor is preempted while polling (e.g. by an interrupt, or an `InterruptExecutor`
+//! running at a higher priority), the preempting code's time is counted towards the polled task.
+
+use core::cell::Cell;
+
+use critical_section::Mutex;
+
+use crate::raw::TaskRef;
+
+/// Statistics of a single task.
+///
+/// These are reset when the task is spawned.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct TaskStats {
+    /// Number of times the task has been polled.
+    pub polls: u32,
+    /// Total time spent polling the task, in ticks.
+    pub total_ticks: u64,
+    /// Longest single poll of the task, in ticks.
+    pub max_ticks: u64,
+}
+
+/// Statistics of an executor.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct ExecutorStats {
+    /// Time spent polling tasks, in ticks.
+    pub busy_ticks: u64,
+    /// Time spent waiting for tasks to become ready, in ticks.
+    pub idle_ticks: u64,
+}
+
+impl ExecutorStats {
+    /// Percentage of time spent polling tasks, from 0 to 100.
+    ///
+    /// Returns 0 if nothing has been measured yet.
+    pub fn load_percent(&self) -> u8 {
+        let total = self.busy_ticks + self.idle_ticks;
+        if total == 0 {
+            return 0;
+        }
+        (self.busy_ticks * 100 / total) as u8
+    }
+}
+
+/// Snapshot of a task, passed to [`for_each_task`].
+#[derive(Debug, Clone, Copy)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct TaskSnapshot {
+    /// The task's ID. See [`SpawnToken::id()`](crate::SpawnToken::id).
+    pub id: u32,
+    /// The task's name, see [`Metadata::name()`](crate::Metadata::name).
+    pub name: Option<&'static str>,
+    /// The task's statistics.
+    pub stats: TaskStats,
+}
+
+/// Call `f` with a snapshot of every task that has been spawned so far, on any executor.
+///
+/// Tasks that have exited are included, with the statistics of their last run. A task storage
+/// that has been spawned multiple times (e.g. a slot in a task pool) appears only once.
+pub fn for_each_task(mut f: impl FnMut(TaskSnapshot)) {
+    crate::raw::trace::TASK_TRACKER.for_each(|task: TaskRef| {
+        let metadata = task.metadata();
+        f(TaskSnapshot {
+            id: task.id(),
+            name: metadata.name(),
+            stats: metadata.stats(),
+        })
+    })
+}
+
+pub(crate) struct TaskStatsCell {
+    stats: Mutex<Cell<TaskStats>>,
+}
+
+impl TaskStatsCell {
+    pub(crate) const fn new() -> Self {
+        Self {
+            stats: Mutex::new(Cell::new(TaskStats {
+                polls: 0,
+                total_ticks: 0,
+                max_ticks: 0,
+            })),
+        }
+    }
+
+    pub(crate) fn get(&self) -> TaskStats {
+        critical_section::with(|cs| self.stats.borrow(cs).get())
+    }
+
+    pub(crate) fn reset(&self) {
+        critical_section::with(|cs| self.stats.borrow(cs).set(TaskStats::default()))
+    }
+
+    fn record_poll(&self, ticks: u64) {
+        critical_section::with(|cs| {
+            let cell = self.stats.borrow(cs);
+            let mut stats = cell.get();
+            stats.polls = stats.polls.wrapping_add(1);
+            stats.total_ticks += ticks;
+            stats.max_ticks = stats.max_ticks.max(ticks);
+            cell.set(stats);
+        })
+    }
+}
+
+#[derive(Clone, Copy)]
+struct ExecutorState {
+    stats: ExecutorStats,
+    /// When the executor last went idle, if it is idle.
+    idle_since: Option<u64>,
+    /// When the executor started polling the current poll round.
+    poll_start: u64,
+    /// When the executor started polling the current task.
+    task_start: u64,
+}
+
+pub(crate) struct ExecutorStatsCell {
+    state: Mutex<Cell<ExecutorState>>,
+}
+
+impl ExecutorStatsCell {
+    pub(crate) const fn new() -> Self {
+        Self {
+            state: Mutex::new(Cell::new(ExecutorState {
+                stats: ExecutorStats {
+                    busy_ticks: 0,
+                    idle_ticks: 0,
+                },
+                idle_since: None,
+                poll_start: 0,
+                task_start: 0,
+            })),
+        }
+    }
+
+    pub(crate) fn get(&self) -> ExecutorStats {
+        critical_section::with(|cs| self.state.borrow(cs).get().stats)
+    }
+
+    fn update(&self, f: impl FnOnce(&mut ExecutorState)) {
+        critical_section::with(|cs| {
+            let cell = self.state.borrow(cs);
+            let mut state = cell.get();
+            f(&mut state);
+            cell.set(state);
+        })
+    }
+
+    pub(crate) fn poll_start(&self) {
+        let now = embassy_time_driver::now();
+        self.update(|s| {
+            if let Some(idle_since) = s.idle_since.take() {
+                s.stats.idle_ticks += now.saturating_sub(idle_since);
+            }
+            s.poll_start = now;
+        })
+    }
+
+    pub(crate) fn task_exec_begin(&self) {
+        let now = embassy_time_driver::now();
+        self.update(|s| s.task_start = now)
+    }
+
+    pub(crate) fn task_exec_end(&self, task: &TaskRef) {
+        let now = embassy_time_driver::now();
+        let task_start = critical_section::with(|cs| self.state.borrow(cs).get().task_start);
+        task.metadata().stats.record_poll(now.saturating_sub(task_start));
+    }
+
+    pub(crate) fn executor_idle(&self) {
+        let now = embassy_time_driver::now();
+        self.update(|s| {
+            s.stats.busy_ticks += now.saturating_sub(s.poll_start);
+            s.idle_since = Some(now);
+        })
+    }
+}
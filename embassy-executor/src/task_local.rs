@@ -0,0 +1,289 @@
+//! Task-local storage.
+//!
+//! Task-locals are the async equivalent of thread-locals: a value set for a task is visible to
+//! all code running in that task, without passing it through every function call. Declare keys
+//! with the [`task_local!`](macro@crate::task_local) macro, set a value for the duration of a future
+//! with [`LocalKey::scope()`], and read it with [`LocalKey::with()`]:
+//!
+//! ```rust,ignore
+//! use core::cell::Cell;
+//!
+//! embassy_executor::task_local! {
+//!     static REQUEST_ID: u32;
+//!     static BYTES_SENT: Cell<usize>;
+//! }
+//!
+//! #[embassy_executor::task(pool_size = 4)]
+//! async fn connection(id: u32, socket: TcpSocket<'static>) {
+//!     REQUEST_ID.scope(id, BYTES_SENT.scope(Cell::new(0), handle(socket))).await
+//! }
+//!
+//! fn log_sent(bytes: usize) {
+//!     let id = REQUEST_ID.get();
+//!     let total = BYTES_SENT.with(|sent| {
+//!         sent.set(sent.get() + bytes);
+//!         sent.get()
+//!     });
+//!     info!("request {}: sent {} bytes", id, total);
+//! }
+//! ```
+//!
+//! The value is stored in the future returned by [`LocalKey::scope()`], so it takes space in the
+//! task's [`TaskStorage`](crate::raw::TaskStorage) like any other local variable of the task.
+//! While the scope is being polled, it is registered in the task's [`Metadata`](crate::Metadata),
+//! which is where [`LocalKey::with()`] finds it.
+//!
+//! Accessors are plain functions, so they can be called from synchronous code such as logging
+//! helpers. They find the task that the executor is currently running, whatever the waker of
+//! the future calling them. Outside of a task, [`LocalKey::try_with()`] returns an error.
+//!
+//! With `std`, the executor tracks the current task per thread. Without it, it tracks it per
+//! core, so executors preempting each other on a core, like an `InterruptExecutor` and a
+//! thread-mode executor, can all use task-locals. On RP2040 and RP235x, enable the `rp-sio`
+//! feature to also run an executor on each core.
+
+use core::cell::Cell;
+use core::future::Future;
+use core::marker::{PhantomData, PhantomPinned};
+use core::pin::Pin;
+use core::ptr::{self, NonNull};
+use core::task::{Context, Poll};
+
+use critical_section::{CriticalSection, Mutex};
+
+use crate::raw;
+
+/// Declare task-local keys.
+///
+/// Each `static` declared is a [`LocalKey`]. See the [module documentation](mod@crate::task_local).
+///
+/// ```rust
+/// embassy_executor::task_local! {
+///     /// ID of the request the task is handling.
+///     pub static REQUEST_ID: u32;
+///     static RETRIES: core::cell::Cell<u8>;
+/// }
+/// ```
+#[macro_export]
+macro_rules! task_local {
+    () => {};
+    ($(#[$attr:meta])* $vis:vis static $name:ident: $t:ty; $($rest:tt)*) => {
+        $(#[$attr])*
+        $vis static $name: $crate::task_local::LocalKey<$t> = $crate::task_local::LocalKey::new();
+        $crate::task_local!($($rest)*);
+    };
+    ($(#[$attr:meta])* $vis:vis static $name:ident: $t:ty) => {
+        $crate::task_local!($(#[$attr])* $vis static $name: $t;);
+    };
+}
+
+/// A key for task-local data, declared with [`task_local!`](macro@crate::task_local).
+pub struct LocalKey<T: 'static> {
+    // Keys are identified by their address, so they must not be zero-sized.
+    _id: u8,
+    _phantom: PhantomData<fn() -> T>,
+}
+
+/// Error returned by [`LocalKey::try_with()`] when the current task has no value for the key, or
+/// when it's not called from a task.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct AccessError;
+
+impl core::fmt::Display for AccessError {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        write!(f, "task-local value not set")
+    }
+}
+
+impl core::error::Error for AccessError {}
+
+impl<T: 'static> LocalKey<T> {
+    #[doc(hidden)]
+    pub const fn new() -> Self {
+        Self {
+            _id: 0,
+            _phantom: PhantomData,
+        }
+    }
+
+    fn id(&'static self) -> *const () {
+        self as *const Self as *const ()
+    }
+
+    /// Run `future` with the task-local value set to `value`.
+    ///
+    /// Code running in `future` sees `value`, including inside nested functions. A nested
+    /// scope of the same key shadows this one until it completes.
+    ///
+    /// # Panics
+    ///
+    /// The returned future panics if it's not polled by a task spawned in an Embassy executor.
+    pub fn scope<F: Future>(&'static self, value: T, future: F) -> TaskLocalFuture<T, F> {
+        TaskLocalFuture {
+            value,
+            future,
+            entry: Entry {
+                key: self.id(),
+                value: ptr::null(),
+                next: Cell::new(None),
+            },
+            _pinned: PhantomPinned,
+        }
+    }
+
+    /// Call `f` with the value of the current task, or return an error if it has none, or if
+    /// this isn't called from a task.
+    ///
+    /// `f` is called in a critical section, so it must be kept short.
+    pub fn try_with<R>(&'static self, f: impl FnOnce(&T) -> R) -> Result<R, AccessError>
+    where
+        T: Send,
+    {
+        let metadata = raw::current_task().ok_or(AccessError)?.metadata();
+        critical_section::with(|cs| match metadata.locals.find(cs, self.id()) {
+            // Safety: entries of this key point to a `T`, which stays valid as long as it's
+            // registered, and is only accessed in a critical section.
+            Some(value) => Ok(f(unsafe { &*(value as *const T) })),
+            None => Err(AccessError),
+        })
+    }
+
+    /// Call `f` with the value of the current task.
+    ///
+    /// `f` is called in a critical section, so it must be kept short.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the current task has no value for this key, i.e. it isn't running
+    /// inside [`scope()`](Self::scope), or if this isn't called from a task.
+    pub fn with<R>(&'static self, f: impl FnOnce(&T) -> R) -> R
+    where
+        T: Send,
+    {
+        match self.try_with(f) {
+            Ok(r) => r,
+            Err(_) => panic!("task-local value not set"),
+        }
+    }
+
+    /// Get a copy of the value of the current task.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the current task has no value for this key, or if this isn't called from a task.
+    pub fn get(&'static self) -> T
+    where
+        T: Send + Copy,
+    {
+        self.with(|v| *v)
+    }
+}
+
+/// Future returned by [`LocalKey::scope()`].
+pub struct TaskLocalFuture<T: 'static, F> {
+    value: T,
+    future: F,
+    entry: Entry,
+    _pinned: PhantomPinned,
+}
+
+impl<T: 'static, F: Future> Future for TaskLocalFuture<T, F> {
+    type Output = F::Output;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<F::Output> {
+        let Some(task) = raw::current_task() else {
+            panic!("task-local scopes must be polled by a task spawned in an Embassy executor")
+        };
+        let locals = &task.metadata().locals;
+        // Safety: nothing is moved out of `this`.
+        let this = unsafe { self.get_unchecked_mut() };
+        this.entry.value = &this.value as *const T as *const ();
+
+        let entry = NonNull::from(&this.entry);
+        // Safety: the entry is pinned, and unregistered before `poll` returns (or unwinds).
+        unsafe { locals.push(entry) };
+        let _guard = OnDrop(|| locals.remove(entry));
+
+        // Safety: `future` is pinned as part of `this`.
+        unsafe { Pin::new_unchecked(&mut this.future) }.poll(cx)
+    }
+}
+
+struct OnDrop<F: FnMut()>(F);
+
+impl<F: FnMut()> Drop for OnDrop<F> {
+    fn drop(&mut self) {
+        (self.0)()
+    }
+}
+
+/// A task-local value registered in a task, linked from [`TaskLocals`].
+struct Entry {
+    key: *const (),
+    value: *const (),
+    next: Cell<Option<EntryRef>>,
+}
+
+// Safety: `Entry` is only accessed in a critical section once registered. It is
+// `Send` so that `TaskLocalFuture` is `Send` if the value and the future are.
+unsafe impl Send for Entry {}
+
+#[derive(Clone, Copy, PartialEq)]
+struct EntryRef(NonNull<Entry>);
+
+// Safety: entries are only accessed in a critical section.
+unsafe impl Send for EntryRef {}
+
+/// The task-local values registered in a task, in its [`Metadata`](crate::Metadata).
+pub(crate) struct TaskLocals {
+    head: Mutex<Cell<Option<EntryRef>>>,
+}
+
+impl TaskLocals {
+    pub(crate) const fn new() -> Self {
+        Self {
+            head: Mutex::new(Cell::new(None)),
+        }
+    }
+
+    /// # Safety
+    ///
+    /// `entry` must stay valid until it is removed.
+    unsafe fn push(&self, entry: NonNull<Entry>) {
+        critical_section::with(|cs| {
+            let head = self.head.borrow(cs);
+            entry.as_ref().next.set(head.get());
+            head.set(Some(EntryRef(entry)));
+        })
+    }
+
+    fn remove(&self, entry: NonNull<Entry>) {
+        critical_section::with(|cs| {
+            let mut link = self.head.borrow(cs);
+            // Safety: entries are valid while registered.
+            while let Some(e) = link.get() {
+                let next = unsafe { &e.0.as_ref().next };
+                if e.0 == entry {
+                    link.set(next.get());
+                    return;
+                }
+                link = next;
+            }
+        })
+    }
+
+    /// Find the most recently registered value of `key`.
+    fn find(&self, cs: CriticalSection, key: *const ()) -> Option<*const ()> {
+        let mut entry = self.head.borrow(cs).get();
+        while let Some(e) = entry {
+            // Safety: entries are valid while registered.
+            let e = unsafe { e.0.as_ref() };
+            if e.key == key {
+                return Some(e.value);
+            }
+            entry = e.next.get();
+        }
+        None
+    }
+}
@@ -0,0 +1,263 @@
+//! Slow-poll detection and watchdog integration.
+//!
+//! A task that busy-loops, or blocks for a long time inside `poll` (e.g. a long blocking SPI
+//! transfer), stalls every other task on its executor. With the `watchdog` feature, executors
+//! measure every poll using the `embassy-time-driver`, and report polls longer than a threshold:
+//!
+//! ```rust,ignore
+//! use embassy_executor::watchdog;
+//!
+//! // Report polls longer than 10ms.
+//! watchdog::set_threshold(embassy_time::Duration::from_millis(10).as_ticks());
+//! watchdog::set_slow_poll_hook(|poll| {
+//!     warn!("task {} blocked the executor for {} ticks", poll.name.unwrap_or("unnamed"), poll.ticks);
+//! });
+//!
+//! // Only feed the hardware watchdog while no task is blocking its executor.
+//! watchdog::set_feed(|_executor_id| hardware_watchdog.feed());
+//!
+//! // In a task that must run at least once a second, stop feeding if it doesn't.
+//! Metadata::for_current_task().await.set_progress_timeout(Some(embassy_time::Duration::from_secs(1).as_ticks()));
+//! ```
+//!
+//! Without a hook, slow polls are logged with `defmt` or `log`, if enabled.
+//!
+//! The feed function is called each time an executor finishes a round of polling, if none of
+//! the tasks polled in the round was slow, and every task of the executor with a
+//! [progress timeout](crate::Metadata::set_progress_timeout) has been polled within its timeout.
+//! A task stuck in a poll stops the feeding, and so does a watched task that is never woken, or
+//! woken but never polled. Tasks without a progress timeout, which may legitimately wait forever
+//! (e.g. for a button press), don't stop the feeding by not running.
+//!
+//! Note that an idle executor doesn't poll, so at least one task should wake up periodically
+//! (e.g. with a timer) to keep the watchdog fed. With multiple executors, each calls the feed
+//! function with its ID (see [`Spawner::executor_id()`](crate::Spawner::executor_id)), so that it
+//! can check all executors are making progress before feeding.
+//!
+//! The configuration is shared by all executors.
+
+use core::cell::Cell;
+use core::sync::atomic::Ordering;
+
+use critical_section::Mutex;
+
+use crate::raw::TaskRef;
+use crate::raw::trace::TASK_TRACKER;
+
+/// A poll that took longer than the [threshold](set_threshold), passed to the [slow-poll hook](set_slow_poll_hook).
+#[derive(Debug, Clone, Copy)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct SlowPoll {
+    /// ID of the executor that polled the task. See [`Spawner::executor_id()`](crate::Spawner::executor_id).
+    pub executor_id: usize,
+    /// The task's ID. See [`SpawnToken::id()`](crate::SpawnToken::id).
+    pub task_id: u32,
+    /// The task's name, see [`Metadata::name()`](crate::Metadata::name).
+    pub name: Option<&'static str>,
+    /// Duration of the poll, in ticks.
+    pub ticks: u64,
+}
+
+#[derive(Clone, Copy)]
+struct Config {
+    threshold: u64,
+    hook: Option<fn(&SlowPoll)>,
+    feed: Option<fn(usize)>,
+}
+
+static CONFIG: Mutex<Cell<Config>> = Mutex::new(Cell::new(Config {
+    threshold: u64::MAX,
+    hook: None,
+    feed: None,
+}));
+
+fn update(f: impl FnOnce(&mut Config)) {
+    critical_section::with(|cs| {
+        let cell = CONFIG.borrow(cs);
+        let mut config = cell.get();
+        f(&mut config);
+        cell.set(config);
+    })
+}
+
+fn config() -> Config {
+    critical_section::with(|cs| CONFIG.borrow(cs).get())
+}
+
+/// Set the longest duration of a poll, in ticks, before it is reported as slow.
+///
+/// Defaults to `u64::MAX`, which disables slow-poll reporting.
+pub fn set_threshold(ticks: u64) {
+    update(|c| c.threshold = ticks)
+}
+
+/// Set the function called when a poll takes longer than the [threshold](set_threshold).
+///
+/// It is called from the executor, right after the slow poll.
+pub fn set_slow_poll_hook(hook: fn(&SlowPoll)) {
+    update(|c| c.hook = Some(hook))
+}
+
+/// Set the function feeding the watchdog.
+///
+/// It is called with the executor's ID each time an executor finishes a round of polling
+/// in which no poll was slow, if all its tasks with a progress timeout are making progress.
+pub fn set_feed(feed: fn(usize)) {
+    update(|c| c.feed = Some(feed))
+}
+
+#[derive(Clone, Copy)]
+struct State {
+    /// When the executor started polling the current task.
+    task_start: u64,
+    /// Whether a poll of the current round was slow.
+    slow: bool,
+}
+
+/// Per-executor watchdog state.
+pub(crate) struct ExecutorWatchdog {
+    state: Mutex<Cell<State>>,
+}
+
+impl ExecutorWatchdog {
+    pub(crate) const fn new() -> Self {
+        Self {
+            state: Mutex::new(Cell::new(State {
+                task_start: 0,
+                slow: false,
+            })),
+        }
+    }
+
+    pub(crate) fn task_exec_begin(&self) {
+        let now = embassy_time_driver::now();
+        critical_section::with(|cs| {
+            let cell = self.state.borrow(cs);
+            cell.set(State {
+                task_start: now,
+                ..cell.get()
+            })
+        })
+    }
+
+    pub(crate) fn task_exec_end(&self, executor_id: usize, task: &TaskRef) {
+        let now = embassy_time_driver::now();
+        let config = config();
+        let ticks = critical_section::with(|cs| {
+            let cell = self.state.borrow(cs);
+            let mut state = cell.get();
+            let ticks = now.saturating_sub(state.task_start);
+            state.slow |= ticks > config.threshold;
+            cell.set(state);
+            ticks
+        });
+        task.metadata().watchdog.polled();
+        if ticks <= config.threshold {
+            return;
+        }
+
+        let poll = SlowPoll {
+            executor_id,
+            task_id: task.id(),
+            name: task.metadata().name(),
+            ticks,
+        };
+        match config.hook {
+            Some(hook) => hook(&poll),
+            None => warn!(
+                "task {} ({}) was polled for {} ticks",
+                poll.name.unwrap_or("unnamed"),
+                poll.task_id,
+                poll.ticks
+            ),
+        }
+    }
+
+    pub(crate) fn executor_idle(&self, executor_id: usize) {
+        let slow = critical_section::with(|cs| {
+            let cell = self.state.borrow(cs);
+            let state = cell.get();
+            cell.set(State { slow: false, ..state });
+            state.slow
+        });
+        if slow || self.tasks_stalled(executor_id) {
+            return;
+        }
+        if let Some(feed) = config().feed {
+            feed(executor_id);
+        }
+    }
+
+    /// Whether a task of the executor with a progress timeout hasn't been polled within it.
+    fn tasks_stalled(&self, executor_id: usize) -> bool {
+        let now = embassy_time_driver::now();
+        TASK_TRACKER.iter().any(|task| {
+            let header = task.header();
+            header.state.is_spawned()
+                && header.executor.load(Ordering::Relaxed) as usize == executor_id
+                && task.metadata().watchdog.stalled(now)
+        })
+    }
+}
+
+#[derive(Clone, Copy)]
+struct Progress {
+    /// The longest time the task may go without being polled, if it is watched.
+    timeout: Option<u64>,
+    /// When the task was last polled, or started being watched.
+    last_poll: u64,
+}
+
+/// Per-task watchdog state.
+pub(crate) struct TaskWatchdog {
+    progress: Mutex<Cell<Progress>>,
+}
+
+impl TaskWatchdog {
+    pub(crate) const fn new() -> Self {
+        Self {
+            progress: Mutex::new(Cell::new(Progress {
+                timeout: None,
+                last_poll: 0,
+            })),
+        }
+    }
+
+    /// Stop watching the task.
+    pub(crate) fn reset(&self) {
+        critical_section::with(|cs| {
+            self.progress.borrow(cs).set(Progress {
+                timeout: None,
+                last_poll: 0,
+            })
+        })
+    }
+
+    pub(crate) fn set_timeout(&self, timeout: Option<u64>) {
+        let now = embassy_time_driver::now();
+        critical_section::with(|cs| {
+            self.progress.borrow(cs).set(Progress {
+                timeout,
+                last_poll: now,
+            })
+        })
+    }
+
+    pub(crate) fn polled(&self) {
+        let now = embassy_time_driver::now();
+        critical_section::with(|cs| {
+            let cell = self.progress.borrow(cs);
+            cell.set(Progress {
+                last_poll: now,
+                ..cell.get()
+            })
+        })
+    }
+
+    fn stalled(&self, now: u64) -> bool {
+        let progress = critical_section::with(|cs| self.progress.borrow(cs).get());
+        progress
+            .timeout
+            .is_some_and(|timeout| now.saturating_sub(progress.last_poll) > timeout)
+    }
+}
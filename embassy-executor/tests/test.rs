@@ -37,6 +37,13 @@ impl Trace {
     }
 }
 
+/// Serializes tests advancing the global mock time driver.
+#[cfg(any(feature = "stats", feature = "watchdog"))]
+fn lock_mock_time() -> std::sync::MutexGuard<'static, ()> {
+    static LOCK: Mutex<()> = Mutex::new(());
+    LOCK.lock().unwrap_or_else(|e| e.into_inner())
+}
+
 fn setup() -> (&'static Executor, Trace) {
     let trace = Trace::new();
     let context = Box::leak(Box::new(trace.clone())) as *mut _ as *mut ();
@@ -356,3 +363,668 @@ fn task_metadata() {
     executor.spawner().spawn(task1(None).unwrap());
     unsafe { executor.poll() };
 }
+
+#[cfg(feature = "join-handle")]
+mod join_handle {
+    use std::pin::{Pin, pin};
+    use std::task::{Context, Waker};
+
+    use embassy_executor::raw::{JoinError, TaskPool};
+
+    use super::*;
+
+    type BoxFuture<T> = Pin<Box<dyn Future<Output = T>>>;
+
+    struct DropGuard(Trace, &'static str);
+
+    impl Drop for DropGuard {
+        fn drop(&mut self) {
+            self.0.push(self.1)
+        }
+    }
+
+    fn poll_once<F: Future>(f: Pin<&mut F>) -> Poll<F::Output> {
+        f.poll(&mut Context::from_waker(Waker::noop()))
+    }
+
+    /// Check whether the pool has a free slot, by spawning a pending task on a scratch executor.
+    fn can_spawn<T: 'static>(pool: &'static TaskPool<BoxFuture<T>, 1>) -> bool {
+        match pool.spawn_joinable(|| Box::pin(std::future::pending())) {
+            Ok((token, _handle)) => {
+                setup().0.spawner().spawn(token);
+                true
+            }
+            Err(_) => false,
+        }
+    }
+
+    #[test]
+    fn join() {
+        let pool: &'static TaskPool<BoxFuture<u32>, 1> = Box::leak(Box::new(TaskPool::new()));
+        let (executor, trace) = setup();
+
+        let t = trace.clone();
+        let (token, handle) = pool
+            .spawn_joinable(move || {
+                Box::pin(async move {
+                    let mut yielded = false;
+                    poll_fn(|cx| {
+                        t.push("poll task1");
+                        if yielded {
+                            return Poll::Ready(42);
+                        }
+                        yielded = true;
+                        cx.waker().wake_by_ref();
+                        Poll::Pending
+                    })
+                    .await
+                })
+            })
+            .unwrap();
+        executor.spawner().spawn(token);
+
+        let mut join = pin!(handle.join());
+        assert_eq!(poll_once(join.as_mut()), Poll::Pending);
+
+        unsafe { executor.poll() };
+        unsafe { executor.poll() };
+
+        // The slot stays reserved until the output has been taken.
+        assert!(!can_spawn(pool));
+        assert_eq!(poll_once(join.as_mut()), Poll::Ready(Ok(42)));
+        assert!(can_spawn(pool));
+
+        assert_eq!(
+            trace.get(),
+            &[
+                "pend",       // spawning a task pends the executor
+                "poll task1", //
+                "pend",       // task self-wakes
+                "poll task1", // task completes
+            ]
+        )
+    }
+
+    #[test]
+    fn cancel() {
+        let pool: &'static TaskPool<BoxFuture<u32>, 1> = Box::leak(Box::new(TaskPool::new()));
+        let (executor, trace) = setup();
+
+        let t = trace.clone();
+        let (token, handle) = pool
+            .spawn_joinable(move || {
+                Box::pin(async move {
+                    let _guard = DropGuard(t.clone(), "drop task1");
+                    poll_fn(|_| {
+                        t.push("poll task1");
+                        Poll::Pending
+                    })
+                    .await
+                })
+            })
+            .unwrap();
+        executor.spawner().spawn(token);
+        unsafe { executor.poll() };
+
+        handle.cancel();
+        assert!(!handle.is_finished());
+        unsafe { executor.poll() };
+        assert!(handle.is_finished());
+
+        // Cancelling an exited task does nothing.
+        handle.cancel();
+        unsafe { executor.poll() };
+
+        assert_eq!(poll_once(pin!(handle.join())), Poll::Ready(Err(JoinError::Cancelled)));
+        assert!(can_spawn(pool));
+
+        assert_eq!(
+            trace.get(),
+            &[
+                "pend",       // spawning a task pends the executor
+                "poll task1", //
+                "pend",       // cancelling wakes the task
+                "drop task1", // future is dropped without being polled
+            ]
+        )
+    }
+
+    #[test]
+    fn drop_handle() {
+        let pool: &'static TaskPool<BoxFuture<DropGuard>, 1> = Box::leak(Box::new(TaskPool::new()));
+        let (executor, trace) = setup();
+
+        // Dropping the handle before the task exits doesn't cancel it, and frees the slot on exit.
+        let t = trace.clone();
+        let (token, handle) = pool
+            .spawn_joinable(move || Box::pin(async move { DropGuard(t, "drop output") }))
+            .unwrap();
+        executor.spawner().spawn(token);
+        drop(handle);
+        unsafe { executor.poll() };
+        assert!(can_spawn(pool));
+
+        // Dropping the handle after the task exits drops the output and frees the slot.
+        let pool: &'static TaskPool<BoxFuture<DropGuard>, 1> = Box::leak(Box::new(TaskPool::new()));
+        let t = trace.clone();
+        let (token, handle) = pool
+            .spawn_joinable(move || Box::pin(async move { DropGuard(t, "drop output") }))
+            .unwrap();
+        executor.spawner().spawn(token);
+        unsafe { executor.poll() };
+        assert!(handle.is_finished());
+        drop(handle);
+        assert!(can_spawn(pool));
+
+        assert_eq!(
+            trace.get(),
+            &[
+                "pend",        // spawning a task pends the executor
+                "drop output", // output is dropped by the task, nobody wants it
+                "pend",        // spawning a task pends the executor
+                "drop output", // output is dropped with the handle
+            ]
+        )
+    }
+}
+
+#[cfg(feature = "stats")]
+#[test]
+fn task_stats() {
+    use embassy_executor::stats;
+    use embassy_time::{Duration, MockDriver};
+
+    #[task]
+    async fn task1() {
+        // Take 3 ticks, yield, then take 5 ticks.
+        MockDriver::get().advance(Duration::from_ticks(3));
+        let mut yielded = false;
+        poll_fn(|cx| {
+            if yielded {
+                return Poll::Ready(());
+            }
+            yielded = true;
+            cx.waker().wake_by_ref();
+            Poll::Pending
+        })
+        .await;
+        MockDriver::get().advance(Duration::from_ticks(5));
+    }
+
+    let _lock = lock_mock_time();
+    let (executor, _) = setup();
+    let token = task1().unwrap();
+    let id = token.id();
+    token.metadata().set_name("task1");
+    executor.spawner().spawn(token);
+
+    unsafe { executor.poll() };
+    MockDriver::get().advance(Duration::from_ticks(10));
+    unsafe { executor.poll() };
+
+    let mut found = None;
+    stats::for_each_task(|task| {
+        if task.id == id {
+            assert!(found.is_none(), "task listed twice");
+            found = Some(task);
+        }
+    });
+    let task = found.unwrap();
+    assert_eq!(task.name, Some("task1"));
+    assert_eq!(
+        task.stats,
+        stats::TaskStats {
+            polls: 2,
+            total_ticks: 8,
+            max_ticks: 5,
+        }
+    );
+
+    let executor_stats = executor.stats();
+    assert_eq!(executor_stats.busy_ticks, 8);
+    assert_eq!(executor_stats.idle_ticks, 10);
+    assert_eq!(executor_stats.load_percent(), 44);
+
+    // Respawning resets the statistics, and doesn't list the task twice.
+    executor.spawner().spawn(task1().unwrap());
+    unsafe { executor.poll() };
+    let mut count = 0;
+    stats::for_each_task(|task| {
+        if task.id == id {
+            count += 1;
+            assert_eq!(task.stats.polls, 1);
+        }
+    });
+    assert_eq!(count, 1);
+}
+
+#[cfg(feature = "metadata-size")]
+#[test]
+fn task_memory() {
+    use embassy_executor::memory;
+
+    #[task(pool_size = 3, size_warning = 65536)]
+    async fn task1() {
+        let buf = [0u8; 256];
+        poll_fn(|_| Poll::<()>::Pending).await;
+        core::hint::black_box(buf);
+    }
+
+    let (executor, _) = setup();
+    let token = task1().unwrap();
+    let id = token.id();
+    assert!(token.metadata().size() > 256);
+    executor.spawner().spawn(token);
+    executor.spawner().spawn(task1().unwrap());
+    unsafe { executor.poll() };
+
+    let task = memory::tasks().find(|t| t.id == id).unwrap();
+    assert!(task.spawned);
+    assert_eq!(task.pool_capacity, 3);
+    assert_eq!(task.pool_spawned, 2);
+    // The never-spawned slot of the pool isn't listed.
+    assert_eq!(memory::tasks().filter(|t| t.pool_id == task.pool_id).count(), 2);
+}
+
+#[cfg(feature = "executor-shared")]
+#[test]
+fn shared_executor() {
+    use embassy_executor::raw::SharedExecutor;
+
+    static SHARED: SharedExecutor = SharedExecutor::new();
+
+    /// Polls `worker` from inside its first poll, after waking itself.
+    #[task]
+    async fn task1(trace: Trace, worker: usize) {
+        let mut polls = 0;
+        poll_fn(|cx| {
+            polls += 1;
+            if polls > 1 {
+                trace.push("poll task1 again");
+                return Poll::Ready(());
+            }
+
+            trace.push("poll task1");
+            cx.waker().wake_by_ref();
+            // Safety: `worker` is a leaked executor, not polled anywhere else.
+            unsafe { (*(worker as *const Executor)).poll() };
+            trace.push("poll task1 end");
+            Poll::Pending
+        })
+        .await
+    }
+
+    #[task]
+    async fn task2(trace: Trace) {
+        trace.push("poll task2")
+    }
+
+    let trace = Trace::new();
+    let context = Box::leak(Box::new(trace.clone())) as *mut _ as *mut ();
+    let worker1 = &*Box::leak(Box::new(Executor::new(context)));
+    let worker2 = &*Box::leak(Box::new(Executor::new(context)));
+    worker1.attach_shared(&SHARED);
+    worker2.attach_shared(&SHARED);
+
+    let spawner = SHARED.spawner();
+    spawner.spawn(task1(trace.clone(), worker2 as *const Executor as usize).unwrap());
+    spawner.spawn(task2(trace.clone()).unwrap());
+
+    unsafe { worker1.poll() };
+
+    assert_eq!(
+        trace.get(),
+        &[
+            "pend",             // attaching worker1 pends it
+            "pend",             // attaching worker2 pends it
+            "pend",             // spawning a shared task pends all workers
+            "pend",             //
+            "poll task1",       // worker1 takes task1...
+            "pend",             // ...which wakes itself, pending all workers
+            "pend",             //
+            "poll task2",       // worker2 takes task2, but not task1 which worker1 is polling
+            "poll task1 end",   //
+            "pend",             // task1 is ready again now that worker1 is done with it
+            "pend",             //
+            "poll task1 again", // worker1 polls it in the same round
+        ]
+    );
+
+    unsafe { worker2.poll() };
+    assert_eq!(trace.get().len(), 12);
+}
+
+#[cfg(feature = "coop")]
+#[test]
+fn coop_budget() {
+    use std::task::Waker;
+
+    // Provided by the executor, used by e.g. `embassy_sync::coop::poll_proceed`.
+    unsafe extern "Rust" {
+        fn __embassy_coop_poll_proceed(waker: &Waker) -> bool;
+    }
+
+    /// Do 5 operations, yielding whenever the budget is exhausted.
+    #[task]
+    async fn task1(trace: Trace) {
+        let mut ops = 0;
+        poll_fn(|cx| {
+            while ops < 5 {
+                if !unsafe { __embassy_coop_poll_proceed(cx.waker()) } {
+                    trace.push("yield");
+                    cx.waker().wake_by_ref();
+                    return Poll::Pending;
+                }
+                trace.push("op");
+                ops += 1;
+            }
+            Poll::Ready(())
+        })
+        .await
+    }
+
+    let (executor, trace) = setup();
+    executor.set_coop_budget(Some(2));
+    executor.spawner().spawn(task1(trace.clone()).unwrap());
+    for _ in 0..3 {
+        unsafe { executor.poll() };
+    }
+
+    assert_eq!(
+        trace.get(),
+        &[
+            "pend", // spawning a task pends the executor
+            "op", "op", "yield", "pend", // budget of 2 per poll
+            "op", "op", "yield", "pend", //
+            "op",
+        ]
+    );
+}
+
+#[cfg(feature = "coop")]
+#[test]
+fn coop_budget_refunded_when_pending() {
+    use std::task::Waker;
+
+    unsafe extern "Rust" {
+        fn __embassy_coop_poll_proceed(waker: &Waker) -> bool;
+        fn __embassy_coop_refund(waker: &Waker);
+    }
+
+    /// Check 5 operations that aren't ready, like a `select` of pending futures would, then do 2.
+    #[task]
+    async fn task1(trace: Trace) {
+        poll_fn(|cx| {
+            for _ in 0..5 {
+                if !unsafe { __embassy_coop_poll_proceed(cx.waker()) } {
+                    trace.push("yield");
+                    cx.waker().wake_by_ref();
+                    return Poll::Pending;
+                }
+                // Not ready, give the budget back.
+                unsafe { __embassy_coop_refund(cx.waker()) };
+            }
+            Poll::Ready(())
+        })
+        .await;
+        for _ in 0..2 {
+            poll_fn(|cx| {
+                if !unsafe { __embassy_coop_poll_proceed(cx.waker()) } {
+                    trace.push("yield");
+                    cx.waker().wake_by_ref();
+                    return Poll::Pending;
+                }
+                trace.push("op");
+                Poll::Ready(())
+            })
+            .await;
+        }
+    }
+
+    let (executor, trace) = setup();
+    executor.set_coop_budget(Some(2));
+    executor.spawner().spawn(task1(trace.clone()).unwrap());
+    unsafe { executor.poll() };
+
+    assert_eq!(
+        trace.get(),
+        &[
+            "pend", // spawning a task pends the executor
+            "op", "op", // pending operations didn't use the budget
+        ]
+    );
+}
+
+#[cfg(feature = "task-arena")]
+mod task_arena {
+    use std::mem::MaybeUninit;
+
+    use embassy_executor::SpawnError;
+    use embassy_executor::raw::TaskArena;
+    use embassy_sync::blocking_mutex::raw::CriticalSectionRawMutex;
+    use embassy_sync::channel::Channel;
+
+    use super::*;
+
+    type Done = Channel<CriticalSectionRawMutex, (), 16>;
+
+    fn memory(len: usize) -> &'static mut [MaybeUninit<u8>] {
+        Box::leak(vec![MaybeUninit::uninit(); len].into_boxed_slice())
+    }
+
+    #[test]
+    fn reuses_memory_of_finished_tasks() {
+        static DONE: Done = Channel::new();
+        let arena: &'static TaskArena = Box::leak(Box::new(TaskArena::new()));
+        let (executor, trace) = setup();
+
+        // An arena without memory can't spawn.
+        assert!(matches!(arena.spawn(|| async {}), Err(SpawnError::ArenaFull)));
+
+        arena.add_memory(memory(4096));
+        let mut spawned = 0;
+        loop {
+            let t = trace.clone();
+            match arena.spawn(move || async move {
+                DONE.receive().await;
+                t.push("done");
+            }) {
+                Ok(token) => executor.spawner().spawn(token),
+                Err(e) => {
+                    assert!(matches!(e, SpawnError::ArenaFull));
+                    break;
+                }
+            }
+            spawned += 1;
+        }
+        assert!(spawned > 1);
+        unsafe { executor.poll() };
+
+        // Finish one task, its memory can be used by the next one.
+        DONE.try_send(()).unwrap();
+        unsafe { executor.poll() };
+        assert_eq!(trace.get().iter().filter(|&&t| t == "done").count(), 1);
+        executor.spawner().spawn(arena.spawn(|| async {}).unwrap());
+        assert!(matches!(arena.spawn(|| async {}), Err(SpawnError::ArenaFull)));
+
+        // A smaller task can use the memory of a bigger one.
+        unsafe { executor.poll() };
+        executor.spawner().spawn(arena.spawn(|| async {}).unwrap());
+    }
+
+    #[test]
+    fn spawns_tasks_of_different_types() {
+        let arena: &'static TaskArena = Box::leak(Box::new(TaskArena::new()));
+        arena.add_memory(memory(8192));
+        let (executor, trace) = setup();
+
+        let t = trace.clone();
+        executor
+            .spawner()
+            .spawn(arena.spawn(move || async move { t.push("small") }).unwrap());
+        let t = trace.clone();
+        executor.spawner().spawn(
+            arena
+                .spawn(move || async move {
+                    let buf = [1u8; 256];
+                    poll_fn(|_| Poll::Ready(())).await;
+                    t.push(if buf.iter().all(|&b| b == 1) {
+                        "big"
+                    } else {
+                        "corrupted"
+                    });
+                })
+                .unwrap(),
+        );
+        unsafe { executor.poll() };
+
+        assert_eq!(trace.get(), &["pend", "big", "small"]);
+    }
+
+    #[cfg(feature = "alloc")]
+    #[test]
+    fn grows_from_the_heap() {
+        let arena: &'static TaskArena = Box::leak(Box::new(TaskArena::with_heap(1024)));
+        let (executor, _trace) = setup();
+        for _ in 0..64 {
+            executor
+                .spawner()
+                .spawn(arena.spawn(std::future::pending::<()>).unwrap());
+        }
+        unsafe { executor.poll() };
+    }
+}
+
+#[cfg(feature = "watchdog")]
+#[test]
+fn watchdog() {
+    use embassy_executor::watchdog::{self, SlowPoll};
+    use embassy_time::{Duration, MockDriver};
+
+    // Polls take 2, 10 and 2 ticks.
+    #[task]
+    async fn task1() {
+        for ticks in [2, 10, 2] {
+            MockDriver::get().advance(Duration::from_ticks(ticks));
+            let mut yielded = false;
+            poll_fn(|cx| {
+                if yielded {
+                    return Poll::Ready(());
+                }
+                yielded = true;
+                cx.waker().wake_by_ref();
+                Poll::Pending
+            })
+            .await;
+        }
+    }
+
+    static SLOW_POLLS: Mutex<Vec<SlowPoll>> = Mutex::new(Vec::new());
+    static FEEDS: Mutex<Vec<usize>> = Mutex::new(Vec::new());
+
+    let _lock = lock_mock_time();
+    watchdog::set_threshold(4);
+    watchdog::set_slow_poll_hook(|poll| SLOW_POLLS.lock().unwrap().push(*poll));
+    watchdog::set_feed(|executor_id| FEEDS.lock().unwrap().push(executor_id));
+
+    let (executor, _) = setup();
+    let token = task1().unwrap();
+    token.metadata().set_name("task1");
+    executor.spawner().spawn(token);
+
+    let feeds = || FEEDS.lock().unwrap().iter().filter(|&&id| id == executor.id()).count();
+
+    unsafe { executor.poll() };
+    assert_eq!(feeds(), 1);
+
+    // The slow poll is reported, and the watchdog isn't fed.
+    unsafe { executor.poll() };
+    assert_eq!(feeds(), 1);
+    let slow_polls: Vec<_> = SLOW_POLLS
+        .lock()
+        .unwrap()
+        .iter()
+        .filter(|p| p.executor_id == executor.id())
+        .copied()
+        .collect();
+    assert_eq!(slow_polls.len(), 1);
+    assert_eq!(slow_polls[0].name, Some("task1"));
+    assert_eq!(slow_polls[0].ticks, 10);
+
+    unsafe { executor.poll() };
+    assert_eq!(feeds(), 2);
+}
+
+#[cfg(feature = "watchdog")]
+#[test]
+fn watchdog_progress() {
+    use std::task::Waker;
+
+    use embassy_executor::watchdog;
+    use embassy_time::{Duration, MockDriver};
+
+    static WAKER: Mutex<Option<Waker>> = Mutex::new(None);
+    static FEEDS: Mutex<Vec<usize>> = Mutex::new(Vec::new());
+
+    // Keeps the executor polling.
+    #[task]
+    async fn busy() {
+        loop {
+            let mut yielded = false;
+            poll_fn(|cx| {
+                if yielded {
+                    return Poll::Ready(());
+                }
+                yielded = true;
+                cx.waker().wake_by_ref();
+                Poll::Pending
+            })
+            .await;
+        }
+    }
+
+    // Only runs when woken through `WAKER`.
+    #[task]
+    async fn watched() {
+        loop {
+            let mut waited = false;
+            poll_fn(|cx| {
+                if waited {
+                    return Poll::Ready(());
+                }
+                waited = true;
+                *WAKER.lock().unwrap() = Some(cx.waker().clone());
+                Poll::Pending
+            })
+            .await;
+        }
+    }
+
+    let _lock = lock_mock_time();
+    watchdog::set_feed(|executor_id| FEEDS.lock().unwrap().push(executor_id));
+
+    let (executor, _) = setup();
+    executor.spawner().spawn(busy().unwrap());
+    let token = watched().unwrap();
+    token.metadata().set_progress_timeout(Some(5));
+    executor.spawner().spawn(token);
+
+    let feeds = || FEEDS.lock().unwrap().iter().filter(|&&id| id == executor.id()).count();
+
+    unsafe { executor.poll() };
+    assert_eq!(feeds(), 1);
+
+    // The watched task isn't woken, so the watchdog stops being fed once its timeout has elapsed,
+    // even though the other task keeps running.
+    MockDriver::get().advance(Duration::from_ticks(3));
+    unsafe { executor.poll() };
+    assert_eq!(feeds(), 2);
+    MockDriver::get().advance(Duration::from_ticks(3));
+    unsafe { executor.poll() };
+    assert_eq!(feeds(), 2);
+
+    // Feeding resumes once it runs again.
+    WAKER.lock().unwrap().take().unwrap().wake();
+    unsafe { executor.poll() };
+    assert_eq!(feeds(), 3);
+}
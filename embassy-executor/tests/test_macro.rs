@@ -0,0 +1,68 @@
+#![cfg(all(feature = "arch-std", feature = "executor-thread"))]
+
+use std::sync::atomic::{AtomicU32, Ordering};
+
+use embassy_executor::Spawner;
+use embassy_sync::blocking_mutex::raw::CriticalSectionRawMutex;
+use embassy_sync::channel::Channel;
+use embassy_time::{Duration, Instant, Timer, with_timeout};
+
+#[embassy_executor::test]
+async fn without_spawner() {
+    let channel = Channel::<CriticalSectionRawMutex, u32, 1>::new();
+    channel.send(1).await;
+    assert_eq!(channel.receive().await, 1);
+}
+
+static REQUESTS: Channel<CriticalSectionRawMutex, u32, 1> = Channel::new();
+static RESPONSES: Channel<CriticalSectionRawMutex, u32, 1> = Channel::new();
+
+#[embassy_executor::task]
+async fn increment() {
+    let value = REQUESTS.receive().await;
+    RESPONSES.send(value + 1).await;
+}
+
+#[embassy_executor::test]
+async fn spawns_helper_tasks(spawner: Spawner) {
+    spawner.spawn(increment().unwrap());
+    REQUESTS.send(1).await;
+    assert_eq!(RESPONSES.receive().await, 2);
+}
+
+#[embassy_executor::test]
+#[should_panic(expected = "inside the test")]
+async fn panics_fail_the_test() {
+    panic!("inside the test");
+}
+
+#[embassy_executor::test(mock_time)]
+async fn mock_time_starts_at_zero() {
+    assert_eq!(Instant::now(), Instant::from_ticks(0));
+    Timer::after(Duration::from_secs(3600)).await;
+    assert_eq!(Instant::now(), Instant::from_ticks(0) + Duration::from_secs(3600));
+}
+
+#[embassy_executor::test(mock_time)]
+async fn mock_time_timeout() {
+    let result = with_timeout(Duration::from_secs(60), core::future::pending::<()>()).await;
+    assert!(result.is_err());
+    assert_eq!(Instant::now().as_secs(), 60);
+}
+
+static TICKS: AtomicU32 = AtomicU32::new(0);
+
+#[embassy_executor::task]
+async fn ticker() {
+    loop {
+        Timer::after(Duration::from_millis(10)).await;
+        TICKS.fetch_add(1, Ordering::Relaxed);
+    }
+}
+
+#[embassy_executor::test(mock_time)]
+async fn mock_time_advances_to_earliest_timer(spawner: Spawner) {
+    spawner.spawn(ticker().unwrap());
+    Timer::after(Duration::from_millis(55)).await;
+    assert_eq!(TICKS.load(Ordering::Relaxed), 5);
+}
@@ -0,0 +1,73 @@
+#![cfg(all(feature = "arch-std", feature = "executor-thread", feature = "reactor-epoll"))]
+
+use std::io::{Read as _, Write as _};
+use std::os::unix::net::UnixStream;
+use std::thread;
+use std::time::Duration;
+
+use embassy_executor::reactor::Async;
+use embedded_io_async::{Read, Write};
+
+#[embassy_executor::test]
+async fn reads_data_written_by_another_thread() {
+    let (a, mut b) = UnixStream::pair().unwrap();
+    let mut a = Async::new(a).unwrap();
+
+    let writer = thread::spawn(move || {
+        for chunk in [b"hello ".as_slice(), b"world"] {
+            thread::sleep(Duration::from_millis(10));
+            b.write_all(chunk).unwrap();
+        }
+    });
+
+    let mut buf = [0; 11];
+    a.read_exact(&mut buf).await.unwrap();
+    assert_eq!(&buf, b"hello world");
+    writer.join().unwrap();
+}
+
+#[embassy_executor::test]
+async fn write_waits_for_the_peer_to_read() {
+    let (a, mut b) = UnixStream::pair().unwrap();
+    let mut a = Async::new(a).unwrap();
+
+    // Much more than the socket buffer, so that writing would block.
+    const LEN: usize = 4 * 1024 * 1024;
+    let reader = thread::spawn(move || {
+        let mut buf = vec![0; LEN];
+        b.read_exact(&mut buf).unwrap();
+        buf
+    });
+
+    let data: Vec<u8> = (0..LEN).map(|i| i as u8).collect();
+    a.write_all(&data).await.unwrap();
+    assert_eq!(reader.join().unwrap(), data);
+}
+
+#[embassy_executor::test]
+async fn read_returns_zero_at_eof() {
+    let (a, b) = UnixStream::pair().unwrap();
+    let mut a = Async::new(a).unwrap();
+
+    let closer = thread::spawn(move || {
+        thread::sleep(Duration::from_millis(10));
+        drop(b);
+    });
+
+    let mut buf = [0; 8];
+    assert_eq!(a.read(&mut buf).await.unwrap(), 0);
+    closer.join().unwrap();
+}
+
+#[embassy_executor::test]
+async fn into_inner_keeps_the_io_object() {
+    let (a, mut b) = UnixStream::pair().unwrap();
+    let a = Async::new(a).unwrap();
+    b.write_all(b"x").unwrap();
+    a.readable().await.unwrap();
+
+    let mut a = a.into_inner();
+    let mut buf = [0; 1];
+    a.read_exact(&mut buf).unwrap();
+    assert_eq!(&buf, b"x");
+}
@@ -0,0 +1,81 @@
+#![cfg(all(feature = "arch-std", feature = "executor-thread", feature = "executor-shared"))]
+
+use std::collections::HashSet;
+use std::future::poll_fn;
+use std::sync::Mutex;
+use std::sync::mpsc::{Sender, channel};
+use std::task::Poll;
+use std::thread::{self, ThreadId};
+use std::time::Duration;
+
+use embassy_executor::Executor;
+use embassy_executor::raw::SharedExecutor;
+
+static SHARED: SharedExecutor = SharedExecutor::new();
+static THREADS: Mutex<Option<HashSet<ThreadId>>> = Mutex::new(None);
+
+async fn yield_now() {
+    let mut yielded = false;
+    poll_fn(|cx| {
+        if yielded {
+            Poll::Ready(())
+        } else {
+            yielded = true;
+            cx.waker().wake_by_ref();
+            Poll::Pending
+        }
+    })
+    .await
+}
+
+#[embassy_executor::task(pool_size = 8)]
+async fn work(done: Sender<()>) {
+    for _ in 0..5 {
+        THREADS
+            .lock()
+            .unwrap()
+            .get_or_insert_with(HashSet::new)
+            .insert(thread::current().id());
+        // Block the worker, so that the other one picks up the ready tasks.
+        thread::sleep(Duration::from_millis(2));
+        yield_now().await;
+    }
+    done.send(()).unwrap();
+}
+
+#[test]
+fn tasks_are_balanced_between_threads() {
+    let (tx, rx) = channel();
+    for _ in 0..8 {
+        SHARED.spawner().spawn(work(tx.clone()).unwrap());
+    }
+
+    for _ in 0..2 {
+        thread::spawn(|| {
+            let executor = Box::leak(Box::new(Executor::new()));
+            executor.run_shared(&SHARED, |_| {})
+        });
+    }
+
+    for _ in 0..8 {
+        rx.recv_timeout(Duration::from_secs(10)).unwrap();
+    }
+    assert_eq!(THREADS.lock().unwrap().as_ref().unwrap().len(), 2);
+}
+
+#[embassy_executor::task]
+async fn get_spawner() {
+    unsafe { embassy_executor::Spawner::for_current_executor() }.await;
+}
+
+#[test]
+fn shared_tasks_cant_get_a_non_send_spawner() {
+    static OTHER: SharedExecutor = SharedExecutor::new();
+
+    OTHER.spawner().spawn(get_spawner().unwrap());
+    let worker = thread::spawn(|| {
+        let executor = Box::leak(Box::new(Executor::new()));
+        executor.run_shared(&OTHER, |_| {})
+    });
+    assert!(worker.join().is_err());
+}
@@ -0,0 +1,109 @@
+// Without `std`, the executor tracks the current task in a global, which the executors of other
+// tests, polled on other threads, would change. So task-locals are tested on their own.
+#![cfg(feature = "task-local")]
+
+use std::cell::Cell;
+use std::future::{Future, poll_fn};
+use std::pin::pin;
+use std::sync::Mutex;
+use std::task::{Context, Poll, Waker};
+
+use embassy_executor::raw::Executor;
+use embassy_executor::task;
+use embassy_executor::task_local::AccessError;
+// Provides the time driver used by features like `stats`.
+use embassy_time as _;
+
+#[unsafe(export_name = "__pender")]
+fn __pender(_context: *mut ()) {}
+
+static TRACE: Mutex<Vec<&'static str>> = Mutex::new(Vec::new());
+
+fn trace(value: &'static str) {
+    TRACE.lock().unwrap().push(value)
+}
+
+embassy_executor::task_local! {
+    static ID: u32;
+    static COUNT: Cell<u32>;
+}
+
+/// A synchronous helper, reading the task-locals of the task calling it.
+fn count() {
+    let count = COUNT.with(|c| {
+        c.set(c.get() + 1);
+        c.get()
+    });
+    trace(match (ID.get(), count) {
+        (1, 1) => "id 1, count 1",
+        (2, 2) => "id 2, count 2",
+        (1, 3) => "id 1, count 3",
+        (1, 4) => "id 1, count 4",
+        _ => "unexpected",
+    });
+}
+
+/// Poll `fut` once, leaving it pending.
+async fn poll_once(fut: impl Future<Output = ()>) -> Poll<()> {
+    let mut fut = pin!(fut);
+    poll_fn(|cx| Poll::Ready(fut.as_mut().poll(cx))).await
+}
+
+#[task]
+async fn task1() {
+    assert_eq!(ID.try_with(|_| ()), Err(AccessError));
+
+    let inner = async {
+        count();
+        ID.scope(2, async { count() }).await;
+        // Stay pending: the `ID = 2` scope is done, the outer ones are not.
+        poll_fn(|cx| {
+            cx.waker().wake_by_ref();
+            Poll::<()>::Pending
+        })
+        .await;
+    };
+    ID.scope(
+        1,
+        COUNT.scope(Cell::new(0), async {
+            let _ = poll_once(inner).await;
+            count();
+            // Values are found even when polled with a waker that isn't from the executor.
+            let foreign = pin!(async { count() });
+            assert!(foreign.poll(&mut Context::from_waker(Waker::noop())).is_ready());
+        }),
+    )
+    .await;
+    assert_eq!(ID.try_with(|_| ()), Err(AccessError));
+    trace("done");
+}
+
+#[task]
+async fn task2() {
+    assert_eq!(ID.try_with(|_| ()), Err(AccessError));
+    trace("task2 has no value");
+}
+
+#[test]
+fn task_local() {
+    // Outside of a task.
+    assert_eq!(ID.try_with(|_| ()), Err(AccessError));
+
+    let executor = Box::leak(Box::new(Executor::new(std::ptr::null_mut())));
+    executor.spawner().spawn(task1().unwrap());
+    executor.spawner().spawn(task2().unwrap());
+    unsafe { executor.poll() };
+
+    assert_eq!(
+        *TRACE.lock().unwrap(),
+        &[
+            "task2 has no value",
+            "id 1, count 1",
+            "id 2, count 2",
+            "id 1, count 3",
+            "id 1, count 4",
+            "done",
+        ]
+    );
+    assert_eq!(ID.try_with(|_| ()), Err(AccessError));
+}
@@ -0,0 +1,59 @@
+#![cfg(all(feature = "task-local", feature = "arch-std", feature = "executor-thread"))]
+
+use std::future::poll_fn;
+use std::sync::Barrier;
+use std::sync::mpsc::{Sender, channel};
+use std::task::Poll;
+use std::thread;
+
+use embassy_executor::Executor;
+
+embassy_executor::task_local! {
+    static ID: u32;
+}
+
+static BARRIER: Barrier = Barrier::new(2);
+
+async fn yield_now() {
+    let mut yielded = false;
+    poll_fn(|cx| {
+        if yielded {
+            Poll::Ready(())
+        } else {
+            yielded = true;
+            cx.waker().wake_by_ref();
+            Poll::Pending
+        }
+    })
+    .await
+}
+
+#[embassy_executor::task(pool_size = 2)]
+async fn worker(id: u32, done: Sender<u32>) {
+    ID.scope(id, async {
+        // Both tasks are in their scope from now on.
+        BARRIER.wait();
+        for _ in 0..1000 {
+            assert_eq!(ID.get(), id);
+            yield_now().await;
+        }
+    })
+    .await;
+    done.send(id).unwrap();
+}
+
+#[test]
+fn executors_on_two_threads() {
+    let (done, results) = channel();
+    for id in [1, 2] {
+        let done = done.clone();
+        thread::spawn(move || {
+            let executor = Box::leak(Box::new(Executor::new()));
+            executor.run(|spawner| spawner.spawn(worker(id, done).unwrap()));
+        });
+    }
+
+    let mut results = [results.recv().unwrap(), results.recv().unwrap()];
+    results.sort();
+    assert_eq!(results, [1, 2]);
+}
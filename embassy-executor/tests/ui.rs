@@ -33,6 +33,7 @@ fn ui() {
     t.compile_fail("tests/ui/type_error.rs");
     t.compile_fail("tests/ui/where_clause.rs");
     t.compile_fail("tests/ui/unsafe_op_in_unsafe_task.rs");
+    t.compile_fail("tests/ui/task_size_warning.rs");
 
     t.pass("tests/ui/task_safety_attribute.rs");
 }
@@ -0,0 +1,13 @@
+#![deny(deprecated)]
+
+#[embassy_executor::task(size_warning = 64)]
+async fn big_task() {
+    let buf = [0u8; 1024];
+    core::future::pending::<()>().await;
+    core::hint::black_box(buf);
+}
+
+#[embassy_executor::task(size_warning = 4096)]
+async fn small_task() {}
+
+fn main() {}
@@ -0,0 +1,565 @@
+//! Run a varying set of futures concurrently, getting their outputs as they complete.
+//!
+//! Unlike [`join_array`](crate::join::join_array) or [`select_slice`](crate::select::select_slice),
+//! futures can be pushed into a [`FuturesSet`] while others are in flight.
+//!
+//! ```rust
+//! use core::pin::pin;
+//!
+//! use embassy_futures::block_on;
+//! use embassy_futures::futures_set::{FuturesSet, FuturesSetWakers};
+//!
+//! static WAKERS: FuturesSetWakers<4> = FuturesSetWakers::new();
+//!
+//! async fn double(n: u32) -> u32 {
+//!     embassy_futures::yield_now().await;
+//!     n * 2
+//! }
+//!
+//! block_on(async {
+//!     let mut set = pin!(FuturesSet::new(&WAKERS));
+//!     for n in 1..=3 {
+//!         assert!(set.as_mut().push(double(n)).is_ok());
+//!     }
+//!
+//!     let mut total = 0;
+//!     while let Some(output) = set.as_mut().next().await {
+//!         total += output;
+//!         if output == 2 {
+//!             // Push another future while the others are in flight.
+//!             assert!(set.as_mut().push(double(10)).is_ok());
+//!         }
+//!     }
+//!     assert_eq!(total, 32);
+//! });
+//! ```
+
+use core::cell::{Cell, RefCell};
+use core::future::{Future, poll_fn};
+use core::pin::Pin;
+use core::ptr;
+use core::sync::atomic::{AtomicBool, AtomicPtr, Ordering, fence};
+use core::task::{Context, Poll, RawWaker, RawWakerVTable, Waker};
+
+use critical_section::Mutex;
+
+/// Storage for the wakers of a [`FuturesSet`] of up to `N` futures.
+///
+/// Each future in the set gets its own waker, so that only the futures that were woken are polled
+/// again. Futures may keep their waker after the set is gone, so the wakers refer to this storage,
+/// which must be `'static`, rather than to the set itself.
+///
+/// The storage is used by one set at a time. A set created while another one uses it, for example
+/// by another instance of the same task, polls all its futures whenever it's woken instead. Once
+/// the set using the storage is dropped, the storage can be used by another set.
+pub struct FuturesSetWakers<const N: usize> {
+    set: SetWaker,
+    slots: [SlotWaker; N],
+}
+
+/// Waker of the task polling the set.
+struct SetWaker {
+    in_use: Mutex<Cell<bool>>,
+    waker: Mutex<RefCell<Option<Waker>>>,
+}
+
+/// Waker of a future in the set.
+struct SlotWaker {
+    ready: AtomicBool,
+    set: AtomicPtr<SetWaker>,
+}
+
+static VTABLE: RawWakerVTable = RawWakerVTable::new(clone_slot_waker, wake_slot, wake_slot, drop_slot_waker);
+
+unsafe fn clone_slot_waker(data: *const ()) -> RawWaker {
+    RawWaker::new(data, &VTABLE)
+}
+
+unsafe fn wake_slot(data: *const ()) {
+    // Safety: slot wakers point to a `SlotWaker` of a `'static` `FuturesSetWakers`.
+    let slot = unsafe { &*(data as *const SlotWaker) };
+    slot.ready.store(true, Ordering::Release);
+    if let Some(set) = unsafe { slot.set.load(Ordering::Acquire).as_ref() } {
+        set.wake();
+    }
+}
+
+unsafe fn drop_slot_waker(_: *const ()) {}
+
+impl<const N: usize> FuturesSetWakers<N> {
+    /// Create storage for the wakers of a set.
+    pub const fn new() -> Self {
+        Self {
+            set: SetWaker {
+                in_use: Mutex::new(Cell::new(false)),
+                waker: Mutex::new(RefCell::new(None)),
+            },
+            slots: [const {
+                SlotWaker {
+                    ready: AtomicBool::new(false),
+                    set: AtomicPtr::new(ptr::null_mut()),
+                }
+            }; N],
+        }
+    }
+
+    /// Claim the storage for a set, returning `false` if another set uses it.
+    fn try_claim(&'static self) -> bool {
+        let in_use = critical_section::with(|cs| self.set.in_use.borrow(cs).replace(true));
+        if in_use {
+            return false;
+        }
+        for slot in &self.slots {
+            slot.ready.store(false, Ordering::Relaxed);
+            slot.set.store(ptr::from_ref(&self.set).cast_mut(), Ordering::Release);
+        }
+        true
+    }
+
+    fn release(&self) {
+        critical_section::with(|cs| {
+            self.set.waker.borrow_ref_mut(cs).take();
+            self.set.in_use.borrow(cs).set(false);
+        })
+    }
+}
+
+impl<const N: usize> Default for FuturesSetWakers<N> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl SetWaker {
+    fn register(&self, waker: &Waker) {
+        critical_section::with(|cs| {
+            let mut registered = self.waker.borrow_ref_mut(cs);
+            match registered.as_ref() {
+                Some(w) if w.will_wake(waker) => {}
+                _ => *registered = Some(waker.clone()),
+            }
+        })
+    }
+
+    fn wake(&self) {
+        if let Some(waker) = critical_section::with(|cs| self.waker.borrow_ref(cs).clone()) {
+            waker.wake();
+        }
+    }
+}
+
+impl SlotWaker {
+    fn waker(&'static self) -> Waker {
+        // Safety: the vtable functions uphold the `RawWaker` contract, and `self` is `'static`.
+        unsafe { Waker::from_raw(RawWaker::new(ptr::from_ref(self).cast(), &VTABLE)) }
+    }
+}
+
+/// A set of up to `N` futures running concurrently, like `FuturesUnordered` without allocation.
+///
+/// Futures are added with [`push()`](Self::push), and their outputs are returned by
+/// [`next()`](Self::next) in the order they complete. Each future has its own waker, so that
+/// polling the set only polls the futures that were woken, or just pushed.
+///
+/// The set must be pinned to be used, e.g. with [`core::pin::pin!`]. It needs a `'static`
+/// [`FuturesSetWakers`] for the wakers of its futures, see the [module documentation](self) for
+/// an example.
+pub struct FuturesSet<F, const N: usize> {
+    /// `None` if the wakers were used by another set, in which case all the futures are polled
+    /// with the waker of the task.
+    wakers: Option<&'static FuturesSetWakers<N>>,
+    futures: [Option<F>; N],
+    len: usize,
+    /// Index of the first slot to poll, so that all futures get a chance to complete.
+    next: usize,
+}
+
+impl<F: Future, const N: usize> FuturesSet<F, N> {
+    /// Create an empty set, using `wakers` for the wakers of its futures.
+    ///
+    /// If `wakers` is already used by another set, this set polls all its futures whenever it's
+    /// woken instead, see [`FuturesSetWakers`].
+    pub fn new(wakers: &'static FuturesSetWakers<N>) -> Self {
+        Self {
+            wakers: Some(wakers).filter(|wakers| wakers.try_claim()),
+            futures: [const { None }; N],
+            len: 0,
+            next: 0,
+        }
+    }
+
+    /// Maximum number of futures in the set.
+    pub const fn capacity(&self) -> usize {
+        N
+    }
+
+    /// Number of futures in the set.
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Returns whether the set has no futures.
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Returns whether the set is full.
+    pub fn is_full(&self) -> bool {
+        self.len == N
+    }
+
+    /// Add a future to the set, or give it back if the set is full.
+    ///
+    /// The future is first polled by the next call to [`next()`](Self::next) or
+    /// [`poll_next()`](Self::poll_next).
+    pub fn push(self: Pin<&mut Self>, future: F) -> Result<(), F> {
+        // Safety: only empty slots are written to, no pinned future is moved.
+        let this = unsafe { self.get_unchecked_mut() };
+        let Some(i) = this.futures.iter().position(Option::is_none) else {
+            return Err(future);
+        };
+        this.futures[i] = Some(future);
+        this.len += 1;
+        if let Some(wakers) = this.wakers {
+            wakers.slots[i].ready.store(true, Ordering::Relaxed);
+        }
+        Ok(())
+    }
+
+    /// Wait for a future of the set to complete, removing it from the set and returning its output.
+    ///
+    /// Returns `None` if the set is empty.
+    ///
+    /// ## Cancel safety
+    /// The produced Future is cancel safe: no output is lost if it is dropped, and the futures in
+    /// the set keep their progress.
+    pub fn next(self: Pin<&mut Self>) -> impl Future<Output = Option<F::Output>> + '_ {
+        let mut this = self;
+        poll_fn(move |cx| this.as_mut().poll_next(cx))
+    }
+
+    /// Poll the futures of the set that were woken, returning the output of the first one that
+    /// completes, or `None` if the set is empty.
+    pub fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<F::Output>> {
+        // Safety: the futures are pinned along with the set, and only dropped in place.
+        let this = unsafe { self.get_unchecked_mut() };
+        if this.len == 0 {
+            return Poll::Ready(None);
+        }
+        if let Some(wakers) = this.wakers {
+            wakers.set.register(cx.waker());
+        }
+
+        for n in 0..N {
+            let i = (this.next + n) % N;
+            let Some(future) = this.futures[i].as_mut() else {
+                continue;
+            };
+            let future = unsafe { Pin::new_unchecked(future) };
+            let poll = match this.wakers {
+                Some(wakers) => {
+                    let slot = &wakers.slots[i];
+                    if !slot.ready.load(Ordering::Acquire) {
+                        continue;
+                    }
+                    // Clear the flag before polling, so that a wake during the poll isn't lost.
+                    slot.ready.store(false, Ordering::Relaxed);
+                    fence(Ordering::SeqCst);
+
+                    let waker = slot.waker();
+                    future.poll(&mut Context::from_waker(&waker))
+                }
+                None => future.poll(cx),
+            };
+            if let Poll::Ready(output) = poll {
+                this.futures[i] = None;
+                this.len -= 1;
+                this.next = (i + 1) % N;
+                return Poll::Ready(Some(output));
+            }
+        }
+        Poll::Pending
+    }
+}
+
+impl<F, const N: usize> Drop for FuturesSet<F, N> {
+    fn drop(&mut self) {
+        // Drop the futures before the wakers can be used by another set.
+        for future in &mut self.futures {
+            *future = None;
+        }
+        if let Some(wakers) = self.wakers {
+            wakers.release();
+        }
+    }
+}
+
+impl<F, const N: usize> core::fmt::Debug for FuturesSet<F, N> {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.debug_struct("FuturesSet")
+            .field("len", &self.len)
+            .field("capacity", &N)
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    extern crate std;
+
+    use core::pin::pin;
+    use core::sync::atomic::AtomicUsize;
+    use std::rc::Rc;
+    use std::sync::Arc;
+    use std::task::Wake;
+
+    use super::*;
+
+    /// Counts the wakes of the task polling the set.
+    struct CountWaker(AtomicUsize);
+
+    impl Wake for CountWaker {
+        fn wake(self: Arc<Self>) {
+            self.0.fetch_add(1, Ordering::Relaxed);
+        }
+    }
+
+    fn count_waker() -> (Waker, Arc<CountWaker>) {
+        let count = Arc::new(CountWaker(AtomicUsize::new(0)));
+        (Waker::from(count.clone()), count)
+    }
+
+    /// State of a `Probe`, shared with the test.
+    #[derive(Default)]
+    struct ProbeState {
+        polls: Cell<usize>,
+        waker: RefCell<Option<Waker>>,
+        ready: Cell<bool>,
+        dropped: Cell<bool>,
+    }
+
+    impl ProbeState {
+        fn wake(&self) {
+            self.waker.borrow_mut().take().unwrap().wake();
+        }
+    }
+
+    /// A future that completes once told to, and records its polls.
+    struct Probe(Rc<ProbeState>, u32);
+
+    impl Future for Probe {
+        type Output = u32;
+
+        fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<u32> {
+            let state = &self.0;
+            state.polls.set(state.polls.get() + 1);
+            if state.ready.get() {
+                return Poll::Ready(self.1);
+            }
+            *state.waker.borrow_mut() = Some(cx.waker().clone());
+            Poll::Pending
+        }
+    }
+
+    impl Drop for Probe {
+        fn drop(&mut self) {
+            self.0.dropped.set(true);
+        }
+    }
+
+    fn probes<const N: usize>() -> [Rc<ProbeState>; N] {
+        core::array::from_fn(|_| Rc::new(ProbeState::default()))
+    }
+
+    #[test]
+    fn polls_only_woken_futures() {
+        static WAKERS: FuturesSetWakers<3> = FuturesSetWakers::new();
+        let (waker, count) = count_waker();
+        let mut cx = Context::from_waker(&waker);
+        let states = probes::<3>();
+        let mut set = pin!(FuturesSet::new(&WAKERS));
+        for (i, state) in states.iter().enumerate() {
+            assert!(set.as_mut().push(Probe(state.clone(), i as u32)).is_ok());
+        }
+
+        // Pushed futures are polled once.
+        assert_eq!(set.as_mut().poll_next(&mut cx), Poll::Pending);
+        assert!(states.iter().all(|s| s.polls.get() == 1));
+        assert_eq!(set.as_mut().poll_next(&mut cx), Poll::Pending);
+        assert!(states.iter().all(|s| s.polls.get() == 1));
+
+        // Waking a future wakes the task, and only that future is polled again.
+        states[1].wake();
+        assert_eq!(count.0.load(Ordering::Relaxed), 1);
+        assert_eq!(set.as_mut().poll_next(&mut cx), Poll::Pending);
+        assert_eq!(states.each_ref().map(|s| s.polls.get()), [1, 2, 1]);
+
+        states[2].ready.set(true);
+        states[2].wake();
+        assert_eq!(set.as_mut().poll_next(&mut cx), Poll::Ready(Some(2)));
+        assert_eq!(states.each_ref().map(|s| s.polls.get()), [1, 2, 2]);
+        assert!(states[2].dropped.get());
+        assert_eq!(set.len(), 2);
+    }
+
+    #[test]
+    fn push_fails_when_full() {
+        static WAKERS: FuturesSetWakers<2> = FuturesSetWakers::new();
+        let states = probes::<3>();
+        let mut set = pin!(FuturesSet::new(&WAKERS));
+        assert!(set.as_mut().push(Probe(states[0].clone(), 0)).is_ok());
+        assert!(set.as_mut().push(Probe(states[1].clone(), 1)).is_ok());
+        assert!(set.is_full());
+
+        let Err(rejected) = set.as_mut().push(Probe(states[2].clone(), 2)) else {
+            panic!("pushed into a full set");
+        };
+        assert_eq!(rejected.1, 2);
+        assert_eq!(set.len(), 2);
+    }
+
+    #[test]
+    fn reuses_slots_and_wakers() {
+        static WAKERS: FuturesSetWakers<1> = FuturesSetWakers::new();
+        let (waker, count) = count_waker();
+        let mut cx = Context::from_waker(&waker);
+        let states = probes::<3>();
+
+        {
+            let mut set = pin!(FuturesSet::new(&WAKERS));
+            assert!(set.as_mut().push(Probe(states[0].clone(), 0)).is_ok());
+            assert_eq!(set.as_mut().poll_next(&mut cx), Poll::Pending);
+            states[0].ready.set(true);
+            let stale = states[0].waker.borrow_mut().take().unwrap();
+            stale.wake_by_ref();
+            assert_eq!(set.as_mut().poll_next(&mut cx), Poll::Ready(Some(0)));
+
+            // The slot of the finished future is used by the next one.
+            assert!(set.as_mut().push(Probe(states[1].clone(), 1)).is_ok());
+            assert_eq!(set.as_mut().poll_next(&mut cx), Poll::Pending);
+            assert_eq!(states[1].polls.get(), 1);
+
+            // A waker kept by the finished future only makes the new one be polled again.
+            stale.wake();
+            assert_eq!(set.as_mut().poll_next(&mut cx), Poll::Pending);
+            assert_eq!(states[1].polls.get(), 2);
+        }
+        assert!(states[1].dropped.get());
+
+        // Wakers kept after the set is dropped don't wake its task anymore.
+        let wakes = count.0.load(Ordering::Relaxed);
+        states[1].wake();
+        assert_eq!(count.0.load(Ordering::Relaxed), wakes);
+
+        // The wakers can be used by another set.
+        let mut set = pin!(FuturesSet::new(&WAKERS));
+        assert!(set.as_mut().push(Probe(states[2].clone(), 2)).is_ok());
+        assert_eq!(set.as_mut().poll_next(&mut cx), Poll::Pending);
+        states[2].ready.set(true);
+        states[2].wake();
+        assert_eq!(count.0.load(Ordering::Relaxed), wakes + 1);
+        assert_eq!(set.as_mut().poll_next(&mut cx), Poll::Ready(Some(2)));
+        assert_eq!(set.as_mut().poll_next(&mut cx), Poll::Ready(None));
+    }
+
+    #[test]
+    fn wakers_used_by_another_set() {
+        static WAKERS: FuturesSetWakers<2> = FuturesSetWakers::new();
+        let (waker, count) = count_waker();
+        let mut cx = Context::from_waker(&waker);
+        let states = probes::<4>();
+
+        let mut set = pin!(FuturesSet::new(&WAKERS));
+        assert!(set.as_mut().push(Probe(states[0].clone(), 0)).is_ok());
+        assert!(set.as_mut().push(Probe(states[1].clone(), 1)).is_ok());
+        assert_eq!(set.as_mut().poll_next(&mut cx), Poll::Pending);
+
+        // Another set, like one of another instance of the same task, still works.
+        let mut other = pin!(FuturesSet::new(&WAKERS));
+        assert!(other.as_mut().push(Probe(states[2].clone(), 2)).is_ok());
+        assert!(other.as_mut().push(Probe(states[3].clone(), 3)).is_ok());
+        assert_eq!(other.as_mut().poll_next(&mut cx), Poll::Pending);
+        assert!(states.iter().all(|s| s.polls.get() == 1));
+
+        // Its futures are woken through the task, and all polled again.
+        states[3].ready.set(true);
+        states[3].wake();
+        assert_eq!(count.0.load(Ordering::Relaxed), 1);
+        assert_eq!(other.as_mut().poll_next(&mut cx), Poll::Ready(Some(3)));
+        assert_eq!(states.each_ref().map(|s| s.polls.get()), [1, 1, 2, 2]);
+
+        // The first set still only polls the futures that were woken.
+        states[1].ready.set(true);
+        states[1].wake();
+        assert_eq!(set.as_mut().poll_next(&mut cx), Poll::Ready(Some(1)));
+        assert_eq!(states.each_ref().map(|s| s.polls.get()), [1, 2, 2, 2]);
+
+        states[2].ready.set(true);
+        states[2].wake();
+        assert_eq!(other.as_mut().poll_next(&mut cx), Poll::Ready(Some(2)));
+        assert_eq!(other.as_mut().poll_next(&mut cx), Poll::Ready(None));
+    }
+
+    #[test]
+    fn nested_sets_share_wakers() {
+        static WAKERS: FuturesSetWakers<2> = FuturesSetWakers::new();
+
+        async fn inner(n: u32) -> u32 {
+            let mut set = pin!(FuturesSet::new(&WAKERS));
+            for i in 0..2 {
+                assert!(
+                    set.as_mut()
+                        .push(async move {
+                            crate::yield_now().await;
+                            n + i
+                        })
+                        .is_ok()
+                );
+            }
+            let mut total = 0;
+            while let Some(output) = set.as_mut().next().await {
+                total += output;
+            }
+            total
+        }
+
+        let total = crate::block_on(async {
+            let mut set = pin!(FuturesSet::new(&WAKERS));
+            assert!(set.as_mut().push(inner(10)).is_ok());
+            assert!(set.as_mut().push(inner(20)).is_ok());
+            let mut total = 0;
+            while let Some(output) = set.as_mut().next().await {
+                total += output;
+            }
+            total
+        });
+        assert_eq!(total, 62);
+    }
+
+    #[test]
+    fn cancel_safe() {
+        static WAKERS: FuturesSetWakers<2> = FuturesSetWakers::new();
+        let (waker, _) = count_waker();
+        let mut cx = Context::from_waker(&waker);
+        let states = probes::<2>();
+        {
+            let mut set = pin!(FuturesSet::new(&WAKERS));
+            assert!(set.as_mut().push(Probe(states[0].clone(), 0)).is_ok());
+            assert!(set.as_mut().push(Probe(states[1].clone(), 1)).is_ok());
+
+            // Dropping a pending `next()` loses nothing.
+            assert_eq!(pin!(set.as_mut().next()).poll(&mut cx), Poll::Pending);
+            states[0].ready.set(true);
+            states[0].wake();
+            assert_eq!(set.len(), 2);
+            assert_eq!(pin!(set.as_mut().next()).poll(&mut cx), Poll::Ready(Some(0)));
+            assert_eq!(states[0].polls.get(), 2);
+            assert!(!states[1].dropped.get());
+        }
+
+        // Dropping the set drops its pending futures, and releases the wakers.
+        assert!(states[1].dropped.get());
+        assert_eq!(states[1].polls.get(), 1);
+        let _set = FuturesSet::<Probe, 2>::new(&WAKERS);
+    }
+}
@@ -6,8 +6,9 @@ use core::pin::Pin;
 use core::task::{Context, Poll};
 use core::{fmt, mem};
 
+#[doc(hidden)]
 #[derive(Debug)]
-enum MaybeDone<Fut: Future> {
+pub enum MaybeDone<Fut: Future> {
     /// A not-yet-completed future
     Future(/* #[pin] */ Fut),
     /// The output of the completed future
@@ -18,7 +19,7 @@ enum MaybeDone<Fut: Future> {
 }
 
 impl<Fut: Future> MaybeDone<Fut> {
-    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> bool {
+    pub fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> bool {
         let this = unsafe { self.get_unchecked_mut() };
         match this {
             Self::Future(fut) => match unsafe { Pin::new_unchecked(fut) }.poll(cx) {
@@ -32,7 +33,7 @@ impl<Fut: Future> MaybeDone<Fut> {
         }
     }
 
-    fn take_output(&mut self) -> Fut::Output {
+    pub fn take_output(&mut self) -> Fut::Output {
         match &*self {
             Self::Done(_) => {}
             Self::Future(_) | Self::Gone => panic!("take_output when MaybeDone is not done."),
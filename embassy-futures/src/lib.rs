@@ -6,10 +6,15 @@
 pub(crate) mod fmt;
 
 mod block_on;
+mod macros;
 mod yield_now;
 
+#[cfg(feature = "futures-set")]
+pub mod futures_set;
 pub mod join;
 pub mod select;
 
 pub use block_on::*;
+#[doc(hidden)]
+pub use macros::__private;
 pub use yield_now::*;
@@ -0,0 +1,468 @@
+//! `select!` and `join!` macros, for any number of futures.
+
+#[doc(hidden)]
+pub mod __private {
+    pub use core::future::{Future, poll_fn};
+    pub use core::pin::Pin;
+    pub use core::sync::atomic::AtomicUsize;
+    use core::sync::atomic::Ordering;
+    pub use core::task::Poll;
+
+    pub use crate::join::MaybeDone;
+    pub use crate::select::Either;
+
+    /// Returns the index to start polling from, and moves `next` to the following one.
+    ///
+    /// Only atomic loads and stores are used, so that this works on all targets. Concurrent
+    /// polls may start from the same index, which only affects fairness.
+    pub fn rotate(next: &AtomicUsize, len: usize) -> usize {
+        let start = next.load(Ordering::Relaxed) % len;
+        next.store((start + 1) % len, Ordering::Relaxed);
+        start
+    }
+}
+
+/// Wait for one of several futures to complete, and run the branch handling its output.
+///
+/// Each branch has the form `pattern = future => handler`. The futures are polled concurrently
+/// and, once one of them completes, the others are dropped and the handler of its branch runs
+/// with the pattern bindings in scope. Handlers run in the calling async context, so they can
+/// use `.await`, `return`, `break` and `continue`.
+///
+/// If the output of a future doesn't match the pattern of its branch, the branch is disabled
+/// and the other futures keep running. If all branches are disabled, the `else` branch runs;
+/// without an `else` branch, `select!` panics.
+///
+/// By default, the futures are polled starting from a different branch every time this
+/// `select!` is polled, so that a future that is always ready doesn't starve the others.
+/// Starting with `biased;` polls them in order instead, giving priority to the first branches.
+///
+/// The branch to start from is kept in a `static` of the call site, so it's shared by every
+/// execution of that `select!`: concurrent executions, like those of several instances of a task,
+/// advance the same rotation. Each of them may then keep starting from the same branch, which
+/// only affects fairness.
+///
+/// `select!` must be used in an async context, and doesn't allocate: the futures are stored on
+/// the stack of the calling future.
+///
+/// # Examples
+///
+/// ```
+/// use embassy_futures::{select, yield_now};
+///
+/// # embassy_futures::block_on(async {
+/// let slow = async {
+///     yield_now().await;
+///     1
+/// };
+/// let fast = async { 2 };
+///
+/// let result = select! {
+///     n = slow => n * 10,
+///     n = fast => n * 100,
+/// };
+/// assert_eq!(result, 200);
+/// # });
+/// ```
+///
+/// Patterns can disable branches, and `biased;` gives priority to the first branches:
+///
+/// ```
+/// use embassy_futures::select;
+///
+/// # embassy_futures::block_on(async {
+/// let result = select! {
+///     biased;
+///     Some(n) = async { None::<u32> } => n,
+///     Ok(n) = async { Err::<u32, ()>(()) } => n,
+///     n = async { 3 } => n,
+///     n = async { 4 } => n,
+/// };
+/// assert_eq!(result, 3);
+///
+/// let result = select! {
+///     Some(n) = async { None::<u32> } => n,
+///     else => 0,
+/// };
+/// assert_eq!(result, 0);
+/// # });
+/// ```
+#[macro_export]
+macro_rules! select {
+    // No more branches, and no `else` branch.
+    (@ { $biased:tt ($($count:tt)*) ($($total:tt)*) () $($branches:tt)* }) => {
+        $crate::select!(@ {
+            $biased
+            ($($count)*)
+            ($($total)*)
+            ({ ::core::panic!("all branches of `select!` are disabled and there is no `else` branch") })
+            $($branches)*
+        })
+    };
+
+    (@ { $biased:tt () (0) ($else:tt) }) => {
+        ::core::compile_error!("`select!` needs at least one branch")
+    };
+
+    (@ {
+        $biased:tt
+        ($($count:tt)*)
+        ($($total:tt)*)
+        ($else:tt)
+        $( ( ($($skip:tt)*) ($($index:tt)*) ($($pat:tt)*) $fut:expr, $handler:tt ) )*
+    }) => {{
+        let __output = {
+            let mut __futures = ( $( $fut, )* );
+            let __futures = &mut __futures;
+            let mut __disabled = [false; $($total)*];
+            // Branch polled first by the next poll of this `select!`, shared by all its uses.
+            static __NEXT: $crate::__private::AtomicUsize = $crate::__private::AtomicUsize::new(0);
+            $crate::__private::poll_fn(move |cx| {
+                let __len = __disabled.len();
+                let __start = if $biased { 0 } else { $crate::__private::rotate(&__NEXT, __len) };
+                for __n in 0..__len {
+                    let __i = (__start + __n) % __len;
+                    if __disabled[__i] {
+                        continue;
+                    }
+                    $(
+                        if __i == $($index)* {
+                            let ( $($skip,)* __fut, .. ) = &mut *__futures;
+                            // Safety: the futures are never moved, they are dropped in place
+                            // along with `__futures`.
+                            let __fut = unsafe { $crate::__private::Pin::new_unchecked(__fut) };
+                            if let $crate::__private::Poll::Ready(__out) = $crate::__private::Future::poll(__fut, cx) {
+                                __disabled[__i] = true;
+                                // Check the pattern without moving the output, with `mut` and `ref`
+                                // removed since the output is matched by reference.
+                                #[allow(unused_variables, unreachable_patterns)]
+                                match &__out {
+                                    $crate::__select_clean_pattern!(@ [] () $($pat)*) => {
+                                        return $crate::__private::Poll::Ready(
+                                            $crate::__select_output!(($($skip)*) __out)
+                                        );
+                                    }
+                                    _ => {}
+                                }
+                            }
+                        }
+                    )*
+                }
+                if __disabled.iter().all(|disabled| *disabled) {
+                    $crate::__private::Poll::Ready($crate::__select_output!(@else ($($count)*) ()))
+                } else {
+                    $crate::__private::Poll::Pending
+                }
+            })
+            .await
+        };
+        match __output {
+            $( $crate::__select_output!(($($skip)*) $($pat)*) => $handler, )*
+            $crate::__select_output!(@else ($($count)*) ()) => $else,
+            #[allow(unreachable_patterns)]
+            _ => ::core::unreachable!(),
+        }
+    }};
+
+    // Normalize the branches, numbering them.
+    (@ { $biased:tt $count:tt $total:tt () $($branches:tt)* } else => $else:block $(,)?) => {
+        $crate::select!(@ { $biased $count $total ($else) $($branches)* })
+    };
+    (@ { $biased:tt $count:tt $total:tt () $($branches:tt)* } else => $else:expr $(,)?) => {
+        $crate::select!(@ { $biased $count $total ({ $else }) $($branches)* })
+    };
+    (@ { $($state:tt)* } $($rest:tt)+) => {
+        $crate::select!(@pattern { $($state)* } () $($rest)+)
+    };
+
+    // Collect the tokens of the pattern of a branch, up to the `=`.
+    (@pattern { $biased:tt ($($count:tt)*) ($($total:tt)*) $else:tt $($branches:tt)* } ($($pat:tt)*) = $fut:expr => $handler:block, $($rest:tt)*) => {
+        $crate::select!(@ {
+            $biased ($($count)* _) ($($total)* + 1) $else $($branches)* ( ($($count)*) ($($total)*) ($($pat)*) $fut, $handler )
+        } $($rest)*)
+    };
+    (@pattern { $biased:tt ($($count:tt)*) ($($total:tt)*) $else:tt $($branches:tt)* } ($($pat:tt)*) = $fut:expr => $handler:block $($rest:tt)*) => {
+        $crate::select!(@ {
+            $biased ($($count)* _) ($($total)* + 1) $else $($branches)* ( ($($count)*) ($($total)*) ($($pat)*) $fut, $handler )
+        } $($rest)*)
+    };
+    (@pattern { $biased:tt ($($count:tt)*) ($($total:tt)*) $else:tt $($branches:tt)* } ($($pat:tt)*) = $fut:expr => $handler:expr $(, $($rest:tt)*)?) => {
+        $crate::select!(@ {
+            $biased ($($count)* _) ($($total)* + 1) $else $($branches)* ( ($($count)*) ($($total)*) ($($pat)*) $fut, { $handler } )
+        } $($($rest)*)?)
+    };
+    (@pattern $state:tt ($($pat:tt)*) $next:tt $($rest:tt)*) => {
+        $crate::select!(@pattern $state ($($pat)* $next) $($rest)*)
+    };
+
+    (biased; $($branches:tt)*) => {
+        $crate::select!(@ { true () (0) () } $($branches)*)
+    };
+    ($($branches:tt)*) => {
+        $crate::select!(@ { false () (0) () } $($branches)*)
+    };
+}
+
+/// Wraps the output of the `n`th branch of `select!`, with `n` given as `n` underscores, in
+/// nested [`Either`](crate::select::Either)s. Used for both expressions and patterns.
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __select_output {
+    (() $($output:tt)*) => {
+        $crate::__private::Either::First($($output)*)
+    };
+    ((_ $($skip:tt)*) $($output:tt)*) => {
+        $crate::__private::Either::Second($crate::__select_output!(($($skip)*) $($output)*))
+    };
+    // Output when all `n` branches are disabled, after the last branch.
+    (@else (_) $($output:tt)*) => {
+        $crate::__private::Either::Second($($output)*)
+    };
+    (@else (_ $($skip:tt)+) $($output:tt)*) => {
+        $crate::__private::Either::Second($crate::__select_output!(@else ($($skip)+) $($output)*))
+    };
+}
+
+/// Removes the `mut` and `ref` binding modifiers from a pattern, so that it can be matched against
+/// a reference. Groups are handled with a stack of the enclosing groups and their remaining tokens.
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __select_clean_pattern {
+    (@ [] ($($pat:tt)*)) => {
+        $($pat)*
+    };
+    (@ $stack:tt $pat:tt mut $($rest:tt)*) => {
+        $crate::__select_clean_pattern!(@ $stack $pat $($rest)*)
+    };
+    (@ $stack:tt $pat:tt ref $($rest:tt)*) => {
+        $crate::__select_clean_pattern!(@ $stack $pat $($rest)*)
+    };
+    (@ [$($stack:tt)*] $pat:tt ( $($inner:tt)* ) $($rest:tt)*) => {
+        $crate::__select_clean_pattern!(@ [(paren $pat ($($rest)*)) $($stack)*] () $($inner)*)
+    };
+    (@ [$($stack:tt)*] $pat:tt [ $($inner:tt)* ] $($rest:tt)*) => {
+        $crate::__select_clean_pattern!(@ [(bracket $pat ($($rest)*)) $($stack)*] () $($inner)*)
+    };
+    (@ [$($stack:tt)*] $pat:tt { $($inner:tt)* } $($rest:tt)*) => {
+        $crate::__select_clean_pattern!(@ [(brace $pat ($($rest)*)) $($stack)*] () $($inner)*)
+    };
+    (@ [(paren ($($pat:tt)*) ($($rest:tt)*)) $($stack:tt)*] ($($inner:tt)*)) => {
+        $crate::__select_clean_pattern!(@ [$($stack)*] ($($pat)* ( $($inner)* )) $($rest)*)
+    };
+    (@ [(bracket ($($pat:tt)*) ($($rest:tt)*)) $($stack:tt)*] ($($inner:tt)*)) => {
+        $crate::__select_clean_pattern!(@ [$($stack)*] ($($pat)* [ $($inner)* ]) $($rest)*)
+    };
+    (@ [(brace ($($pat:tt)*) ($($rest:tt)*)) $($stack:tt)*] ($($inner:tt)*)) => {
+        $crate::__select_clean_pattern!(@ [$($stack)*] ($($pat)* { $($inner)* }) $($rest)*)
+    };
+    (@ $stack:tt ($($pat:tt)*) $next:tt $($rest:tt)*) => {
+        $crate::__select_clean_pattern!(@ $stack ($($pat)* $next) $($rest)*)
+    };
+}
+
+/// Wait for several futures to complete, evaluating to a tuple of their outputs.
+///
+/// Unlike the [`join`](mod@crate::join) functions, `join!` takes any number of futures, which can
+/// be of different types.
+///
+/// By default, the futures are polled starting from a different one every time. Starting with
+/// `biased;` polls them in order instead. Like with [`select!`], the future to start from is
+/// shared by every execution of the call site.
+///
+/// `join!` must be used in an async context, and doesn't allocate: the futures are stored on the
+/// stack of the calling future.
+///
+/// # Examples
+///
+/// ```
+/// use embassy_futures::{join, yield_now};
+///
+/// # embassy_futures::block_on(async {
+/// let a = async { 1 };
+/// let b = async {
+///     yield_now().await;
+///     "two"
+/// };
+/// let c = async { 3.0 };
+///
+/// let (a, b, c) = join!(a, b, c);
+/// assert_eq!((a, b, c), (1, "two", 3.0));
+///
+/// let (d,) = join!(biased; async { 4 });
+/// assert_eq!(d, 4);
+/// # });
+/// ```
+#[macro_export]
+macro_rules! join {
+    (@ {
+        $biased:tt
+        ($($count:tt)*)
+        ($($total:tt)*)
+        $( ( ($($skip:tt)*) ($($index:tt)*) $fut:expr ) )*
+    }) => {{
+        let mut __futures = ( $( $crate::__private::MaybeDone::Future($fut), )* );
+        let __futures = &mut __futures;
+        // Future polled first by the next poll of this `join!`, shared by all its uses.
+        static __NEXT: $crate::__private::AtomicUsize = $crate::__private::AtomicUsize::new(0);
+        $crate::__private::poll_fn(move |cx| {
+            let __len: usize = $($total)*;
+            let __start = if $biased { 0 } else { $crate::__private::rotate(&__NEXT, __len) };
+            let mut __done = true;
+            for __n in 0..__len {
+                let __i = (__start + __n) % __len;
+                $(
+                    if __i == $($index)* {
+                        let ( $($skip,)* __fut, .. ) = &mut *__futures;
+                        // Safety: the futures are never moved, they are dropped in place along
+                        // with `__futures`.
+                        __done &= unsafe { $crate::__private::Pin::new_unchecked(__fut) }.poll(cx);
+                    }
+                )*
+            }
+            if __done {
+                $crate::__private::Poll::Ready(( $({
+                    let ( $($skip,)* __fut, .. ) = &mut *__futures;
+                    __fut.take_output()
+                },)* ))
+            } else {
+                $crate::__private::Poll::Pending
+            }
+        })
+        .await
+    }};
+
+    // Normalize the futures, numbering them.
+    (@ { $biased:tt ($($count:tt)*) ($($total:tt)*) $($futs:tt)* } $fut:expr $(, $($rest:tt)*)?) => {
+        $crate::join!(@ {
+            $biased ($($count)* _) ($($total)* + 1) $($futs)* ( ($($count)*) ($($total)*) $fut )
+        } $($($rest)*)?)
+    };
+
+    (biased; $($futs:tt)+) => {
+        $crate::join!(@ { true () (0) } $($futs)+)
+    };
+    ($($futs:tt)+) => {
+        $crate::join!(@ { false () (0) } $($futs)+)
+    };
+}
+
+#[cfg(test)]
+mod tests {
+    extern crate std;
+
+    use core::cell::RefCell;
+    use std::vec::Vec;
+
+    use crate::{block_on, yield_now};
+
+    /// Completes with `n` after yielding `yields` times.
+    async fn after(yields: u32, n: u32) -> u32 {
+        for _ in 0..yields {
+            yield_now().await;
+        }
+        n
+    }
+
+    #[test]
+    fn select_many_branches() {
+        let result = block_on(async {
+            select! {
+                n = after(9, 0) => n,
+                n = after(8, 1) => n,
+                n = after(7, 2) => n,
+                n = after(6, 3) => n,
+                n = after(5, 4) => n,
+                n = after(4, 5) => n,
+                n = after(3, 6) => n,
+                Some(n) = async { None::<u32> } => n,
+                n = after(2, 8) => n,
+                n = after(3, 9) => n,
+            }
+        });
+        assert_eq!(result, 8);
+    }
+
+    #[test]
+    fn join_many_futures() {
+        let result = block_on(async {
+            join!(
+                after(9, 0),
+                after(0, 1),
+                async { "two" },
+                after(7, 3),
+                after(1, 4),
+                async { 5.0 },
+                after(3, 6),
+                after(5, 7),
+                after(2, 8),
+            )
+        });
+        assert_eq!(result, (0, 1, "two", 3, 4, 5.0, 6, 7, 8));
+    }
+
+    #[test]
+    fn select_rotates_branches() {
+        block_on(async {
+            let mut wins = [0; 3];
+            for _ in 0..9 {
+                let i = select! {
+                    i = async { 0 } => i,
+                    i = async { 1 } => i,
+                    i = async { 2 } => i,
+                };
+                wins[i] += 1;
+            }
+            // Each poll starts from the next branch, so the always ready futures take turns.
+            assert_eq!(wins, [3, 3, 3]);
+
+            for _ in 0..3 {
+                let i = select! {
+                    biased;
+                    i = async { 0 } => i,
+                    i = async { 1 } => i,
+                };
+                assert_eq!(i, 0);
+            }
+        });
+    }
+
+    #[test]
+    fn join_rotates_futures() {
+        let polls = RefCell::new(Vec::new());
+        let record = |i: u32| {
+            let polls = &polls;
+            async move {
+                for _ in 0..2 {
+                    polls.borrow_mut().push(i);
+                    yield_now().await;
+                }
+            }
+        };
+        block_on(async { join!(record(0), record(1), record(2)) });
+        // The second poll starts from the second future. The third one only completes the
+        // futures, which don't record it.
+        assert_eq!(*polls.borrow(), [0, 1, 2, 1, 2, 0]);
+    }
+
+    /// Runs the same `select!` call site on every execution.
+    async fn first_of(a: u32, b: u32) -> u32 {
+        select! {
+            n = after(a, 0) => n,
+            n = after(b, 1) => n,
+        }
+    }
+
+    #[test]
+    fn concurrent_executions_of_a_call_site() {
+        block_on(async {
+            // Each execution has its own futures.
+            assert_eq!(join!(first_of(0, 3), first_of(3, 0), first_of(2, 1)), (0, 1, 1));
+
+            // The rotation is shared: the first execution polled starts from the first branch,
+            // the second one from the second branch, every time.
+            for _ in 0..3 {
+                assert_eq!(join!(biased; first_of(0, 0), first_of(0, 0)), (0, 1));
+            }
+        });
+    }
+}
@@ -5,10 +5,16 @@ use std::io::{Read, Write};
 use std::os::unix::io::{AsRawFd, RawFd};
 use std::task::Context;
 
+#[cfg(all(feature = "async-io", not(feature = "reactor-epoll")))]
 use async_io::Async;
+#[cfg(feature = "reactor-epoll")]
+use embassy_executor::reactor::Async;
 use embassy_net_driver::{Capabilities, Driver, HardwareAddress, LinkState};
 use log::*;
 
+#[cfg(not(any(feature = "async-io", feature = "reactor-epoll")))]
+compile_error!("enable the `async-io` or the `reactor-epoll` feature of `embassy-net-tuntap`.");
+
 /// Get the MTU of the given interface.
 pub const SIOCGIFMTU: libc::c_ulong = 0x8921;
 /// Get the index of the given interface.
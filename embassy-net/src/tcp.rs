@@ -503,27 +503,29 @@ impl<'d> TcpIo<'d> {
 
     fn read<'s>(&'s mut self, buf: &'s mut [u8]) -> impl Future<Output = Result<usize, Error>> + 's {
         poll_fn(|cx| {
-            // CAUTION: smoltcp semantics around EOF are different to what you'd expect
-            // from posix-like IO, so we have to tweak things here.
-            self.with_mut(|s, _| match s.recv_slice(buf) {
-                // Reading into empty buffer
-                Ok(0) if buf.is_empty() => {
-                    // embedded_io_async::Read's contract is to not block if buf is empty. While
-                    // this function is not a direct implementor of the trait method, we still don't
-                    // want our future to never resolve.
-                    Poll::Ready(Ok(0))
-                }
-                // No data ready
-                Ok(0) => {
-                    s.register_recv_waker(cx.waker());
-                    Poll::Pending
-                }
-                // Data ready!
-                Ok(n) => Poll::Ready(Ok(n)),
-                // EOF
-                Err(tcp::RecvError::Finished) => Poll::Ready(Ok(0)),
-                // Connection reset. TODO: this can also be timeouts etc, investigate.
-                Err(tcp::RecvError::InvalidState) => Poll::Ready(Err(Error::ConnectionReset)),
+            embassy_sync::coop::poll_with_budget(cx, |cx| {
+                // CAUTION: smoltcp semantics around EOF are different to what you'd expect
+                // from posix-like IO, so we have to tweak things here.
+                self.with_mut(|s, _| match s.recv_slice(buf) {
+                    // Reading into empty buffer
+                    Ok(0) if buf.is_empty() => {
+                        // embedded_io_async::Read's contract is to not block if buf is empty. While
+                        // this function is not a direct implementor of the trait method, we still don't
+                        // want our future to never resolve.
+                        Poll::Ready(Ok(0))
+                    }
+                    // No data ready
+                    Ok(0) => {
+                        s.register_recv_waker(cx.waker());
+                        Poll::Pending
+                    }
+                    // Data ready!
+                    Ok(n) => Poll::Ready(Ok(n)),
+                    // EOF
+                    Err(tcp::RecvError::Finished) => Poll::Ready(Ok(0)),
+                    // Connection reset. TODO: this can also be timeouts etc, investigate.
+                    Err(tcp::RecvError::InvalidState) => Poll::Ready(Err(Error::ConnectionReset)),
+                })
             })
         })
     }
@@ -541,16 +543,18 @@ impl<'d> TcpIo<'d> {
 
     fn write<'s>(&'s mut self, buf: &'s [u8]) -> impl Future<Output = Result<usize, Error>> + 's {
         poll_fn(|cx| {
-            self.with_mut(|s, _| match s.send_slice(buf) {
-                // Not ready to send (no space in the tx buffer)
-                Ok(0) => {
-                    s.register_send_waker(cx.waker());
-                    Poll::Pending
-                }
-                // Some data sent
-                Ok(n) => Poll::Ready(Ok(n)),
-                // Connection reset. TODO: this can also be timeouts etc, investigate.
-                Err(tcp::SendError::InvalidState) => Poll::Ready(Err(Error::ConnectionReset)),
+            embassy_sync::coop::poll_with_budget(cx, |cx| {
+                self.with_mut(|s, _| match s.send_slice(buf) {
+                    // Not ready to send (no space in the tx buffer)
+                    Ok(0) => {
+                        s.register_send_waker(cx.waker());
+                        Poll::Pending
+                    }
+                    // Some data sent
+                    Ok(n) => Poll::Ready(Ok(n)),
+                    // Connection reset. TODO: this can also be timeouts etc, investigate.
+                    Err(tcp::SendError::InvalidState) => Poll::Ready(Err(Error::ConnectionReset)),
+                })
             })
         })
     }
@@ -561,23 +565,25 @@ impl<'d> TcpIo<'d> {
     {
         let mut f = Some(f);
         poll_fn(move |cx| {
-            self.with_mut(|s, _| {
-                if !s.can_send() {
-                    if s.may_send() {
-                        // socket buffer is full wait until it has atleast one byte free
-                        s.register_send_waker(cx.waker());
-                        Poll::Pending
+            embassy_sync::coop::poll_with_budget(cx, |cx| {
+                self.with_mut(|s, _| {
+                    if !s.can_send() {
+                        if s.may_send() {
+                            // socket buffer is full wait until it has atleast one byte free
+                            s.register_send_waker(cx.waker());
+                            Poll::Pending
+                        } else {
+                            // if we can't transmit because the transmit half of the duplex connection is closed then return an error
+                            Poll::Ready(Err(Error::ConnectionReset))
+                        }
                     } else {
-                        // if we can't transmit because the transmit half of the duplex connection is closed then return an error
-                        Poll::Ready(Err(Error::ConnectionReset))
+                        Poll::Ready(match s.send(unwrap!(f.take())) {
+                            // Connection reset. TODO: this can also be timeouts etc, investigate.
+                            Err(tcp::SendError::InvalidState) => Err(Error::ConnectionReset),
+                            Ok(r) => Ok(r),
+                        })
                     }
-                } else {
-                    Poll::Ready(match s.send(unwrap!(f.take())) {
-                        // Connection reset. TODO: this can also be timeouts etc, investigate.
-                        Err(tcp::SendError::InvalidState) => Err(Error::ConnectionReset),
-                        Ok(r) => Ok(r),
-                    })
-                }
+                })
             })
         })
         .await
@@ -589,25 +595,27 @@ impl<'d> TcpIo<'d> {
     {
         let mut f = Some(f);
         poll_fn(move |cx| {
-            self.with_mut(|s, _| {
-                if !s.can_recv() {
-                    if s.may_recv() {
-                        // socket buffer is empty wait until it has atleast one byte has arrived
-                        s.register_recv_waker(cx.waker());
-                        Poll::Pending
+            embassy_sync::coop::poll_with_budget(cx, |cx| {
+                self.with_mut(|s, _| {
+                    if !s.can_recv() {
+                        if s.may_recv() {
+                            // socket buffer is empty wait until it has atleast one byte has arrived
+                            s.register_recv_waker(cx.waker());
+                            Poll::Pending
+                        } else {
+                            // if we can't receive because the receive half of the duplex connection is closed then return an error
+                            Poll::Ready(Err(Error::ConnectionReset))
+                        }
                     } else {
-                        // if we can't receive because the receive half of the duplex connection is closed then return an error
-                        Poll::Ready(Err(Error::ConnectionReset))
+                        Poll::Ready(match s.recv(unwrap!(f.take())) {
+                            // Connection reset. TODO: this can also be timeouts etc, investigate.
+                            Err(tcp::RecvError::Finished) | Err(tcp::RecvError::InvalidState) => {
+                                Err(Error::ConnectionReset)
+                            }
+                            Ok(r) => Ok(r),
+                        })
                     }
-                } else {
-                    Poll::Ready(match s.recv(unwrap!(f.take())) {
-                        // Connection reset. TODO: this can also be timeouts etc, investigate.
-                        Err(tcp::RecvError::Finished) | Err(tcp::RecvError::InvalidState) => {
-                            Err(Error::ConnectionReset)
-                        }
-                        Ok(r) => Ok(r),
-                    })
-                }
+                })
             })
         })
         .await
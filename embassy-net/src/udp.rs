@@ -155,14 +155,16 @@ impl<'a> UdpSocket<'a> {
         buf: &mut [u8],
         cx: &mut Context<'_>,
     ) -> Poll<Result<(usize, UdpMetadata), RecvError>> {
-        self.with_mut(|s, _| match s.recv_slice(buf) {
-            Ok((n, meta)) => Poll::Ready(Ok((n, meta))),
-            // No data ready
-            Err(udp::RecvError::Truncated) => Poll::Ready(Err(RecvError::Truncated)),
-            Err(udp::RecvError::Exhausted) => {
-                s.register_recv_waker(cx.waker());
-                Poll::Pending
-            }
+        embassy_sync::coop::poll_with_budget(cx, |cx| {
+            self.with_mut(|s, _| match s.recv_slice(buf) {
+                Ok((n, meta)) => Poll::Ready(Ok((n, meta))),
+                // No data ready
+                Err(udp::RecvError::Truncated) => Poll::Ready(Err(RecvError::Truncated)),
+                Err(udp::RecvError::Exhausted) => {
+                    s.register_recv_waker(cx.waker());
+                    Poll::Pending
+                }
+            })
         })
     }
 
@@ -180,16 +182,18 @@ impl<'a> UdpSocket<'a> {
     {
         let mut f = Some(f);
         poll_fn(move |cx| {
-            self.with_mut(|s, _| {
-                match s.recv() {
-                    Ok((buffer, endpoint)) => Poll::Ready(unwrap!(f.take())(buffer, endpoint)),
-                    Err(udp::RecvError::Truncated) => unreachable!(),
-                    Err(udp::RecvError::Exhausted) => {
-                        // socket buffer is empty wait until at least one byte has arrived
-                        s.register_recv_waker(cx.waker());
-                        Poll::Pending
+            embassy_sync::coop::poll_with_budget(cx, |cx| {
+                self.with_mut(|s, _| {
+                    match s.recv() {
+                        Ok((buffer, endpoint)) => Poll::Ready(unwrap!(f.take())(buffer, endpoint)),
+                        Err(udp::RecvError::Truncated) => unreachable!(),
+                        Err(udp::RecvError::Exhausted) => {
+                            // socket buffer is empty wait until at least one byte has arrived
+                            s.register_recv_waker(cx.waker());
+                            Poll::Pending
+                        }
                     }
-                }
+                })
             })
         })
         .await
@@ -257,21 +261,23 @@ impl<'a> UdpSocket<'a> {
             return Poll::Ready(Err(SendError::PacketTooLarge));
         }
 
-        self.with_mut(|s, _| match s.send_slice(buf, remote_endpoint) {
-            // Entire datagram has been sent
-            Ok(()) => Poll::Ready(Ok(())),
-            Err(udp::SendError::BufferFull) => {
-                s.register_send_waker(cx.waker());
-                Poll::Pending
-            }
-            Err(udp::SendError::Unaddressable) => {
-                // If no sender/outgoing port is specified, there is not really "no route"
-                if s.endpoint().port == 0 {
-                    Poll::Ready(Err(SendError::SocketNotBound))
-                } else {
-                    Poll::Ready(Err(SendError::NoRoute))
+        embassy_sync::coop::poll_with_budget(cx, |cx| {
+            self.with_mut(|s, _| match s.send_slice(buf, remote_endpoint) {
+                // Entire datagram has been sent
+                Ok(()) => Poll::Ready(Ok(())),
+                Err(udp::SendError::BufferFull) => {
+                    s.register_send_waker(cx.waker());
+                    Poll::Pending
                 }
-            }
+                Err(udp::SendError::Unaddressable) => {
+                    // If no sender/outgoing port is specified, there is not really "no route"
+                    if s.endpoint().port == 0 {
+                        Poll::Ready(Err(SendError::SocketNotBound))
+                    } else {
+                        Poll::Ready(Err(SendError::NoRoute))
+                    }
+                }
+            })
         })
     }
 
@@ -296,22 +302,24 @@ impl<'a> UdpSocket<'a> {
 
         let mut f = Some(f);
         poll_fn(move |cx| {
-            self.with_mut(|s, _| {
-                match s.send(size, remote_endpoint) {
-                    Ok(buffer) => Poll::Ready(Ok(unwrap!(f.take())(buffer))),
-                    Err(udp::SendError::BufferFull) => {
-                        s.register_send_waker(cx.waker());
-                        Poll::Pending
-                    }
-                    Err(udp::SendError::Unaddressable) => {
-                        // If no sender/outgoing port is specified, there is not really "no route"
-                        if s.endpoint().port == 0 {
-                            Poll::Ready(Err(SendError::SocketNotBound))
-                        } else {
-                            Poll::Ready(Err(SendError::NoRoute))
+            embassy_sync::coop::poll_with_budget(cx, |cx| {
+                self.with_mut(|s, _| {
+                    match s.send(size, remote_endpoint) {
+                        Ok(buffer) => Poll::Ready(Ok(unwrap!(f.take())(buffer))),
+                        Err(udp::SendError::BufferFull) => {
+                            s.register_send_waker(cx.waker());
+                            Poll::Pending
+                        }
+                        Err(udp::SendError::Unaddressable) => {
+                            // If no sender/outgoing port is specified, there is not really "no route"
+                            if s.endpoint().port == 0 {
+                                Poll::Ready(Err(SendError::SocketNotBound))
+                            } else {
+                                Poll::Ready(Err(SendError::NoRoute))
+                            }
                         }
                     }
-                }
+                })
             })
         })
         .await
@@ -8,6 +8,11 @@
 //! Enable the `critical-section-impl` feature in embassy-rp when sharing data across cores using
 //! the `embassy-sync` primitives and `CriticalSectionRawMutex`.
 //!
+//! To balance tasks between the cores with an `embassy_executor::raw::SharedExecutor`, enable the
+//! `executor-shared-sio` feature of both embassy-rp and `embassy-executor`. The executor of each
+//! core then wakes up the other one by writing to the inter-core FIFO, whose interrupt this
+//! module handles on both cores.
+//!
 //! # Usage
 //!
 //! ```no_run
@@ -109,6 +114,13 @@ impl<const SIZE: usize> Stack<SIZE> {
     }
 }
 
+#[cfg(all(feature = "rt", feature = "rp2040", feature = "executor-shared-sio"))]
+#[interrupt]
+unsafe fn SIO_IRQ_PROC0() {
+    // Core 1 only writes to the FIFO to wake up core 0, and the interrupt already did.
+    fifo_drain_wake();
+}
+
 #[cfg(all(feature = "rt", feature = "rp2040"))]
 #[interrupt]
 unsafe fn SIO_IRQ_PROC1() {
@@ -136,6 +148,13 @@ unsafe fn SIO_IRQ_PROC1() {
 #[cfg(all(feature = "rt", feature = "_rp235x"))]
 #[interrupt]
 unsafe fn SIO_IRQ_FIFO() {
+    #[cfg(feature = "executor-shared-sio")]
+    if current_core() == CoreId::Core0 {
+        // Core 1 only writes to the FIFO to wake up core 0, and the interrupt already did.
+        fifo_drain_wake();
+        return;
+    }
+
     let sio = pac::SIO;
     // Clear IRQ
     sio.fifo().st().write(|w| w.set_wof(false));
@@ -285,23 +304,42 @@ where
 
     // Wait until the other core has copied `entry` before returning.
     fifo_read();
+
+    // Let core 1 wake up core 0 through the FIFO.
+    #[cfg(feature = "executor-shared-sio")]
+    {
+        #[cfg(feature = "rp2040")]
+        unsafe {
+            interrupt::SIO_IRQ_PROC0.enable()
+        };
+        #[cfg(feature = "_rp235x")]
+        unsafe {
+            interrupt::SIO_IRQ_FIFO.enable()
+        };
+    }
 }
 
 /// Pause execution on CORE1.
 pub fn pause_core1() {
     if IS_CORE1_INIT.load(Ordering::Acquire) {
-        fifo_write(PAUSE_TOKEN);
-        // Wait for CORE1 to signal it has paused execution.
-        while fifo_read() != PAUSE_TOKEN {}
+        // Keep the FIFO interrupt of core 0 from taking the reply.
+        cortex_m::interrupt::free(|_| {
+            fifo_write(PAUSE_TOKEN);
+            // Wait for CORE1 to signal it has paused execution.
+            while fifo_read() != PAUSE_TOKEN {}
+        })
     }
 }
 
 /// Resume CORE1 execution.
 pub fn resume_core1() {
     if IS_CORE1_INIT.load(Ordering::Acquire) {
-        fifo_write(RESUME_TOKEN);
-        // Wait for CORE1 to signal it has resumed execution.
-        while fifo_read() != RESUME_TOKEN {}
+        // Keep the FIFO interrupt of core 0 from taking the reply.
+        cortex_m::interrupt::free(|_| {
+            fifo_write(RESUME_TOKEN);
+            // Wait for CORE1 to signal it has resumed execution.
+            while fifo_read() != RESUME_TOKEN {}
+        })
     }
 }
 
@@ -342,6 +380,15 @@ fn fifo_read_wfe() -> u32 {
     sio.fifo().rd().read()
 }
 
+// Drain the wake-up values from the inter-core FIFO, in its interrupt
+#[cfg(all(feature = "rt", feature = "executor-shared-sio"))]
+fn fifo_drain_wake() {
+    let sio = pac::SIO;
+    // Clear IRQ
+    sio.fifo().st().write(|w| w.set_wof(false));
+    fifo_drain();
+}
+
 // Drain inter-core FIFO
 #[inline(always)]
 fn fifo_drain() {
@@ -3,8 +3,12 @@ use chrono::{Datelike, Timelike};
 use crate::pac::rtc::regs::{Rtc0, Rtc1, Setup0, Setup1};
 
 /// Alias for [`chrono::NaiveDateTime`]
+///
+/// Converts to and from [`embassy_time::DateTime`] with `TryFrom`.
 pub type DateTime = chrono::NaiveDateTime;
 /// Alias for [`chrono::Weekday`]
+///
+/// Converts to and from [`embassy_time::Weekday`] with `From`.
 pub type DayOfWeek = chrono::Weekday;
 
 /// Errors regarding the [`DateTime`] and [`DateTimeFilter`] structs.
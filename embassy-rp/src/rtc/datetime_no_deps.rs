@@ -58,6 +58,51 @@ pub enum DayOfWeek {
     Saturday = 6,
 }
 
+impl TryFrom<embassy_time::DateTime> for DateTime {
+    type Error = Error;
+
+    /// The sub-second part of the time is dropped.
+    fn try_from(date_time: embassy_time::DateTime) -> Result<Self, Error> {
+        let dt = DateTime {
+            year: u16::try_from(date_time.year).map_err(|_| Error::InvalidYear)?,
+            month: date_time.month,
+            day: date_time.day,
+            day_of_week: date_time.weekday().into(),
+            hour: date_time.hour,
+            minute: date_time.minute,
+            second: date_time.second,
+        };
+        validate_datetime(&dt)?;
+        Ok(dt)
+    }
+}
+
+impl From<DateTime> for embassy_time::DateTime {
+    fn from(date_time: DateTime) -> Self {
+        Self {
+            year: date_time.year as u32,
+            month: date_time.month,
+            day: date_time.day,
+            hour: date_time.hour,
+            minute: date_time.minute,
+            second: date_time.second,
+            microsecond: 0,
+        }
+    }
+}
+
+impl From<embassy_time::Weekday> for DayOfWeek {
+    fn from(weekday: embassy_time::Weekday) -> Self {
+        unwrap!(day_of_week_from_u8((weekday.days_from_monday() + 1) % 7))
+    }
+}
+
+impl From<DayOfWeek> for embassy_time::Weekday {
+    fn from(weekday: DayOfWeek) -> Self {
+        embassy_time::Weekday::from_days_from_monday(weekday as u8 + 6)
+    }
+}
+
 fn day_of_week_from_u8(v: u8) -> Result<DayOfWeek, Error> {
     Ok(match v {
         0 => DayOfWeek::Sunday,
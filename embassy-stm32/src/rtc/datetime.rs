@@ -159,6 +159,41 @@ impl From<DateTime> for chrono::NaiveDateTime {
     }
 }
 
+#[cfg(feature = "time")]
+impl TryFrom<embassy_time::DateTime> for DateTime {
+    type Error = Error;
+
+    fn try_from(date_time: embassy_time::DateTime) -> Result<Self, Error> {
+        let year = u16::try_from(date_time.year).map_err(|_| Error::InvalidYear)?;
+        let day_of_week = date_time.weekday().into();
+        Self::from(
+            year,
+            date_time.month,
+            date_time.day,
+            day_of_week,
+            date_time.hour,
+            date_time.minute,
+            date_time.second,
+            date_time.microsecond,
+        )
+    }
+}
+
+#[cfg(feature = "time")]
+impl From<DateTime> for embassy_time::DateTime {
+    fn from(date_time: DateTime) -> Self {
+        Self {
+            year: date_time.year as u32,
+            month: date_time.month,
+            day: date_time.day,
+            hour: date_time.hour,
+            minute: date_time.minute,
+            second: date_time.second,
+            microsecond: date_time.usecond,
+        }
+    }
+}
+
 /// A day of the week
 #[repr(u8)]
 #[derive(Copy, Clone, Debug, PartialEq, Eq, Ord, PartialOrd, Hash)]
@@ -196,6 +231,20 @@ impl From<DayOfWeek> for chrono::Weekday {
     }
 }
 
+#[cfg(feature = "time")]
+impl From<embassy_time::Weekday> for DayOfWeek {
+    fn from(weekday: embassy_time::Weekday) -> Self {
+        unwrap!(day_of_week_from_u8(weekday.days_from_monday() + 1))
+    }
+}
+
+#[cfg(feature = "time")]
+impl From<DayOfWeek> for embassy_time::Weekday {
+    fn from(weekday: DayOfWeek) -> Self {
+        embassy_time::Weekday::from_days_from_monday(weekday as u8 - 1)
+    }
+}
+
 pub(super) const fn day_of_week_from_u8(v: u8) -> Result<DayOfWeek, Error> {
     Ok(match v {
         1 => DayOfWeek::Monday,
@@ -4,4 +4,7 @@ mod common;
 fn main() {
     let mut cfgs = common::CfgSet::new();
     common::set_target_cfgs(&mut cfgs);
+
+    // Enabled through RUSTFLAGS for the model-checked tests in `tests/loom.rs`.
+    cfgs.declare("loom");
 }
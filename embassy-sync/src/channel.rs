@@ -569,7 +569,7 @@ where
     type Output = ();
 
     fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
-        match self.message.take() {
+        crate::coop::poll_with_budget(cx, |cx| match self.message.take() {
             Some(m) => match self.channel.try_send_with_context(m, Some(cx)) {
                 Ok(..) => Poll::Ready(()),
                 Err(TrySendError::Full(m)) => {
@@ -578,7 +578,7 @@ where
                 }
             },
             None => panic!("Message cannot be None"),
-        }
+        })
     }
 }
 
@@ -595,7 +595,7 @@ impl<'ch, T> Future for DynamicSendFuture<'ch, T> {
     type Output = ();
 
     fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
-        match self.message.take() {
+        crate::coop::poll_with_budget(cx, |cx| match self.message.take() {
             Some(m) => match self.channel.try_send_with_context(m, Some(cx)) {
                 Ok(..) => Poll::Ready(()),
                 Err(TrySendError::Full(m)) => {
@@ -604,7 +604,7 @@ impl<'ch, T> Future for DynamicSendFuture<'ch, T> {
                 }
             },
             None => panic!("Message cannot be None"),
-        }
+        })
     }
 }
 
@@ -835,7 +835,7 @@ where
 
     /// Poll the channel for the next message
     pub fn poll_receive(&self, cx: &mut Context<'_>) -> Poll<T> {
-        self.lock(|c| c.poll_receive(cx))
+        crate::coop::poll_with_budget(cx, |cx| self.lock(|c| c.poll_receive(cx)))
     }
 
     fn try_send_with_context(&self, m: T, cx: Option<&mut Context<'_>>) -> Result<(), TrySendError<T>> {
@@ -0,0 +1,200 @@
+//! Cooperative scheduling budget.
+//!
+//! A task receiving from a channel that's always full never has to wait, so it can keep other
+//! tasks of its executor from running. With the `coop` feature, the futures of this crate consume
+//! a unit of the current task's budget each time they're ready, and yield once it's exhausted.
+//! The budget is provided by the executor: with `embassy-executor`, enable its `coop` feature
+//! and see its `coop` module.
+//!
+//! Without the `coop` feature, [`poll_proceed()`] always lets the task proceed.
+//!
+//! Other crates can take part in budgeting by polling their operations with
+//! [`poll_with_budget()`]:
+//!
+//! ```rust
+//! use core::future::poll_fn;
+//! use core::task::Poll;
+//!
+//! async fn next_sample(source: &mut impl FnMut() -> Option<u16>) -> u16 {
+//!     poll_fn(|cx| {
+//!         embassy_sync::coop::poll_with_budget(cx, |_| match source() {
+//!             Some(sample) => Poll::Ready(sample),
+//!             // The budget is given back, waiting doesn't consume it.
+//!             None => Poll::Pending,
+//!         })
+//!     })
+//!     .await
+//! }
+//! ```
+//!
+//! [`poll_proceed()`] and [`RestoreOnPending::made_progress()`] give finer control, when a ready
+//! result doesn't always mean that progress was made.
+
+use core::future::poll_fn;
+use core::task::{Context, Poll, Waker, ready};
+
+#[cfg(feature = "coop")]
+unsafe extern "Rust" {
+    fn __embassy_coop_poll_proceed(waker: &Waker) -> bool;
+    fn __embassy_coop_refund(waker: &Waker);
+}
+
+/// Consume a unit of the current task's budget.
+///
+/// Returns `Poll::Pending` if the budget is exhausted, after waking the task so that it's polled
+/// again once other tasks had a chance to run. Call this before trying an operation, and return
+/// `Poll::Pending` if it is.
+///
+/// The unit is given back when the returned guard is dropped, unless
+/// [`RestoreOnPending::made_progress()`] is called, so that a future that isn't ready doesn't
+/// consume budget.
+///
+/// With the `coop` feature, this requires the executor to provide
+/// `extern "Rust" fn __embassy_coop_poll_proceed(waker: &Waker) -> bool` and
+/// `extern "Rust" fn __embassy_coop_refund(waker: &Waker)`, which `embassy-executor` does with
+/// its `coop` feature.
+#[inline]
+pub fn poll_proceed<'a>(cx: &mut Context<'a>) -> Poll<RestoreOnPending<'a>> {
+    #[cfg(feature = "coop")]
+    {
+        if !unsafe { __embassy_coop_poll_proceed(cx.waker()) } {
+            cx.waker().wake_by_ref();
+            return Poll::Pending;
+        }
+        Poll::Ready(RestoreOnPending {
+            waker: Some(cx.waker()),
+        })
+    }
+    #[cfg(not(feature = "coop"))]
+    {
+        let _ = cx;
+        Poll::Ready(RestoreOnPending { waker: None })
+    }
+}
+
+/// Gives back the unit of budget consumed by [`poll_proceed()`] when dropped, unless the operation
+/// made progress.
+#[must_use = "dropping the guard gives the budget back immediately"]
+#[derive(Debug)]
+pub struct RestoreOnPending<'a> {
+    waker: Option<&'a Waker>,
+}
+
+impl<'a> RestoreOnPending<'a> {
+    /// Keep the unit of budget consumed, the operation is done.
+    #[inline]
+    pub fn made_progress(mut self) {
+        self.waker = None;
+    }
+}
+
+impl<'a> Drop for RestoreOnPending<'a> {
+    #[inline]
+    fn drop(&mut self) {
+        #[cfg(feature = "coop")]
+        if let Some(waker) = self.waker {
+            unsafe { __embassy_coop_refund(waker) }
+        }
+    }
+}
+
+/// Poll an operation if the current task has budget left, consuming a unit of it if the
+/// operation is ready.
+///
+/// Returns `Poll::Pending` without calling `poll` if the budget is exhausted, see
+/// [`poll_proceed()`].
+#[inline]
+pub fn poll_with_budget<T>(cx: &mut Context<'_>, poll: impl FnOnce(&mut Context<'_>) -> Poll<T>) -> Poll<T> {
+    let coop = ready!(poll_proceed(cx));
+    let result = poll(cx);
+    if result.is_ready() {
+        coop.made_progress();
+    }
+    result
+}
+
+/// Consume a unit of the current task's budget, yielding if it's exhausted.
+///
+/// Use this in loops that may never wait otherwise.
+pub async fn consume_budget() {
+    poll_fn(|cx| poll_proceed(cx).map(RestoreOnPending::made_progress)).await
+}
+
+#[cfg(all(test, feature = "coop"))]
+mod tests {
+    use core::cell::Cell;
+    use core::pin::pin;
+    use core::task::Waker;
+    use std::future::Future;
+
+    use futures_test::task::new_count_waker;
+
+    use super::*;
+    use crate::blocking_mutex::raw::NoopRawMutex;
+    use crate::channel::Channel;
+
+    std::thread_local! {
+        // Tests run in parallel, only constrain the thread of the test setting a budget.
+        static BUDGET: Cell<Option<u8>> = const { Cell::new(None) };
+    }
+
+    #[unsafe(no_mangle)]
+    fn __embassy_coop_poll_proceed(_waker: &Waker) -> bool {
+        BUDGET.with(|b| match b.get() {
+            None => true,
+            Some(0) => false,
+            Some(n) => {
+                b.set(Some(n - 1));
+                true
+            }
+        })
+    }
+
+    #[unsafe(no_mangle)]
+    fn __embassy_coop_refund(_waker: &Waker) {
+        BUDGET.with(|b| b.set(b.get().map(|n| n + 1)))
+    }
+
+    #[test]
+    fn channel_yields_when_budget_exhausted() {
+        let channel = Channel::<NoopRawMutex, u32, 4>::new();
+        for i in 0..4 {
+            channel.try_send(i).unwrap();
+        }
+
+        let (waker, count) = new_count_waker();
+        let mut cx = Context::from_waker(&waker);
+        BUDGET.with(|b| b.set(Some(2)));
+
+        assert_eq!(pin!(channel.receive()).poll(&mut cx), Poll::Ready(0));
+        assert_eq!(pin!(channel.receive()).poll(&mut cx), Poll::Ready(1));
+        assert_eq!(pin!(channel.receive()).poll(&mut cx), Poll::Pending);
+        assert_eq!(count.get(), 1);
+
+        // The executor resets the budget on the next poll.
+        BUDGET.with(|b| b.set(Some(2)));
+        assert_eq!(pin!(channel.receive()).poll(&mut cx), Poll::Ready(2));
+        BUDGET.with(|b| b.set(None));
+    }
+
+    #[test]
+    fn pending_doesnt_consume_budget() {
+        let channel = Channel::<NoopRawMutex, u32, 4>::new();
+        let (waker, count) = new_count_waker();
+        let mut cx = Context::from_waker(&waker);
+        BUDGET.with(|b| b.set(Some(2)));
+
+        // Waiting on an empty channel any number of times doesn't exhaust the budget.
+        let mut receive = pin!(channel.receive());
+        for _ in 0..10 {
+            assert_eq!(receive.as_mut().poll(&mut cx), Poll::Pending);
+        }
+        assert_eq!(BUDGET.with(|b| b.get()), Some(2));
+        assert_eq!(count.get(), 0);
+
+        channel.try_send(0).unwrap();
+        assert_eq!(receive.poll(&mut cx), Poll::Ready(0));
+        assert_eq!(BUDGET.with(|b| b.get()), Some(1));
+        BUDGET.with(|b| b.set(None));
+    }
+}
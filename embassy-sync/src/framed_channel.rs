@@ -0,0 +1,795 @@
+//! A queue for sending variable-length byte messages between asynchronous tasks.
+//!
+//! Unlike [`Pipe`](crate::pipe::Pipe), message boundaries are preserved: every message sent is
+//! received as a whole. Unlike [`Channel`](crate::channel::Channel), messages don't need to be sized
+//! for the largest possible message. They are stored back to back, each prefixed with its length,
+//! in a single byte buffer of `N` bytes, so many small messages or a few large ones fit equally well.
+//!
+//! Messages are always stored contiguously, so they can be received without copying using
+//! [`receive_with`](FramedChannel::receive_with). When a message does not fit in the space left
+//! at the end of the buffer, it is placed at the start of the buffer instead and the space at the
+//! end is left unused until the receiver catches up.
+//!
+//! # Example
+//!
+//! ```rust
+//! use embassy_sync::blocking_mutex::raw::CriticalSectionRawMutex;
+//! use embassy_sync::framed_channel::FramedChannel;
+//!
+//! static LOG: FramedChannel<CriticalSectionRawMutex, 256> = FramedChannel::new();
+//!
+//! async fn producer() {
+//!     LOG.send(b"booting").await.unwrap();
+//!     LOG.send(b"link up").await.unwrap();
+//! }
+//!
+//! async fn consumer() {
+//!     loop {
+//!         let len = LOG.receive_with(|line| {
+//!             // `line` points into the channel's buffer, no copy was made.
+//!             line.len()
+//!         })
+//!         .await;
+//!     }
+//! }
+//! ```
+
+use core::cell::RefCell;
+use core::future::Future;
+use core::pin::Pin;
+use core::task::{Context, Poll};
+
+use crate::blocking_mutex::Mutex;
+use crate::blocking_mutex::raw::RawMutex;
+use crate::waitqueue::WakerRegistration;
+
+/// Size of the length prefix stored in front of every message.
+const HEADER_LEN: usize = 2;
+
+/// Send-only access to a [`FramedChannel`].
+#[derive(Debug)]
+pub struct Sender<'ch, M, const N: usize>
+where
+    M: RawMutex,
+{
+    channel: &'ch FramedChannel<M, N>,
+}
+
+impl<'ch, M, const N: usize> Clone for Sender<'ch, M, N>
+where
+    M: RawMutex,
+{
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<'ch, M, const N: usize> Copy for Sender<'ch, M, N> where M: RawMutex {}
+
+impl<'ch, M, const N: usize> Sender<'ch, M, N>
+where
+    M: RawMutex,
+{
+    /// Send a message, waiting until there is space for it.
+    ///
+    /// See [`FramedChannel::send()`]
+    pub fn send<'a>(&'a self, message: &'a [u8]) -> SendFuture<'a, M, N> {
+        self.channel.send(message)
+    }
+
+    /// Attempt to immediately send a message.
+    ///
+    /// See [`FramedChannel::try_send()`]
+    pub fn try_send(&self, message: &[u8]) -> Result<(), TrySendError> {
+        self.channel.try_send(message)
+    }
+
+    /// Returns the length of the largest message that fits in the channel.
+    ///
+    /// See [`FramedChannel::max_message_len()`]
+    pub const fn max_message_len(&self) -> usize {
+        self.channel.max_message_len()
+    }
+}
+
+/// Send-only access to a [`FramedChannel`] without knowing the buffer size.
+pub struct DynamicSender<'ch> {
+    channel: &'ch dyn DynamicFramedChannel,
+}
+
+impl<'ch> Clone for DynamicSender<'ch> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<'ch> Copy for DynamicSender<'ch> {}
+
+impl<'ch, M, const N: usize> From<Sender<'ch, M, N>> for DynamicSender<'ch>
+where
+    M: RawMutex,
+{
+    fn from(s: Sender<'ch, M, N>) -> Self {
+        Self { channel: s.channel }
+    }
+}
+
+impl<'ch> DynamicSender<'ch> {
+    /// Send a message, waiting until there is space for it.
+    ///
+    /// See [`FramedChannel::send()`]
+    pub fn send<'a>(&'a self, message: &'a [u8]) -> DynamicSendFuture<'a> {
+        DynamicSendFuture {
+            channel: self.channel,
+            message,
+        }
+    }
+
+    /// Attempt to immediately send a message.
+    ///
+    /// See [`FramedChannel::try_send()`]
+    pub fn try_send(&self, message: &[u8]) -> Result<(), TrySendError> {
+        self.channel.try_send_with_context(message, None)
+    }
+}
+
+/// Receive-only access to a [`FramedChannel`].
+#[derive(Debug)]
+pub struct Receiver<'ch, M, const N: usize>
+where
+    M: RawMutex,
+{
+    channel: &'ch FramedChannel<M, N>,
+}
+
+impl<'ch, M, const N: usize> Clone for Receiver<'ch, M, N>
+where
+    M: RawMutex,
+{
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<'ch, M, const N: usize> Copy for Receiver<'ch, M, N> where M: RawMutex {}
+
+impl<'ch, M, const N: usize> Receiver<'ch, M, N>
+where
+    M: RawMutex,
+{
+    /// Receive the next message, passing it to `f` without copying it out of the channel.
+    ///
+    /// See [`FramedChannel::receive_with()`]
+    pub fn receive_with<F, R>(&self, f: F) -> ReceiveWithFuture<'ch, M, N, F>
+    where
+        F: FnOnce(&[u8]) -> R,
+    {
+        self.channel.receive_with(f)
+    }
+
+    /// Attempt to immediately receive the next message, passing it to `f`.
+    ///
+    /// See [`FramedChannel::try_receive_with()`]
+    pub fn try_receive_with<R>(&self, f: impl FnOnce(&[u8]) -> R) -> Result<R, TryReceiveError> {
+        self.channel.try_receive_with(f)
+    }
+
+    /// Returns the number of messages currently in the channel.
+    ///
+    /// See [`FramedChannel::len()`]
+    pub fn len(&self) -> usize {
+        self.channel.len()
+    }
+
+    /// Returns whether the channel is empty.
+    ///
+    /// See [`FramedChannel::is_empty()`]
+    pub fn is_empty(&self) -> bool {
+        self.channel.is_empty()
+    }
+}
+
+/// Receive-only access to a [`FramedChannel`] without knowing the buffer size.
+pub struct DynamicReceiver<'ch> {
+    channel: &'ch dyn DynamicFramedChannel,
+}
+
+impl<'ch> Clone for DynamicReceiver<'ch> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<'ch> Copy for DynamicReceiver<'ch> {}
+
+impl<'ch, M, const N: usize> From<Receiver<'ch, M, N>> for DynamicReceiver<'ch>
+where
+    M: RawMutex,
+{
+    fn from(r: Receiver<'ch, M, N>) -> Self {
+        Self { channel: r.channel }
+    }
+}
+
+impl<'ch> DynamicReceiver<'ch> {
+    /// Receive the next message, passing it to `f` without copying it out of the channel.
+    ///
+    /// See [`FramedChannel::receive_with()`]
+    pub fn receive_with<F, R>(&self, f: F) -> DynamicReceiveWithFuture<'ch, F>
+    where
+        F: FnOnce(&[u8]) -> R,
+    {
+        DynamicReceiveWithFuture {
+            channel: self.channel,
+            f: Some(f),
+        }
+    }
+
+    /// Attempt to immediately receive the next message, passing it to `f`.
+    ///
+    /// See [`FramedChannel::try_receive_with()`]
+    pub fn try_receive_with<R>(&self, f: impl FnOnce(&[u8]) -> R) -> Result<R, TryReceiveError> {
+        receive_with(self.channel, &mut Some(f), None)
+    }
+}
+
+/// Future returned by [`FramedChannel::send`] and [`Sender::send`].
+#[must_use = "futures do nothing unless you `.await` or poll them"]
+pub struct SendFuture<'ch, M, const N: usize>
+where
+    M: RawMutex,
+{
+    channel: &'ch FramedChannel<M, N>,
+    message: &'ch [u8],
+}
+
+impl<'ch, M, const N: usize> Future for SendFuture<'ch, M, N>
+where
+    M: RawMutex,
+{
+    type Output = Result<(), MessageTooLarge>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        poll_send(self.channel, self.message, cx)
+    }
+}
+
+impl<'ch, M, const N: usize> Unpin for SendFuture<'ch, M, N> where M: RawMutex {}
+
+/// Future returned by [`DynamicSender::send`].
+#[must_use = "futures do nothing unless you `.await` or poll them"]
+pub struct DynamicSendFuture<'ch> {
+    channel: &'ch dyn DynamicFramedChannel,
+    message: &'ch [u8],
+}
+
+impl<'ch> Future for DynamicSendFuture<'ch> {
+    type Output = Result<(), MessageTooLarge>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        poll_send(self.channel, self.message, cx)
+    }
+}
+
+impl<'ch> Unpin for DynamicSendFuture<'ch> {}
+
+impl<'ch, M: RawMutex, const N: usize> From<SendFuture<'ch, M, N>> for DynamicSendFuture<'ch> {
+    fn from(value: SendFuture<'ch, M, N>) -> Self {
+        Self {
+            channel: value.channel,
+            message: value.message,
+        }
+    }
+}
+
+/// Future returned by [`FramedChannel::receive_with`] and [`Receiver::receive_with`].
+#[must_use = "futures do nothing unless you `.await` or poll them"]
+pub struct ReceiveWithFuture<'ch, M, const N: usize, F>
+where
+    M: RawMutex,
+{
+    channel: &'ch FramedChannel<M, N>,
+    f: Option<F>,
+}
+
+impl<'ch, M, const N: usize, F, R> Future for ReceiveWithFuture<'ch, M, N, F>
+where
+    M: RawMutex,
+    F: FnOnce(&[u8]) -> R,
+{
+    type Output = R;
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let this = &mut *self;
+        match receive_with(this.channel, &mut this.f, Some(cx)) {
+            Ok(r) => Poll::Ready(r),
+            Err(TryReceiveError::Empty) => Poll::Pending,
+        }
+    }
+}
+
+impl<'ch, M, const N: usize, F> Unpin for ReceiveWithFuture<'ch, M, N, F> where M: RawMutex {}
+
+/// Future returned by [`DynamicReceiver::receive_with`].
+#[must_use = "futures do nothing unless you `.await` or poll them"]
+pub struct DynamicReceiveWithFuture<'ch, F> {
+    channel: &'ch dyn DynamicFramedChannel,
+    f: Option<F>,
+}
+
+impl<'ch, F, R> Future for DynamicReceiveWithFuture<'ch, F>
+where
+    F: FnOnce(&[u8]) -> R,
+{
+    type Output = R;
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let this = &mut *self;
+        match receive_with(this.channel, &mut this.f, Some(cx)) {
+            Ok(r) => Poll::Ready(r),
+            Err(TryReceiveError::Empty) => Poll::Pending,
+        }
+    }
+}
+
+impl<'ch, F> Unpin for DynamicReceiveWithFuture<'ch, F> {}
+
+impl<'ch, M: RawMutex, const N: usize, F> From<ReceiveWithFuture<'ch, M, N, F>> for DynamicReceiveWithFuture<'ch, F> {
+    fn from(value: ReceiveWithFuture<'ch, M, N, F>) -> Self {
+        Self {
+            channel: value.channel,
+            f: value.f,
+        }
+    }
+}
+
+fn poll_send<C>(channel: &C, message: &[u8], cx: &mut Context<'_>) -> Poll<Result<(), MessageTooLarge>>
+where
+    C: DynamicFramedChannel + ?Sized,
+{
+    match channel.try_send_with_context(message, Some(cx)) {
+        Ok(()) => Poll::Ready(Ok(())),
+        Err(TrySendError::Full) => Poll::Pending,
+        Err(TrySendError::TooLarge) => Poll::Ready(Err(MessageTooLarge)),
+    }
+}
+
+/// Pass the next message to `f`, which is only taken out of its `Option` if there is a message.
+fn receive_with<C, F, R>(channel: &C, f: &mut Option<F>, cx: Option<&mut Context<'_>>) -> Result<R, TryReceiveError>
+where
+    C: DynamicFramedChannel + ?Sized,
+    F: FnOnce(&[u8]) -> R,
+{
+    let mut result = None;
+    channel.try_receive_with_context(&mut |message| result = Some(unwrap!(f.take())(message)), cx)?;
+    Ok(unwrap!(result))
+}
+
+pub(crate) trait DynamicFramedChannel {
+    fn try_send_with_context(&self, message: &[u8], cx: Option<&mut Context<'_>>) -> Result<(), TrySendError>;
+
+    /// Calls `f` exactly once with the next message if there is one.
+    fn try_receive_with_context(
+        &self,
+        f: &mut dyn FnMut(&[u8]),
+        cx: Option<&mut Context<'_>>,
+    ) -> Result<(), TryReceiveError>;
+}
+
+/// Error returned by [`send`](FramedChannel::send) when the message can never fit in the channel.
+#[derive(PartialEq, Eq, Clone, Copy, Debug)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct MessageTooLarge;
+
+/// Error returned by [`try_send`](FramedChannel::try_send).
+#[derive(PartialEq, Eq, Clone, Copy, Debug)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum TrySendError {
+    /// The message could not be sent because there currently is not enough contiguous space for it.
+    Full,
+    /// The message is longer than [`max_message_len`](FramedChannel::max_message_len), so it can
+    /// never be sent.
+    TooLarge,
+}
+
+/// Error returned by [`try_receive_with`](FramedChannel::try_receive_with).
+#[derive(PartialEq, Eq, Clone, Copy, Debug)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum TryReceiveError {
+    /// A message could not be received because the channel is empty.
+    Empty,
+}
+
+#[derive(Debug)]
+struct FramedChannelState<const N: usize> {
+    buf: [u8; N],
+    /// Start of the oldest message.
+    read: usize,
+    /// End of the newest message.
+    write: usize,
+    /// Whether writing wrapped around to the start of the buffer, while older messages are still
+    /// stored at the end of it.
+    wrapped: bool,
+    /// End of the messages at the end of the buffer, only valid while `wrapped`.
+    watermark: usize,
+    /// Number of messages stored.
+    len: usize,
+    receiver_waker: WakerRegistration,
+    senders_waker: WakerRegistration,
+}
+
+impl<const N: usize> FramedChannelState<N> {
+    const MAX_MESSAGE_LEN: usize = {
+        let max = N.saturating_sub(HEADER_LEN);
+        if max > u16::MAX as usize {
+            u16::MAX as usize
+        } else {
+            max
+        }
+    };
+
+    const fn new() -> Self {
+        Self {
+            buf: [0; N],
+            read: 0,
+            write: 0,
+            wrapped: false,
+            watermark: 0,
+            len: 0,
+            receiver_waker: WakerRegistration::new(),
+            senders_waker: WakerRegistration::new(),
+        }
+    }
+
+    fn try_send_with_context(&mut self, message: &[u8], cx: Option<&mut Context<'_>>) -> Result<(), TrySendError> {
+        let frame_len = HEADER_LEN + message.len();
+        if message.len() > Self::MAX_MESSAGE_LEN || frame_len > N {
+            return Err(TrySendError::TooLarge);
+        }
+
+        let start = if self.wrapped {
+            (self.read - self.write >= frame_len).then_some(self.write)
+        } else if N - self.write >= frame_len {
+            Some(self.write)
+        } else if self.read >= frame_len {
+            // Not enough space left at the end, continue at the start of the buffer.
+            self.watermark = self.write;
+            self.wrapped = true;
+            Some(0)
+        } else {
+            None
+        };
+
+        let Some(start) = start else {
+            if let Some(cx) = cx {
+                self.senders_waker.register(cx.waker());
+            }
+            return Err(TrySendError::Full);
+        };
+
+        let frame = &mut self.buf[start..start + frame_len];
+        frame[..HEADER_LEN].copy_from_slice(&(message.len() as u16).to_le_bytes());
+        frame[HEADER_LEN..].copy_from_slice(message);
+        self.write = start + frame_len;
+        self.len += 1;
+        self.receiver_waker.wake();
+        Ok(())
+    }
+
+    fn try_receive_with_context(
+        &mut self,
+        f: &mut dyn FnMut(&[u8]),
+        cx: Option<&mut Context<'_>>,
+    ) -> Result<(), TryReceiveError> {
+        if self.len == 0 {
+            if let Some(cx) = cx {
+                self.receiver_waker.register(cx.waker());
+            }
+            return Err(TryReceiveError::Empty);
+        }
+
+        let message_len = u16::from_le_bytes([self.buf[self.read], self.buf[self.read + 1]]) as usize;
+        let start = self.read + HEADER_LEN;
+        f(&self.buf[start..start + message_len]);
+
+        self.read = start + message_len;
+        self.len -= 1;
+        if self.wrapped && self.read == self.watermark {
+            self.read = 0;
+            self.wrapped = false;
+        }
+        if self.len == 0 {
+            // Start over at the beginning to leave as much contiguous space as possible.
+            self.read = 0;
+            self.write = 0;
+        }
+        self.senders_waker.wake();
+        Ok(())
+    }
+
+    fn clear(&mut self) {
+        if self.len > 0 {
+            self.senders_waker.wake();
+        }
+        self.read = 0;
+        self.write = 0;
+        self.wrapped = false;
+        self.len = 0;
+    }
+}
+
+/// A bounded queue of variable-length byte messages, stored in a fixed buffer of `N` bytes.
+///
+/// Every message takes up its length plus a two byte length prefix. Messages are received in the
+/// order they were sent, and each message is received by a single receiver.
+///
+/// See the [module-level documentation](self) for more details.
+#[derive(Debug)]
+pub struct FramedChannel<M, const N: usize>
+where
+    M: RawMutex,
+{
+    inner: Mutex<M, RefCell<FramedChannelState<N>>>,
+}
+
+impl<M, const N: usize> FramedChannel<M, N>
+where
+    M: RawMutex,
+{
+    /// Establish a new framed channel with a buffer of `N` bytes. For example, to create one with a NoopMutex:
+    ///
+    /// ```
+    /// use embassy_sync::blocking_mutex::raw::NoopRawMutex;
+    /// use embassy_sync::framed_channel::FramedChannel;
+    ///
+    /// // Declare a framed channel storing up to 128 bytes of messages, including their length prefixes.
+    /// let channel = FramedChannel::<NoopRawMutex, 128>::new();
+    /// ```
+    ///
+    /// # Panics
+    ///
+    /// Panics if `N` is too small to hold the length prefix of a message, which is a compile-time
+    /// error when the channel is created in a `static` or `const`.
+    pub const fn new() -> Self {
+        assert!(N >= HEADER_LEN, "FramedChannel buffer is too small to hold a message");
+        Self {
+            inner: Mutex::new(RefCell::new(FramedChannelState::new())),
+        }
+    }
+
+    fn lock<R>(&self, f: impl FnOnce(&mut FramedChannelState<N>) -> R) -> R {
+        self.inner.lock(|rc| f(&mut *unwrap!(rc.try_borrow_mut())))
+    }
+
+    /// Get a sender for this channel.
+    pub fn sender(&self) -> Sender<'_, M, N> {
+        Sender { channel: self }
+    }
+
+    /// Get a receiver for this channel.
+    pub fn receiver(&self) -> Receiver<'_, M, N> {
+        Receiver { channel: self }
+    }
+
+    /// Get a sender for this channel using dynamic dispatch.
+    pub fn dyn_sender(&self) -> DynamicSender<'_> {
+        DynamicSender { channel: self }
+    }
+
+    /// Get a receiver for this channel using dynamic dispatch.
+    pub fn dyn_receiver(&self) -> DynamicReceiver<'_> {
+        DynamicReceiver { channel: self }
+    }
+
+    /// Send a message, waiting until there is space for it.
+    ///
+    /// The message is copied into the channel's buffer. Sending completes when the message has been
+    /// stored, this doesn't mean it has been received yet.
+    ///
+    /// Returns an error right away if the message is longer than [`max_message_len`](Self::max_message_len),
+    /// as it would never fit.
+    pub fn send<'a>(&'a self, message: &'a [u8]) -> SendFuture<'a, M, N> {
+        SendFuture { channel: self, message }
+    }
+
+    /// Attempt to immediately send a message.
+    ///
+    /// This method differs from [`send`](FramedChannel::send) by returning immediately if there
+    /// currently is not enough contiguous space in the buffer, instead of waiting.
+    pub fn try_send(&self, message: &[u8]) -> Result<(), TrySendError> {
+        self.lock(|c| c.try_send_with_context(message, None))
+    }
+
+    /// Receive the next message, passing it to `f` without copying it out of the channel.
+    ///
+    /// If there are no messages in the channel, this waits for one to be sent. The message is removed
+    /// from the channel once `f` returns, and the future resolves to whatever `f` returned.
+    ///
+    /// `f` runs while the channel is locked, so it should be kept short. In particular it must not
+    /// access the channel itself.
+    pub fn receive_with<F, R>(&self, f: F) -> ReceiveWithFuture<'_, M, N, F>
+    where
+        F: FnOnce(&[u8]) -> R,
+    {
+        ReceiveWithFuture {
+            channel: self,
+            f: Some(f),
+        }
+    }
+
+    /// Attempt to immediately receive the next message, passing it to `f`.
+    ///
+    /// This method differs from [`receive_with`](FramedChannel::receive_with) by returning
+    /// immediately if the channel is empty, instead of waiting.
+    pub fn try_receive_with<R>(&self, f: impl FnOnce(&[u8]) -> R) -> Result<R, TryReceiveError> {
+        receive_with(self, &mut Some(f), None)
+    }
+
+    /// Returns the size of the buffer in bytes, including the length prefixes of the messages.
+    pub const fn capacity(&self) -> usize {
+        N
+    }
+
+    /// Returns the length of the largest message that fits in the channel.
+    pub const fn max_message_len(&self) -> usize {
+        FramedChannelState::<N>::MAX_MESSAGE_LEN
+    }
+
+    /// Removes all messages from the channel.
+    pub fn clear(&self) {
+        self.lock(|c| c.clear());
+    }
+
+    /// Returns the number of messages currently in the channel.
+    pub fn len(&self) -> usize {
+        self.lock(|c| c.len)
+    }
+
+    /// Returns whether the channel is empty.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+/// Implements the DynamicFramedChannel to allow creating types that are unaware of the buffer size
+/// with the concrete FramedChannel. It can be used to create dynamic senders and receivers.
+impl<M, const N: usize> DynamicFramedChannel for FramedChannel<M, N>
+where
+    M: RawMutex,
+{
+    fn try_send_with_context(&self, message: &[u8], cx: Option<&mut Context<'_>>) -> Result<(), TrySendError> {
+        self.lock(|c| c.try_send_with_context(message, cx))
+    }
+
+    fn try_receive_with_context(
+        &self,
+        f: &mut dyn FnMut(&[u8]),
+        cx: Option<&mut Context<'_>>,
+    ) -> Result<(), TryReceiveError> {
+        self.lock(|c| c.try_receive_with_context(f, cx))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use core::pin::pin;
+    use std::vec::Vec;
+
+    use futures_util::poll;
+
+    use super::*;
+    use crate::blocking_mutex::raw::NoopRawMutex;
+
+    fn receive<const N: usize>(channel: &FramedChannel<NoopRawMutex, N>) -> Result<Vec<u8>, TryReceiveError> {
+        channel.try_receive_with(|message| message.to_vec())
+    }
+
+    #[test]
+    fn send_and_receive() {
+        let c = FramedChannel::<NoopRawMutex, 16>::new();
+        assert!(c.is_empty());
+        c.try_send(b"hello").unwrap();
+        c.try_send(b"").unwrap();
+        c.try_send(b"abc").unwrap();
+        assert_eq!(c.len(), 3);
+
+        assert_eq!(receive(&c).unwrap(), b"hello");
+        assert_eq!(receive(&c).unwrap(), b"");
+        assert_eq!(receive(&c).unwrap(), b"abc");
+        assert_eq!(receive(&c), Err(TryReceiveError::Empty));
+    }
+
+    #[test]
+    fn sending_when_full() {
+        let c = FramedChannel::<NoopRawMutex, 10>::new();
+        assert_eq!(c.max_message_len(), 8);
+        assert_eq!(c.try_send(&[0; 9]), Err(TrySendError::TooLarge));
+
+        c.try_send(&[1; 4]).unwrap();
+        assert_eq!(c.try_send(&[2; 3]), Err(TrySendError::Full));
+        c.try_send(&[2; 2]).unwrap();
+        assert_eq!(c.try_send(&[]), Err(TrySendError::Full));
+
+        assert_eq!(receive(&c).unwrap(), [1; 4]);
+        assert_eq!(receive(&c).unwrap(), [2; 2]);
+        // Once empty, the whole buffer is available again.
+        c.try_send(&[3; 8]).unwrap();
+        assert_eq!(receive(&c).unwrap(), [3; 8]);
+    }
+
+    #[test]
+    fn only_empty_messages_fit_the_header() {
+        let c = FramedChannel::<NoopRawMutex, 2>::new();
+        assert_eq!(c.max_message_len(), 0);
+        assert_eq!(c.try_send(&[0]), Err(TrySendError::TooLarge));
+        c.try_send(&[]).unwrap();
+        assert_eq!(receive(&c).unwrap(), b"");
+    }
+
+    #[test]
+    #[should_panic(expected = "too small")]
+    fn buffer_smaller_than_header() {
+        let _ = FramedChannel::<NoopRawMutex, 1>::new();
+    }
+
+    #[test]
+    fn messages_wrap_around_contiguously() {
+        let c = FramedChannel::<NoopRawMutex, 12>::new();
+        c.try_send(&[1; 3]).unwrap();
+        c.try_send(&[2; 3]).unwrap();
+        assert_eq!(receive(&c).unwrap(), [1; 3]);
+
+        // Only 2 bytes are left at the end, so this message goes to the start of the buffer.
+        c.try_send(&[3; 3]).unwrap();
+        assert_eq!(c.try_send(&[4; 1]), Err(TrySendError::Full));
+        assert_eq!(receive(&c).unwrap(), [2; 3]);
+        c.try_send(&[4; 5]).unwrap();
+
+        assert_eq!(receive(&c).unwrap(), [3; 3]);
+        assert_eq!(receive(&c).unwrap(), [4; 5]);
+        assert!(c.is_empty());
+    }
+
+    #[test]
+    fn clear() {
+        let c = FramedChannel::<NoopRawMutex, 8>::new();
+        c.try_send(&[1; 6]).unwrap();
+        assert_eq!(c.try_send(&[]), Err(TrySendError::Full));
+        c.clear();
+        assert!(c.is_empty());
+        c.try_send(&[2; 6]).unwrap();
+        assert_eq!(receive(&c).unwrap(), [2; 6]);
+    }
+
+    #[test]
+    fn dynamic_dispatch() {
+        let c = FramedChannel::<NoopRawMutex, 16>::new();
+        let s: DynamicSender<'_> = c.sender().into();
+        let r: DynamicReceiver<'_> = c.receiver().into();
+
+        s.try_send(b"abc").unwrap();
+        assert_eq!(r.try_receive_with(|message| message.len()), Ok(3));
+        assert_eq!(c.dyn_receiver().try_receive_with(|_| ()), Err(TryReceiveError::Empty));
+    }
+
+    #[futures_test::test]
+    async fn send_waits_for_space() {
+        let c = FramedChannel::<NoopRawMutex, 8>::new();
+        c.send(&[1; 4]).await.unwrap();
+        assert_eq!(c.send(&[0; 7]).await, Err(MessageTooLarge));
+
+        let mut send = pin!(c.send(&[2; 4]));
+        assert!(poll!(send.as_mut()).is_pending());
+        assert_eq!(c.receiver().receive_with(|message| message.to_vec()).await, [1; 4]);
+        assert_eq!(poll!(send.as_mut()), Poll::Ready(Ok(())));
+    }
+
+    #[futures_test::test]
+    async fn receive_waits_for_message() {
+        let c = FramedChannel::<NoopRawMutex, 8>::new();
+        let mut receive = pin!(c.dyn_receiver().receive_with(|message| message[0]));
+        assert!(poll!(receive.as_mut()).is_pending());
+        c.dyn_sender().send(&[7]).await.unwrap();
+        assert_eq!(poll!(receive.as_mut()), Poll::Ready(7));
+    }
+}
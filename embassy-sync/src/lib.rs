@@ -5,6 +5,10 @@
 #![doc = include_str!("../README.md")]
 #![warn(missing_docs)]
 
+// The tests use `std`, while the library itself stays `no_std`.
+#[cfg(test)]
+extern crate std;
+
 // This mod MUST go first, so that the others see its macros.
 pub(crate) mod fmt;
 
@@ -13,12 +17,16 @@ mod ring_buffer;
 
 pub mod blocking_mutex;
 pub mod channel;
+pub mod coop;
+pub mod framed_channel;
 pub mod lazy_lock;
 pub mod mutex;
 pub mod once_lock;
 pub mod pipe;
 pub mod priority_channel;
 pub mod pubsub;
+#[cfg(feature = "time")]
+pub mod rate_limit;
 pub mod rwlock;
 pub mod semaphore;
 pub mod signal;
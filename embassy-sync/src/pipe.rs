@@ -69,10 +69,10 @@ where
     type Output = usize;
 
     fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
-        match self.pipe.try_write_with_context(Some(cx), self.buf) {
+        crate::coop::poll_with_budget(cx, |cx| match self.pipe.try_write_with_context(Some(cx), self.buf) {
             Ok(n) => Poll::Ready(n),
             Err(TryWriteError::Full) => Poll::Pending,
-        }
+        })
     }
 }
 
@@ -147,10 +147,10 @@ where
     type Output = usize;
 
     fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
-        match self.pipe.try_read_with_context(Some(cx), self.buf) {
+        crate::coop::poll_with_budget(cx, |cx| match self.pipe.try_read_with_context(Some(cx), self.buf) {
             Ok(n) => Poll::Ready(n),
             Err(TryReadError::Empty) => Poll::Pending,
-        }
+        })
     }
 }
 
@@ -173,14 +173,16 @@ where
     type Output = &'p [u8];
 
     fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
-        let pipe = self.pipe.take().unwrap();
-        match unsafe { pipe.try_fill_buf_with_context(Some(cx)) } {
-            Ok(buf) => Poll::Ready(buf),
-            Err(TryReadError::Empty) => {
-                self.pipe = Some(pipe);
-                Poll::Pending
+        crate::coop::poll_with_budget(cx, |cx| {
+            let pipe = self.pipe.take().unwrap();
+            match unsafe { pipe.try_fill_buf_with_context(Some(cx)) } {
+                Ok(buf) => Poll::Ready(buf),
+                Err(TryReadError::Empty) => {
+                    self.pipe = Some(pipe);
+                    Poll::Pending
+                }
             }
-        }
+        })
     }
 }
 
@@ -644,10 +646,10 @@ impl<'p> Future for DynamicWriteFuture<'p> {
     type Output = usize;
 
     fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
-        match self.pipe.try_write_with_context(Some(cx), self.buf) {
+        crate::coop::poll_with_budget(cx, |cx| match self.pipe.try_write_with_context(Some(cx), self.buf) {
             Ok(n) => Poll::Ready(n),
             Err(TryWriteError::Full) => Poll::Pending,
-        }
+        })
     }
 }
 
@@ -729,10 +731,10 @@ impl<'p> Future for DynamicReadFuture<'p> {
     type Output = usize;
 
     fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
-        match self.pipe.try_read_with_context(Some(cx), self.buf) {
+        crate::coop::poll_with_budget(cx, |cx| match self.pipe.try_read_with_context(Some(cx), self.buf) {
             Ok(n) => Poll::Ready(n),
             Err(TryReadError::Empty) => Poll::Pending,
-        }
+        })
     }
 }
 
@@ -760,14 +762,16 @@ impl<'p> Future for DynamicFillBufFuture<'p> {
     type Output = &'p [u8];
 
     fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
-        let pipe = self.pipe.take().unwrap();
-        match unsafe { pipe.try_fill_buf_with_context(Some(cx)) } {
-            Ok(buf) => Poll::Ready(buf),
-            Err(TryReadError::Empty) => {
-                self.pipe = Some(pipe);
-                Poll::Pending
+        crate::coop::poll_with_budget(cx, |cx| {
+            let pipe = self.pipe.take().unwrap();
+            match unsafe { pipe.try_fill_buf_with_context(Some(cx)) } {
+                Ok(buf) => Poll::Ready(buf),
+                Err(TryReadError::Empty) => {
+                    self.pipe = Some(pipe);
+                    Poll::Pending
+                }
             }
-        }
+        })
     }
 }
 
@@ -309,7 +309,7 @@ where
     type Output = ();
 
     fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
-        match self.message.take() {
+        crate::coop::poll_with_budget(cx, |cx| match self.message.take() {
             Some(m) => match self.channel.try_send_with_context(m, Some(cx)) {
                 Ok(..) => Poll::Ready(()),
                 Err(TrySendError::Full(m)) => {
@@ -318,7 +318,7 @@ where
                 }
             },
             None => panic!("Message cannot be None"),
-        }
+        })
     }
 }
 
@@ -522,7 +522,7 @@ where
 
     /// Poll the channel for the next message
     pub fn poll_receive(&self, cx: &mut Context<'_>) -> Poll<T> {
-        self.lock(|c| c.poll_receive(cx))
+        crate::coop::poll_with_budget(cx, |cx| self.lock(|c| c.poll_receive(cx)))
     }
 
     fn try_send_with_context(&self, m: T, cx: Option<&mut Context<'_>>) -> Result<(), TrySendError<T>> {
@@ -4,9 +4,9 @@
 
 use core::cell::RefCell;
 use core::fmt::Debug;
-use core::task::{Context, Poll};
+use core::task::{Context, Poll, Waker};
 
-use heapless::Deque;
+use heapless::{Deque, Vec};
 
 use self::publisher::{ImmediatePub, Pub};
 use self::subscriber::Sub;
@@ -71,6 +71,40 @@ pub use subscriber::{DynSubscriber, Subscriber};
 /// # block_on(test);
 /// ```
 ///
+/// ## Replay and filtering
+///
+/// A channel created with [`PubSubChannel::with_replay()`] keeps the most recent messages around
+/// even after all subscribers have read them, so that subscribers created later still receive them.
+///
+/// Subscribers created with [`PubSubChannel::subscriber_with_filter()`] only receive the messages
+/// matching their filter, and are only woken when such a message is published.
+///
+/// ```
+/// # use embassy_sync::blocking_mutex::raw::NoopRawMutex;
+/// # use embassy_sync::pubsub::PubSubChannel;
+/// # use futures_executor::block_on;
+/// # let test = async {
+/// // Keep the last 2 messages for late subscribers
+/// let channel = PubSubChannel::<NoopRawMutex, u32, 4, 4, 4>::with_replay(2);
+/// let pub0 = channel.publisher().unwrap();
+///
+/// pub0.publish(1).await;
+/// pub0.publish(2).await;
+/// pub0.publish(3).await;
+///
+/// // A subscriber created now still receives the last 2 messages
+/// let mut sub0 = channel.subscriber().unwrap();
+/// assert_eq!(sub0.next_message_pure().await, 2);
+/// assert_eq!(sub0.next_message_pure().await, 3);
+///
+/// // A filtered subscriber only sees (and is only woken by) matching messages
+/// let mut sub1 = channel.subscriber_with_filter(|m| m % 2 == 0).unwrap();
+/// assert_eq!(sub1.next_message_pure().await, 2);
+/// assert_eq!(sub1.try_next_message(), None);
+/// # };
+/// #
+/// # block_on(test);
+/// ```
 #[derive(Debug)]
 pub struct PubSubChannel<M: RawMutex, T: Clone, const CAP: usize, const SUBS: usize, const PUBS: usize> {
     inner: Mutex<M, RefCell<PubSubState<T, CAP, SUBS, PUBS>>>,
@@ -81,39 +115,75 @@ impl<M: RawMutex, T: Clone, const CAP: usize, const SUBS: usize, const PUBS: usi
 {
     /// Create a new channel
     pub const fn new() -> Self {
+        Self::with_replay(0)
+    }
+
+    /// Create a new channel that keeps the `replay` most recent messages for subscribers created later.
+    ///
+    /// Messages kept only for replay don't block publishers: they are dropped to make room for new messages.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `replay` is larger than `CAP`.
+    pub const fn with_replay(replay: usize) -> Self {
+        core::assert!(replay <= CAP, "replay must not be larger than the channel capacity");
         Self {
-            inner: Mutex::const_new(M::INIT, RefCell::new(PubSubState::new())),
+            inner: Mutex::const_new(M::INIT, RefCell::new(PubSubState::new(replay))),
         }
     }
 
-    /// Create a new subscriber. It will only receive messages that are published after its creation.
+    /// Create a new subscriber. It will only receive messages that are published after its creation,
+    /// and the messages kept for replay (see [`Self::with_replay()`]).
     ///
     /// If there are no subscriber slots left, an error will be returned.
     pub fn subscriber(&self) -> Result<Subscriber<'_, M, T, CAP, SUBS, PUBS>, Error> {
-        self.inner.lock(|inner| {
-            let mut s = inner.borrow_mut();
-
-            if s.subscriber_count >= SUBS {
-                Err(Error::MaximumSubscribersReached)
-            } else {
-                s.subscriber_count += 1;
-                Ok(Subscriber(Sub::new(s.next_message_id, self)))
-            }
-        })
+        self.register_subscriber()
+            .map(|next_message_id| Subscriber(Sub::new(next_message_id, None, self)))
     }
 
-    /// Create a new subscriber. It will only receive messages that are published after its creation.
+    /// Create a new subscriber. It will only receive messages that are published after its creation,
+    /// and the messages kept for replay (see [`Self::with_replay()`]).
     ///
     /// If there are no subscriber slots left, an error will be returned.
     pub fn dyn_subscriber(&self) -> Result<DynSubscriber<'_, T>, Error> {
+        self.register_subscriber()
+            .map(|next_message_id| DynSubscriber(Sub::new(next_message_id, None, self)))
+    }
+
+    /// Create a new subscriber that only receives the messages for which `filter` returns `true`.
+    ///
+    /// The subscriber is not woken for messages that don't match its filter. Other than that,
+    /// it behaves like a subscriber created with [`Self::subscriber()`].
+    ///
+    /// If there are no subscriber slots left, an error will be returned.
+    pub fn subscriber_with_filter(
+        &self,
+        filter: fn(&T) -> bool,
+    ) -> Result<Subscriber<'_, M, T, CAP, SUBS, PUBS>, Error> {
+        self.register_subscriber()
+            .map(|next_message_id| Subscriber(Sub::new(next_message_id, Some(filter), self)))
+    }
+
+    /// Create a new subscriber that only receives the messages for which `filter` returns `true`.
+    ///
+    /// The subscriber is not woken for messages that don't match its filter. Other than that,
+    /// it behaves like a subscriber created with [`Self::dyn_subscriber()`].
+    ///
+    /// If there are no subscriber slots left, an error will be returned.
+    pub fn dyn_subscriber_with_filter(&self, filter: fn(&T) -> bool) -> Result<DynSubscriber<'_, T>, Error> {
+        self.register_subscriber()
+            .map(|next_message_id| DynSubscriber(Sub::new(next_message_id, Some(filter), self)))
+    }
+
+    /// Take a subscriber slot, returning the id of the first message the new subscriber should receive.
+    fn register_subscriber(&self) -> Result<u64, Error> {
         self.inner.lock(|inner| {
             let mut s = inner.borrow_mut();
 
             if s.subscriber_count >= SUBS {
                 Err(Error::MaximumSubscribersReached)
             } else {
-                s.subscriber_count += 1;
-                Ok(DynSubscriber(Sub::new(s.next_message_id, self)))
+                Ok(s.register_subscriber())
             }
         })
     }
@@ -217,12 +287,17 @@ impl<M: RawMutex, T: Clone, const CAP: usize, const SUBS: usize, const PUBS: usi
 impl<M: RawMutex, T: Clone, const CAP: usize, const SUBS: usize, const PUBS: usize> SealedPubSubBehavior<T>
     for PubSubChannel<M, T, CAP, SUBS, PUBS>
 {
-    fn get_message_with_context(&self, next_message_id: &mut u64, cx: Option<&mut Context<'_>>) -> Poll<WaitResult<T>> {
+    fn get_message_with_context(
+        &self,
+        next_message_id: &mut u64,
+        filter: Option<fn(&T) -> bool>,
+        cx: Option<&mut Context<'_>>,
+    ) -> Poll<WaitResult<T>> {
         self.inner.lock(|s| {
             let mut s = s.borrow_mut();
 
             // Check if we can read a message
-            match s.get_message(*next_message_id) {
+            match s.get_message(next_message_id, filter) {
                 // Yes, so we are done polling
                 Some(WaitResult::Message(message)) => {
                     *next_message_id += 1;
@@ -231,7 +306,10 @@ impl<M: RawMutex, T: Clone, const CAP: usize, const SUBS: usize, const PUBS: usi
                 // No, so we need to reregister our waker and sleep again
                 None => {
                     if let Some(cx) = cx {
-                        s.subscriber_wakers.register(cx.waker());
+                        match filter {
+                            Some(filter) => s.filtered_subscriber_wakers.register(cx.waker(), filter),
+                            None => s.subscriber_wakers.register(cx.waker()),
+                        }
                     }
                     Poll::Pending
                 }
@@ -308,43 +386,78 @@ struct PubSubState<T: Clone, const CAP: usize, const SUBS: usize, const PUBS: us
     next_message_id: u64,
     /// Collection of wakers for Subscribers that are waiting.  
     subscriber_wakers: MultiWakerRegistration<SUBS>,
+    /// Collection of wakers for filtered Subscribers that are waiting, only woken for matching messages.
+    filtered_subscriber_wakers: FilteredWakerRegistration<T, SUBS>,
     /// Collection of wakers for Publishers that are waiting.  
     publisher_wakers: MultiWakerRegistration<PUBS>,
     /// The amount of subscribers that are active
     subscriber_count: usize,
     /// The amount of publishers that are active
     publisher_count: usize,
+    /// The amount of most recent messages kept for subscribers created later
+    replay: usize,
 }
 
 impl<T: Clone, const CAP: usize, const SUBS: usize, const PUBS: usize> PubSubState<T, CAP, SUBS, PUBS> {
     /// Create a new internal channel state
-    const fn new() -> Self {
+    const fn new(replay: usize) -> Self {
         Self {
             queue: Deque::new(),
             next_message_id: 0,
             subscriber_wakers: MultiWakerRegistration::new(),
+            filtered_subscriber_wakers: FilteredWakerRegistration::new(),
             publisher_wakers: MultiWakerRegistration::new(),
             subscriber_count: 0,
             publisher_count: 0,
+            replay,
         }
     }
 
+    fn register_subscriber(&mut self) -> u64 {
+        self.subscriber_count += 1;
+
+        // The new subscriber gets to read the messages kept for replay as well
+        let len = self.queue.len();
+        let replayed = self.replay.min(len);
+        self.queue
+            .iter_mut()
+            .skip(len - replayed)
+            .for_each(|(_, counter)| *counter += 1);
+
+        self.next_message_id - replayed as u64
+    }
+
     fn try_publish(&mut self, message: T) -> Result<(), T> {
-        if self.subscriber_count == 0 {
+        if self.subscriber_count == 0 && self.replay == 0 {
             // We don't need to publish anything because there is no one to receive it
             return Ok(());
         }
 
         if self.queue.is_full() {
-            return Err(message);
+            match self.queue.front() {
+                // The oldest message has been read by everyone and is only kept for replay, so make room
+                Some((_, 0)) => {
+                    self.queue.pop_front();
+                }
+                _ => {
+                    // Filtered subscribers aren't woken for the messages they skip, but must still read
+                    // them to make room, so wake them all.
+                    self.filtered_subscriber_wakers.wake_all();
+                    return Err(message);
+                }
+            }
         }
+
+        // Wake all of the subscribers, and the filtered subscribers interested in this message
+        self.subscriber_wakers.wake();
+        self.filtered_subscriber_wakers.wake_matching(&message);
+
         // We just did a check for this
         self.queue.push_back((message, self.subscriber_count)).ok().unwrap();
 
         self.next_message_id += 1;
 
-        // Wake all of the subscribers
-        self.subscriber_wakers.wake();
+        self.drop_read_messages();
 
         Ok(())
     }
@@ -359,35 +472,72 @@ impl<T: Clone, const CAP: usize, const SUBS: usize, const PUBS: usize> PubSubSta
         self.try_publish(message).ok().unwrap();
     }
 
-    fn get_message(&mut self, message_id: u64) -> Option<WaitResult<T>> {
+    /// Get the message with the given id, or with the first id after it matching `filter`.
+    ///
+    /// Messages skipped because they don't match the filter are marked as read, and `message_id`
+    /// is advanced past them.
+    fn get_message(&mut self, message_id: &mut u64, filter: Option<fn(&T) -> bool>) -> Option<WaitResult<T>> {
         let start_id = self.next_message_id - self.queue.len() as u64;
 
-        if message_id < start_id {
-            return Some(WaitResult::Lagged(start_id - message_id));
+        if *message_id < start_id {
+            return Some(WaitResult::Lagged(start_id - *message_id));
         }
 
-        let current_message_index = (message_id - start_id) as usize;
+        loop {
+            let current_message_index = (*message_id - start_id) as usize;
+            let len = self.queue.len();
 
-        if current_message_index >= self.queue.len() {
-            return None;
-        }
+            if current_message_index >= len {
+                self.drop_read_messages();
+                return None;
+            }
+
+            // We've checked that the index is valid
+            let queue_item = self.queue.iter_mut().nth(current_message_index).unwrap();
 
-        // We've checked that the index is valid
-        let queue_item = self.queue.iter_mut().nth(current_message_index).unwrap();
+            // We're reading this item, so decrement the counter
+            queue_item.1 -= 1;
 
-        // We're reading this item, so decrement the counter
-        queue_item.1 -= 1;
+            if filter.is_some_and(|filter| !filter(&queue_item.0)) {
+                *message_id += 1;
+                continue;
+            }
 
-        let message = if current_message_index == 0 && queue_item.1 == 0 {
-            let (message, _) = self.queue.pop_front().unwrap();
+            let message = if current_message_index == 0 && queue_item.1 == 0 && len > self.replay {
+                let (message, _) = self.queue.pop_front().unwrap();
+                self.publisher_wakers.wake();
+                // Return pop'd message without clone
+                message
+            } else {
+                let message = queue_item.0.clone();
+                self.drop_read_messages();
+                message
+            };
+
+            return Some(WaitResult::Message(message));
+        }
+    }
+
+    /// Drop the messages that have been read by all subscribers and are not kept for replay.
+    fn drop_read_messages(&mut self) {
+        let mut wake_publishers = false;
+        while let Some((_, 0)) = self.queue.front() {
+            if self.queue.len() <= self.replay {
+                break;
+            }
+            self.queue.pop_front().unwrap();
+            wake_publishers = true;
+        }
+
+        if wake_publishers {
             self.publisher_wakers.wake();
-            // Return pop'd message without clone
-            message
-        } else {
-            queue_item.0.clone()
-        };
+        }
+    }
 
-        Some(WaitResult::Message(message))
+    /// The amount of messages at the front of the queue that have been read by all subscribers,
+    /// and are only kept for replay.
+    fn replay_len(&self) -> usize {
+        self.queue.iter().take_while(|(_, count)| *count == 0).count()
     }
 
     fn unregister_subscriber(&mut self, subscriber_next_message_id: u64) {
@@ -402,19 +552,7 @@ impl<T: Clone, const CAP: usize, const SUBS: usize, const PUBS: usize> PubSubSta
                 .skip(current_message_index)
                 .for_each(|(_, counter)| *counter -= 1);
 
-            let mut wake_publishers = false;
-            while let Some((_, count)) = self.queue.front() {
-                if *count == 0 {
-                    self.queue.pop_front().unwrap();
-                    wake_publishers = true;
-                } else {
-                    break;
-                }
-            }
-
-            if wake_publishers {
-                self.publisher_wakers.wake();
-            }
+            self.drop_read_messages();
         }
     }
 
@@ -430,15 +568,73 @@ impl<T: Clone, const CAP: usize, const SUBS: usize, const PUBS: usize> PubSubSta
     }
 
     fn len(&self) -> usize {
-        self.queue.len()
+        // Messages only kept for replay don't take up capacity, since publishing drops them
+        self.queue.len() - self.replay_len()
     }
 
     fn is_empty(&self) -> bool {
-        self.queue.is_empty()
+        self.len() == 0
     }
 
     fn is_full(&self) -> bool {
-        self.queue.is_full()
+        self.len() == CAP
+    }
+}
+
+/// A filter deciding which messages a subscriber receives.
+type Filter<T> = fn(&T) -> bool;
+
+/// Wakers of filtered subscribers, each with the filter deciding which messages it is woken for.
+#[derive(Debug)]
+struct FilteredWakerRegistration<T, const N: usize> {
+    wakers: Vec<(Waker, Filter<T>), N>,
+}
+
+impl<T, const N: usize> FilteredWakerRegistration<T, N> {
+    const fn new() -> Self {
+        Self { wakers: Vec::new() }
+    }
+
+    /// Register a waker, to be woken for messages matching `filter`.
+    ///
+    /// If the buffer is full, all wakers are woken and the buffer is cleared, like
+    /// [`MultiWakerRegistration::register`] does.
+    fn register(&mut self, w: &Waker, filter: Filter<T>) {
+        for (w2, f) in self.wakers.iter_mut() {
+            if w.will_wake(w2) {
+                *f = filter;
+                return;
+            }
+        }
+
+        if self.wakers.is_full() {
+            self.wake_all();
+        }
+
+        if self.wakers.push((w.clone(), filter)).is_err() {
+            // This can't happen unless N=0, which is caught by MultiWakerRegistration
+            panic!("tried to push a waker to a zero-length FilteredWakerRegistration")
+        }
+    }
+
+    /// Wake all wakers, whatever their filter.
+    fn wake_all(&mut self) {
+        while let Some((waker, _)) = self.wakers.pop() {
+            waker.wake();
+        }
+    }
+
+    /// Wake the wakers whose filter matches `message`, leaving the others registered.
+    fn wake_matching(&mut self, message: &T) {
+        let mut i = 0;
+        while i < self.wakers.len() {
+            if (self.wakers[i].1)(message) {
+                let (waker, _) = self.wakers.swap_remove(i);
+                waker.wake();
+            } else {
+                i += 1;
+            }
+        }
     }
 }
 
@@ -458,7 +654,14 @@ trait SealedPubSubBehavior<T> {
     /// Try to get a message from the queue with the given message id.
     ///
     /// If the message is not yet present and a context is given, then its waker is registered in the subscriber wakers.
-    fn get_message_with_context(&self, next_message_id: &mut u64, cx: Option<&mut Context<'_>>) -> Poll<WaitResult<T>>;
+    ///
+    /// If a filter is given, messages not matching it are skipped, and the waker is only woken for matching messages.
+    fn get_message_with_context(
+        &self,
+        next_message_id: &mut u64,
+        filter: Option<fn(&T) -> bool>,
+        cx: Option<&mut Context<'_>>,
+    ) -> Poll<WaitResult<T>>;
 
     /// Get the amount of messages that are between the given the next_message_id and the most recent message.
     /// This is not necessarily the amount of messages a subscriber can still received as it may have lagged.
@@ -517,6 +720,8 @@ pub enum WaitResult<T> {
 
 #[cfg(test)]
 mod tests {
+    use core::future::Future;
+
     use super::*;
     use crate::blocking_mutex::raw::NoopRawMutex;
 
@@ -761,6 +966,143 @@ mod tests {
         assert_eq!(0, sub1.try_next_message_pure().unwrap().0);
     }
 
+    #[futures_test::test]
+    async fn replay_for_late_subscribers() {
+        let channel = PubSubChannel::<NoopRawMutex, u32, 4, 4, 4>::with_replay(2);
+        let pub0 = channel.publisher().unwrap();
+
+        // Messages are kept even without any subscriber
+        pub0.publish(1).await;
+        pub0.publish(2).await;
+        pub0.publish(3).await;
+
+        // Messages kept only for replay don't take up capacity
+        assert_eq!(channel.len(), 0);
+        assert_eq!(channel.free_capacity(), 4);
+
+        let mut sub0 = channel.subscriber().unwrap();
+        assert_eq!(sub0.available(), 2);
+        assert_eq!(channel.len(), 2);
+        assert_eq!(sub0.try_next_message(), Some(WaitResult::Message(2)));
+        assert_eq!(sub0.try_next_message(), Some(WaitResult::Message(3)));
+        assert_eq!(sub0.try_next_message(), None);
+        assert_eq!(channel.len(), 0);
+
+        // Read messages are still kept for the next subscriber
+        let mut sub1 = channel.dyn_subscriber().unwrap();
+        assert_eq!(sub1.try_next_message_pure(), Some(2));
+
+        pub0.publish(4).await;
+        assert_eq!(sub0.try_next_message_pure(), Some(4));
+        assert_eq!(sub1.try_next_message_pure(), Some(3));
+        assert_eq!(sub1.try_next_message_pure(), Some(4));
+
+        let mut sub2 = channel.subscriber().unwrap();
+        assert_eq!(sub2.try_next_message_pure(), Some(3));
+        assert_eq!(sub2.try_next_message_pure(), Some(4));
+        assert_eq!(sub2.try_next_message_pure(), None);
+    }
+
+    #[futures_test::test]
+    async fn replay_does_not_block_publishers() {
+        let channel = PubSubChannel::<NoopRawMutex, u32, 2, 4, 4>::with_replay(2);
+        let pub0 = channel.publisher().unwrap();
+        let mut sub0 = channel.subscriber().unwrap();
+
+        pub0.publish(1).await;
+        pub0.publish(2).await;
+        assert!(channel.is_full());
+        assert_eq!(pub0.try_publish(3), Err(3));
+
+        assert_eq!(sub0.try_next_message_pure(), Some(1));
+        assert!(!channel.is_full());
+        assert_eq!(pub0.try_publish(3), Ok(()));
+        assert_eq!(sub0.try_next_message_pure(), Some(2));
+        assert_eq!(sub0.try_next_message_pure(), Some(3));
+
+        drop(sub0);
+        let mut sub1 = channel.subscriber().unwrap();
+        assert_eq!(sub1.try_next_message_pure(), Some(2));
+        assert_eq!(sub1.try_next_message_pure(), Some(3));
+    }
+
+    #[futures_test::test]
+    async fn filtered_subscriber() {
+        let channel = PubSubChannel::<NoopRawMutex, u32, 4, 4, 4>::new();
+        let pub0 = channel.publisher().unwrap();
+        let mut sub0 = channel.subscriber_with_filter(|m| m % 2 == 0).unwrap();
+        let mut sub1 = channel.dyn_subscriber_with_filter(|m| *m > 2).unwrap();
+
+        pub0.publish(1).await;
+        pub0.publish(2).await;
+        pub0.publish(3).await;
+
+        assert_eq!(sub0.try_next_message(), Some(WaitResult::Message(2)));
+        assert_eq!(sub0.try_next_message(), None);
+        assert_eq!(sub1.try_next_message(), Some(WaitResult::Message(3)));
+        assert_eq!(sub1.try_next_message(), None);
+
+        // Skipped messages are marked as read
+        assert!(channel.is_empty());
+    }
+
+    #[test]
+    fn filtered_subscriber_only_woken_for_matching_messages() {
+        use core::pin::pin;
+
+        use futures_test::task::new_count_waker;
+
+        let channel = PubSubChannel::<NoopRawMutex, u32, 4, 4, 4>::new();
+        let pub0 = channel.publisher().unwrap();
+        let mut sub0 = channel.subscriber_with_filter(|m| m % 2 == 0).unwrap();
+        let (waker, count) = new_count_waker();
+        let mut cx = Context::from_waker(&waker);
+
+        let mut fut = pin!(sub0.next_message());
+        assert!(fut.as_mut().poll(&mut cx).is_pending());
+
+        pub0.try_publish(1).unwrap();
+        assert_eq!(count, 0);
+        assert!(fut.as_mut().poll(&mut cx).is_pending());
+
+        pub0.try_publish(2).unwrap();
+        assert_eq!(count, 1);
+        assert_eq!(fut.as_mut().poll(&mut cx), Poll::Ready(WaitResult::Message(2)));
+    }
+
+    #[test]
+    fn filtered_subscriber_does_not_block_publishers() {
+        use core::pin::pin;
+
+        use futures_test::task::new_count_waker;
+
+        let channel = PubSubChannel::<NoopRawMutex, u32, 4, 4, 4>::new();
+        let pub0 = channel.publisher().unwrap();
+        let mut sub0 = channel.subscriber_with_filter(|m| m % 2 == 0).unwrap();
+        let (sub_waker, sub_count) = new_count_waker();
+        let mut sub_cx = Context::from_waker(&sub_waker);
+
+        let mut sub_fut = pin!(sub0.next_message());
+        assert!(sub_fut.as_mut().poll(&mut sub_cx).is_pending());
+
+        // Publish more messages than fit in the queue, none matching the filter
+        let mut pub_fut = pin!(async {
+            for i in 0..5 {
+                pub0.publish(2 * i + 1).await;
+            }
+        });
+        let (pub_waker, _) = new_count_waker();
+        let mut pub_cx = Context::from_waker(&pub_waker);
+
+        let mut woken = 0;
+        while pub_fut.as_mut().poll(&mut pub_cx).is_pending() {
+            // The publisher is waiting for the subscriber to skip the messages, so it must be woken
+            assert!(sub_count.get() > woken, "the publisher would wait forever");
+            woken = sub_count.get();
+            assert!(sub_fut.as_mut().poll(&mut sub_cx).is_pending());
+        }
+    }
+
     #[futures_test::test]
     async fn publisher_sink() {
         use futures_util::{SinkExt, StreamExt};
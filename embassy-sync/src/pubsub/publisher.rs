@@ -306,14 +306,16 @@ impl<'s, 'a, PSB: PubSubBehavior<T> + ?Sized, T: Clone> Future for PublisherWait
     type Output = ();
 
     fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
-        let message = self.message.take().unwrap();
-        match self.publisher.channel.publish_with_context(message, Some(cx)) {
-            Ok(()) => Poll::Ready(()),
-            Err(message) => {
-                self.message = Some(message);
-                Poll::Pending
+        crate::coop::poll_with_budget(cx, |cx| {
+            let message = self.message.take().unwrap();
+            match self.publisher.channel.publish_with_context(message, Some(cx)) {
+                Ok(()) => Poll::Ready(()),
+                Err(message) => {
+                    self.message = Some(message);
+                    Poll::Pending
+                }
             }
-        }
+        })
     }
 }
 
@@ -14,15 +14,18 @@ use crate::blocking_mutex::raw::RawMutex;
 pub struct Sub<'a, PSB: PubSubBehavior<T> + ?Sized, T: Clone> {
     /// The message id of the next message we are yet to receive
     next_message_id: u64,
+    /// Only messages matching this filter are received, if set
+    filter: Option<fn(&T) -> bool>,
     /// The channel we are a subscriber to
     channel: &'a PSB,
     _phantom: PhantomData<T>,
 }
 
 impl<'a, PSB: PubSubBehavior<T> + ?Sized, T: Clone> Sub<'a, PSB, T> {
-    pub(super) fn new(next_message_id: u64, channel: &'a PSB) -> Self {
+    pub(super) fn new(next_message_id: u64, filter: Option<fn(&T) -> bool>, channel: &'a PSB) -> Self {
         Self {
             next_message_id,
+            filter,
             channel,
             _phantom: Default::default(),
         }
@@ -47,7 +50,10 @@ impl<'a, PSB: PubSubBehavior<T> + ?Sized, T: Clone> Sub<'a, PSB, T> {
     ///
     /// This function does not peek. The message is received if there is one.
     pub fn try_next_message(&mut self) -> Option<WaitResult<T>> {
-        match self.channel.get_message_with_context(&mut self.next_message_id, None) {
+        match self
+            .channel
+            .get_message_with_context(&mut self.next_message_id, self.filter, None)
+        {
             Poll::Ready(result) => Some(result),
             Poll::Pending => None,
         }
@@ -120,17 +126,20 @@ impl<'a, PSB: PubSubBehavior<T> + ?Sized, T: Clone> futures_core::Stream for Sub
     type Item = T;
 
     fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
-        match self
-            .channel
-            .get_message_with_context(&mut self.next_message_id, Some(cx))
-        {
-            Poll::Ready(WaitResult::Message(message)) => Poll::Ready(Some(message)),
-            Poll::Ready(WaitResult::Lagged(_)) => {
-                cx.waker().wake_by_ref();
-                Poll::Pending
+        crate::coop::poll_with_budget(cx, |cx| {
+            let filter = self.filter;
+            match self
+                .channel
+                .get_message_with_context(&mut self.next_message_id, filter, Some(cx))
+            {
+                Poll::Ready(WaitResult::Message(message)) => Poll::Ready(Some(message)),
+                Poll::Ready(WaitResult::Lagged(_)) => {
+                    cx.waker().wake_by_ref();
+                    Poll::Pending
+                }
+                Poll::Pending => Poll::Pending,
             }
-            Poll::Pending => Poll::Pending,
-        }
+        })
     }
 }
 
@@ -186,9 +195,12 @@ impl<'s, 'a, PSB: PubSubBehavior<T> + ?Sized, T: Clone> Future for SubscriberWai
     type Output = WaitResult<T>;
 
     fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
-        self.subscriber
-            .channel
-            .get_message_with_context(&mut self.subscriber.next_message_id, Some(cx))
+        crate::coop::poll_with_budget(cx, |cx| {
+            let filter = self.subscriber.filter;
+            self.subscriber
+                .channel
+                .get_message_with_context(&mut self.subscriber.next_message_id, filter, Some(cx))
+        })
     }
 }
 
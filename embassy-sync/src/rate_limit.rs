@@ -0,0 +1,418 @@
+//! Synchronization primitives for limiting the rate at which an operation may happen.
+//!
+//! This module requires the `time` feature.
+use core::cell::RefCell;
+
+use embassy_time::{Duration, Instant, Timer};
+use heapless::Deque;
+
+use crate::blocking_mutex::Mutex;
+use crate::blocking_mutex::raw::RawMutex;
+
+/// An asynchronous rate limiter.
+///
+/// A rate limiter hands out permits at a bounded rate. Unlike a [`Semaphore`](crate::semaphore::Semaphore),
+/// permits are not returned: they are replenished automatically as time passes.
+pub trait RateLimiter {
+    /// Asynchronously acquire one or more permits, waiting until the rate limit allows it.
+    ///
+    /// Returns [`RequestTooLarge`] if `permits` exceeds what the limiter can ever hand out at once.
+    async fn acquire(&self, permits: usize) -> Result<(), RequestTooLarge>;
+
+    /// Try to immediately acquire one or more permits.
+    fn try_acquire(&self, permits: usize) -> Result<(), TryAcquireError>;
+}
+
+/// An error indicating more permits were requested than the rate limiter can ever hand out at once.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct RequestTooLarge;
+
+/// Error returned by [`RateLimiter::try_acquire`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum TryAcquireError {
+    /// Not enough permits are available right now. They will be at the given instant,
+    /// unless other tasks acquire them first.
+    RateLimited(Instant),
+    /// More permits were requested than the rate limiter can ever hand out at once.
+    RequestTooLarge,
+}
+
+impl From<RequestTooLarge> for TryAcquireError {
+    fn from(_: RequestTooLarge) -> Self {
+        Self::RequestTooLarge
+    }
+}
+
+/// A token bucket [`RateLimiter`].
+///
+/// The bucket holds up to `burst` tokens and is refilled with one token every `interval`.
+/// Acquiring permits takes tokens out of the bucket, so up to `burst` permits can be acquired
+/// back-to-back, after which permits are handed out at the refill rate.
+///
+/// Waiting tasks are not queued: whichever task polls first once enough tokens are available
+/// gets them.
+///
+/// ```
+/// use embassy_sync::blocking_mutex::raw::CriticalSectionRawMutex;
+/// use embassy_sync::rate_limit::{RateLimiter, TokenBucket};
+/// use embassy_time::Duration;
+///
+/// // Allow bursts of 5 uploads, and one upload per second on average.
+/// static UPLOADS: TokenBucket<CriticalSectionRawMutex> = TokenBucket::new(5, Duration::from_secs(1));
+///
+/// async fn upload(data: &[u8]) {
+///     UPLOADS.acquire(1).await.unwrap();
+///     // ...
+/// }
+/// ```
+#[derive(Debug)]
+pub struct TokenBucket<M: RawMutex> {
+    burst: usize,
+    interval: Duration,
+    state: Mutex<M, RefCell<TokenBucketState>>,
+}
+
+#[derive(Debug)]
+struct TokenBucketState {
+    /// Tokens in the bucket as of `last_refill`.
+    tokens: usize,
+    /// The instant `tokens` was last brought up to date, or `None` if the bucket was never used.
+    last_refill: Option<Instant>,
+}
+
+impl<M: RawMutex> TokenBucket<M> {
+    /// Create a new `TokenBucket` holding up to `burst` tokens, refilled with one token every `interval`.
+    ///
+    /// The bucket starts out full.
+    pub const fn new(burst: usize, interval: Duration) -> Self {
+        Self {
+            burst,
+            interval,
+            state: Mutex::new(RefCell::new(TokenBucketState {
+                tokens: burst,
+                last_refill: None,
+            })),
+        }
+    }
+
+    /// The maximum number of tokens in the bucket.
+    pub fn burst(&self) -> usize {
+        self.burst
+    }
+
+    /// The interval at which a single token is added to the bucket.
+    pub fn interval(&self) -> Duration {
+        self.interval
+    }
+
+    /// The number of tokens currently available.
+    pub fn available(&self) -> usize {
+        self.state.lock(|s| {
+            let mut s = s.borrow_mut();
+            self.refill(&mut s, Instant::now());
+            s.tokens
+        })
+    }
+
+    /// Fill the bucket up to `burst` tokens.
+    pub fn reset(&self) {
+        self.state.lock(|s| {
+            let mut s = s.borrow_mut();
+            s.tokens = self.burst;
+            s.last_refill = Some(Instant::now());
+        })
+    }
+
+    fn refill(&self, s: &mut TokenBucketState, now: Instant) {
+        let last = *s.last_refill.get_or_insert(now);
+        let interval = self.interval.as_ticks().max(1);
+        let elapsed = now.saturating_duration_since(last).as_ticks();
+        let new = elapsed / interval;
+        // More ticks than fit in a `usize` fill the bucket anyway.
+        let tokens = usize::try_from(new).unwrap_or(usize::MAX);
+
+        if s.tokens.saturating_add(tokens) >= self.burst {
+            // Tokens don't accumulate beyond a full bucket.
+            s.tokens = self.burst;
+            s.last_refill = Some(now);
+        } else {
+            s.tokens += tokens;
+            // Keep the remainder, so the refill rate doesn't depend on how often the bucket is polled.
+            s.last_refill = Some(last + Duration::from_ticks(new * interval));
+        }
+    }
+
+    fn poll_acquire(&self, permits: usize) -> Result<(), TryAcquireError> {
+        if permits > self.burst {
+            return Err(TryAcquireError::RequestTooLarge);
+        }
+
+        self.state.lock(|s| {
+            let mut s = s.borrow_mut();
+            let now = Instant::now();
+            self.refill(&mut s, now);
+
+            if s.tokens >= permits {
+                s.tokens -= permits;
+                Ok(())
+            } else {
+                let missing = (permits - s.tokens) as u64;
+                let last = unwrap!(s.last_refill);
+                let ready = last + Duration::from_ticks(missing.saturating_mul(self.interval.as_ticks().max(1)));
+                Err(TryAcquireError::RateLimited(ready))
+            }
+        })
+    }
+}
+
+impl<M: RawMutex> RateLimiter for TokenBucket<M> {
+    async fn acquire(&self, permits: usize) -> Result<(), RequestTooLarge> {
+        loop {
+            match self.poll_acquire(permits) {
+                Ok(()) => return Ok(()),
+                Err(TryAcquireError::RequestTooLarge) => return Err(RequestTooLarge),
+                Err(TryAcquireError::RateLimited(at)) => Timer::at(at).await,
+            }
+        }
+    }
+
+    fn try_acquire(&self, permits: usize) -> Result<(), TryAcquireError> {
+        self.poll_acquire(permits)
+    }
+}
+
+/// A sliding window [`RateLimiter`].
+///
+/// At most `N` permits are handed out within any time span of length `window`. Unlike a
+/// [`TokenBucket`], the limit holds for every window, not just on average, at the cost of
+/// remembering the instant each of the last `N` permits was acquired.
+///
+/// Waiting tasks are not queued: whichever task polls first once enough permits are available
+/// gets them.
+#[derive(Debug)]
+pub struct SlidingWindow<M: RawMutex, const N: usize> {
+    window: Duration,
+    state: Mutex<M, RefCell<Deque<Instant, N>>>,
+}
+
+impl<M: RawMutex, const N: usize> SlidingWindow<M, N> {
+    /// Create a new `SlidingWindow` handing out at most `N` permits per `window`.
+    pub const fn new(window: Duration) -> Self {
+        Self {
+            window,
+            state: Mutex::new(RefCell::new(Deque::new())),
+        }
+    }
+
+    /// The length of the window.
+    pub fn window(&self) -> Duration {
+        self.window
+    }
+
+    /// The number of permits that can be acquired right now.
+    pub fn available(&self) -> usize {
+        self.state.lock(|s| {
+            let mut s = s.borrow_mut();
+            self.expire(&mut s, Instant::now());
+            N - s.len()
+        })
+    }
+
+    /// Forget all previously acquired permits.
+    pub fn reset(&self) {
+        self.state.lock(|s| s.borrow_mut().clear())
+    }
+
+    fn expire(&self, s: &mut Deque<Instant, N>, now: Instant) {
+        while let Some(&at) = s.front() {
+            if now.saturating_duration_since(at) >= self.window {
+                s.pop_front();
+            } else {
+                break;
+            }
+        }
+    }
+
+    fn poll_acquire(&self, permits: usize) -> Result<(), TryAcquireError> {
+        if permits > N {
+            return Err(TryAcquireError::RequestTooLarge);
+        }
+
+        self.state.lock(|s| {
+            let mut s = s.borrow_mut();
+            let now = Instant::now();
+            self.expire(&mut s, now);
+
+            let free = N - s.len();
+            if free >= permits {
+                for _ in 0..permits {
+                    // We just checked there is enough space.
+                    unwrap!(s.push_back(now).ok());
+                }
+                Ok(())
+            } else {
+                // The permit that has to expire to make room for the request.
+                let oldest = unwrap!(s.iter().nth(permits - free - 1));
+                Err(TryAcquireError::RateLimited(*oldest + self.window))
+            }
+        })
+    }
+}
+
+impl<M: RawMutex, const N: usize> RateLimiter for SlidingWindow<M, N> {
+    async fn acquire(&self, permits: usize) -> Result<(), RequestTooLarge> {
+        loop {
+            match self.poll_acquire(permits) {
+                Ok(()) => return Ok(()),
+                Err(TryAcquireError::RequestTooLarge) => return Err(RequestTooLarge),
+                Err(TryAcquireError::RateLimited(at)) => Timer::at(at).await,
+            }
+        }
+    }
+
+    fn try_acquire(&self, permits: usize) -> Result<(), TryAcquireError> {
+        self.poll_acquire(permits)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use core::pin::pin;
+
+    use embassy_time::MockDriver;
+    use futures_util::poll;
+    use serial_test::serial;
+
+    use super::*;
+    use crate::blocking_mutex::raw::NoopRawMutex;
+
+    fn setup() -> &'static MockDriver {
+        let driver = MockDriver::get();
+        driver.reset();
+        driver
+    }
+
+    #[test]
+    #[serial]
+    fn token_bucket_try_acquire() {
+        let driver = setup();
+        let bucket = TokenBucket::<NoopRawMutex>::new(3, Duration::from_secs(1));
+
+        assert_eq!(bucket.try_acquire(2), Ok(()));
+        assert_eq!(bucket.try_acquire(1), Ok(()));
+        assert_eq!(
+            bucket.try_acquire(1),
+            Err(TryAcquireError::RateLimited(Instant::from_secs(1)))
+        );
+        assert_eq!(bucket.try_acquire(4), Err(TryAcquireError::RequestTooLarge));
+
+        driver.advance(Duration::from_millis(1500));
+        assert_eq!(bucket.available(), 1);
+        assert_eq!(
+            bucket.try_acquire(2),
+            Err(TryAcquireError::RateLimited(Instant::from_secs(2)))
+        );
+
+        // The partial interval is not lost.
+        driver.advance(Duration::from_millis(500));
+        assert_eq!(bucket.try_acquire(2), Ok(()));
+    }
+
+    #[test]
+    #[serial]
+    fn token_bucket_caps_at_burst() {
+        let driver = setup();
+        let bucket = TokenBucket::<NoopRawMutex>::new(2, Duration::from_secs(1));
+
+        assert_eq!(bucket.try_acquire(2), Ok(()));
+        driver.advance(Duration::from_secs(10));
+        assert_eq!(bucket.available(), 2);
+        assert_eq!(bucket.try_acquire(2), Ok(()));
+        assert_eq!(bucket.available(), 0);
+
+        bucket.reset();
+        assert_eq!(bucket.available(), 2);
+    }
+
+    #[test]
+    #[serial]
+    fn token_bucket_long_idle() {
+        let driver = setup();
+        let bucket = TokenBucket::<NoopRawMutex>::new(usize::MAX, Duration::from_ticks(1));
+
+        assert_eq!(bucket.try_acquire(usize::MAX), Ok(()));
+        assert_eq!(
+            bucket.try_acquire(usize::MAX),
+            Err(TryAcquireError::RateLimited(Instant::from_ticks(u64::MAX)))
+        );
+        // On 32-bit targets, more tokens are due than fit in a `usize`.
+        driver.advance(Duration::from_ticks(u64::MAX / 2));
+        assert_eq!(bucket.available(), usize::try_from(u64::MAX / 2).unwrap_or(usize::MAX));
+    }
+
+    #[futures_test::test]
+    #[serial]
+    async fn token_bucket_acquire_waits() {
+        let driver = setup();
+        let bucket = TokenBucket::<NoopRawMutex>::new(1, Duration::from_secs(1));
+
+        assert_eq!(bucket.acquire(1).await, Ok(()));
+        assert_eq!(bucket.acquire(2).await, Err(RequestTooLarge));
+
+        let mut fut = pin!(bucket.acquire(1));
+        assert!(poll!(fut.as_mut()).is_pending());
+
+        driver.advance(Duration::from_millis(999));
+        assert!(poll!(fut.as_mut()).is_pending());
+
+        driver.advance(Duration::from_millis(1));
+        assert_eq!(poll!(fut.as_mut()), core::task::Poll::Ready(Ok(())));
+    }
+
+    #[test]
+    #[serial]
+    fn sliding_window_try_acquire() {
+        let driver = setup();
+        let window = SlidingWindow::<NoopRawMutex, 3>::new(Duration::from_secs(10));
+
+        assert_eq!(window.try_acquire(1), Ok(()));
+        driver.advance(Duration::from_secs(4));
+        assert_eq!(window.try_acquire(2), Ok(()));
+        assert_eq!(
+            window.try_acquire(1),
+            Err(TryAcquireError::RateLimited(Instant::from_secs(10)))
+        );
+        assert_eq!(
+            window.try_acquire(2),
+            Err(TryAcquireError::RateLimited(Instant::from_secs(14)))
+        );
+        assert_eq!(window.try_acquire(4), Err(TryAcquireError::RequestTooLarge));
+
+        driver.advance(Duration::from_secs(6));
+        assert_eq!(window.available(), 1);
+        assert_eq!(window.try_acquire(1), Ok(()));
+        assert_eq!(window.available(), 0);
+
+        window.reset();
+        assert_eq!(window.available(), 3);
+    }
+
+    #[futures_test::test]
+    #[serial]
+    async fn sliding_window_acquire_waits() {
+        let driver = setup();
+        let window = SlidingWindow::<NoopRawMutex, 2>::new(Duration::from_secs(1));
+
+        assert_eq!(window.acquire(2).await, Ok(()));
+        assert_eq!(window.acquire(3).await, Err(RequestTooLarge));
+
+        let mut fut = pin!(window.acquire(1));
+        assert!(poll!(fut.as_mut()).is_pending());
+
+        driver.advance(Duration::from_secs(1));
+        assert_eq!(poll!(fut.as_mut()), core::task::Poll::Ready(Ok(())));
+        assert_eq!(window.available(), 1);
+    }
+}
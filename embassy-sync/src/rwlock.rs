@@ -2,10 +2,11 @@
 //!
 //! This module provides a read-write lock that can be used to synchronize data between asynchronous tasks.
 use core::cell::{RefCell, UnsafeCell};
-use core::fmt;
 use core::future::{Future, poll_fn};
 use core::ops::{Deref, DerefMut};
-use core::task::Poll;
+use core::pin::Pin;
+use core::task::{Context, Poll};
+use core::{fmt, mem};
 
 use crate::blocking_mutex::Mutex as BlockingMutex;
 use crate::blocking_mutex::raw::RawMutex;
@@ -16,11 +17,64 @@ use crate::waitqueue::WakerRegistration;
 #[cfg_attr(feature = "defmt", derive(defmt::Format))]
 pub struct TryLockError;
 
+/// Fairness policy deciding whether readers or writers get the lock first when both are waiting.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum Policy {
+    /// New readers are admitted as long as the lock is not held for writing, even if a writer is
+    /// waiting. This maximizes read concurrency, but a steady stream of readers can starve writers.
+    #[default]
+    ReaderPreferred,
+    /// New readers (and upgradable readers) wait while a writer is waiting for the lock, so writers
+    /// get the lock as soon as the current readers are done.
+    ///
+    /// A task already holding a read guard must not wait for another one while a writer may be
+    /// waiting, as this will deadlock.
+    WriterPreferred,
+}
+
 #[derive(Debug)]
 struct State {
+    policy: Policy,
     readers: usize,
     writer: bool,
-    waker: WakerRegistration,
+    upgradable: bool,
+    writers_waiting: usize,
+    read_waker: WakerRegistration,
+    write_waker: WakerRegistration,
+}
+
+impl State {
+    fn can_read(&self) -> bool {
+        !self.writer && (self.policy == Policy::ReaderPreferred || self.writers_waiting == 0)
+    }
+
+    fn can_upgradable_read(&self) -> bool {
+        self.can_read() && !self.upgradable
+    }
+
+    fn can_write(&self) -> bool {
+        !self.writer && !self.upgradable && self.readers == 0
+    }
+
+    fn read_unlock(&mut self) {
+        self.readers -= 1;
+        if self.readers == 0 {
+            self.write_waker.wake();
+        }
+    }
+
+    fn upgradable_unlock(&mut self) {
+        self.upgradable = false;
+        self.read_waker.wake();
+        self.write_waker.wake();
+    }
+
+    fn write_unlock(&mut self) {
+        self.writer = false;
+        self.read_waker.wake();
+        self.write_waker.wake();
+    }
 }
 
 /// Async read-write lock.
@@ -37,6 +91,9 @@ struct State {
 /// Use [`NoopRawMutex`](crate::blocking_mutex::raw::NoopRawMutex) when data is only shared between tasks running on the same executor.
 ///
 /// Use [`ThreadModeRawMutex`](crate::blocking_mutex::raw::ThreadModeRawMutex) when data is shared between tasks running on the same executor but you want a singleton.
+///
+/// By default new readers are admitted while a writer is waiting, see [`Policy`] and
+/// [`RwLock::with_policy`] to prefer writers instead.
 pub struct RwLock<M, T>
 where
     M: RawMutex,
@@ -54,14 +111,23 @@ impl<M, T> RwLock<M, T>
 where
     M: RawMutex,
 {
-    /// Create a new read-write lock with the given value.
+    /// Create a new read-write lock with the given value, preferring readers.
     pub const fn new(value: T) -> Self {
+        Self::with_policy(value, Policy::ReaderPreferred)
+    }
+
+    /// Create a new read-write lock with the given value and fairness policy.
+    pub const fn with_policy(value: T, policy: Policy) -> Self {
         Self {
             inner: UnsafeCell::new(value),
             state: BlockingMutex::new(RefCell::new(State {
+                policy,
                 readers: 0,
                 writer: false,
-                waker: WakerRegistration::new(),
+                upgradable: false,
+                writers_waiting: 0,
+                read_waker: WakerRegistration::new(),
+                write_waker: WakerRegistration::new(),
             })),
         }
     }
@@ -74,17 +140,18 @@ where
 {
     /// Lock the read-write lock for reading.
     ///
-    /// This will wait for the lock to be available if it's already locked for writing.
+    /// This will wait for the lock to be available if it's already locked for writing, or if a
+    /// writer is waiting and the lock prefers writers.
     pub fn read(&self) -> impl Future<Output = RwLockReadGuard<'_, M, T>> {
         poll_fn(|cx| {
             let ready = self.state.lock(|s| {
                 let mut s = s.borrow_mut();
-                if s.writer {
-                    s.waker.register(cx.waker());
-                    false
-                } else {
+                if s.can_read() {
                     s.readers += 1;
                     true
+                } else {
+                    s.read_waker.register(cx.waker());
+                    false
                 }
             });
 
@@ -96,30 +163,43 @@ where
         })
     }
 
-    /// Lock the read-write lock for writing.
+    /// Lock the read-write lock for reading, with the option to upgrade to a write lock later.
     ///
-    /// This will wait for the lock to be available if it's already locked for reading or writing.
-    pub fn write(&self) -> impl Future<Output = RwLockWriteGuard<'_, M, T>> {
+    /// The upgradable read lock can be held alongside plain read locks, but excludes writers and
+    /// other upgradable read locks. This will wait for the lock to be available if it's already
+    /// locked for writing or upgradable reading, or if a writer is waiting and the lock prefers writers.
+    pub fn upgradable_read(&self) -> impl Future<Output = RwLockUpgradableReadGuard<'_, M, T>> {
         poll_fn(|cx| {
             let ready = self.state.lock(|s| {
                 let mut s = s.borrow_mut();
-                if s.writer || s.readers > 0 {
-                    s.waker.register(cx.waker());
-                    false
-                } else {
-                    s.writer = true;
+                if s.can_upgradable_read() {
+                    s.upgradable = true;
                     true
+                } else {
+                    s.read_waker.register(cx.waker());
+                    false
                 }
             });
 
             if ready {
-                Poll::Ready(RwLockWriteGuard { rwlock: self })
+                Poll::Ready(RwLockUpgradableReadGuard { rwlock: self })
             } else {
                 Poll::Pending
             }
         })
     }
 
+    /// Lock the read-write lock for writing.
+    ///
+    /// This will wait for the lock to be available if it's already locked for reading or writing.
+    pub fn write(&self) -> impl Future<Output = RwLockWriteGuard<'_, M, T>> {
+        WriteFuture {
+            rwlock: self,
+            upgrading: false,
+            waiting: false,
+        }
+    }
+
     /// Attempt to immediately lock the rwlock.
     ///
     /// If the rwlock is already locked, this will return an error instead of waiting.
@@ -127,7 +207,7 @@ where
         self.state
             .lock(|s| {
                 let mut s = s.borrow_mut();
-                if s.writer {
+                if !s.can_read() {
                     return Err(());
                 }
                 s.readers += 1;
@@ -138,6 +218,24 @@ where
         Ok(RwLockReadGuard { rwlock: self })
     }
 
+    /// Attempt to immediately lock the rwlock for upgradable reading.
+    ///
+    /// If the rwlock is already locked, this will return an error instead of waiting.
+    pub fn try_upgradable_read(&self) -> Result<RwLockUpgradableReadGuard<'_, M, T>, TryLockError> {
+        self.state
+            .lock(|s| {
+                let mut s = s.borrow_mut();
+                if !s.can_upgradable_read() {
+                    return Err(());
+                }
+                s.upgradable = true;
+                Ok(())
+            })
+            .map_err(|_| TryLockError)?;
+
+        Ok(RwLockUpgradableReadGuard { rwlock: self })
+    }
+
     /// Attempt to immediately lock the rwlock.
     ///
     /// If the rwlock is already locked, this will return an error instead of waiting.
@@ -145,7 +243,7 @@ where
         self.state
             .lock(|s| {
                 let mut s = s.borrow_mut();
-                if s.writer || s.readers > 0 {
+                if !s.can_write() {
                     return Err(());
                 }
                 s.writer = true;
@@ -220,6 +318,25 @@ where
     rwlock: &'a RwLock<R, T>,
 }
 
+impl<'a, M, T> RwLockReadGuard<'a, M, T>
+where
+    M: RawMutex,
+    T: ?Sized,
+{
+    /// Returns a locked view over a portion of the locked data.
+    pub fn map<U: ?Sized>(this: Self, fun: impl FnOnce(&T) -> &U) -> MappedRwLockReadGuard<'a, M, U> {
+        let rwlock = this.rwlock;
+        let value = fun(unsafe { &*rwlock.inner.get() });
+        // Don't run the `drop` method for RwLockReadGuard. The ownership of the underlying
+        // locked state is being moved to the returned MappedRwLockReadGuard.
+        mem::forget(this);
+        MappedRwLockReadGuard {
+            state: &rwlock.state,
+            value,
+        }
+    }
+}
+
 impl<'a, M, T> Drop for RwLockReadGuard<'a, M, T>
 where
     M: RawMutex,
@@ -228,10 +345,7 @@ where
     fn drop(&mut self) {
         self.rwlock.state.lock(|s| {
             let mut s = unwrap!(s.try_borrow_mut());
-            s.readers -= 1;
-            if s.readers == 0 {
-                s.waker.wake();
-            }
+            s.read_unlock();
         })
     }
 }
@@ -285,6 +399,25 @@ where
     rwlock: &'a RwLock<R, T>,
 }
 
+impl<'a, R, T> RwLockWriteGuard<'a, R, T>
+where
+    R: RawMutex,
+    T: ?Sized,
+{
+    /// Returns a locked view over a portion of the locked data.
+    pub fn map<U: ?Sized>(this: Self, fun: impl FnOnce(&mut T) -> &mut U) -> MappedRwLockWriteGuard<'a, R, U> {
+        let rwlock = this.rwlock;
+        let value = fun(unsafe { &mut *rwlock.inner.get() });
+        // Don't run the `drop` method for RwLockWriteGuard. The ownership of the underlying
+        // locked state is being moved to the returned MappedRwLockWriteGuard.
+        mem::forget(this);
+        MappedRwLockWriteGuard {
+            state: &rwlock.state,
+            value,
+        }
+    }
+}
+
 impl<'a, R, T> Drop for RwLockWriteGuard<'a, R, T>
 where
     R: RawMutex,
@@ -293,8 +426,7 @@ where
     fn drop(&mut self) {
         self.rwlock.state.lock(|s| {
             let mut s = unwrap!(s.try_borrow_mut());
-            s.writer = false;
-            s.waker.wake();
+            s.write_unlock();
         })
     }
 }
@@ -344,10 +476,410 @@ where
     }
 }
 
+/// Async upgradable read lock guard.
+///
+/// Owning an instance of this type indicates having successfully locked the read-write lock for
+/// upgradable reading, and grants shared access to the contents. It can be atomically upgraded to a
+/// [`RwLockWriteGuard`] with [`RwLockUpgradableReadGuard::upgrade`], without letting any other writer
+/// in between.
+///
+/// Dropping it unlocks the read-write lock.
+#[clippy::has_significant_drop]
+#[must_use = "if unused the RwLock will immediately unlock"]
+pub struct RwLockUpgradableReadGuard<'a, R, T>
+where
+    R: RawMutex,
+    T: ?Sized,
+{
+    rwlock: &'a RwLock<R, T>,
+}
+
+impl<'a, R, T> RwLockUpgradableReadGuard<'a, R, T>
+where
+    R: RawMutex,
+    T: ?Sized,
+{
+    /// Upgrade to a write lock.
+    ///
+    /// This will wait for all plain readers to release the lock. New readers are still admitted in
+    /// the meantime unless the lock prefers writers. Dropping the returned future before it completes
+    /// releases the upgradable read lock.
+    pub fn upgrade(this: Self) -> impl Future<Output = RwLockWriteGuard<'a, R, T>> {
+        let rwlock = this.rwlock;
+        // The upgradable read lock is now owned by the returned future.
+        mem::forget(this);
+        WriteFuture {
+            rwlock,
+            upgrading: true,
+            waiting: false,
+        }
+    }
+
+    /// Attempt to immediately upgrade to a write lock.
+    ///
+    /// If the rwlock is still locked by other readers, the upgradable read guard is returned instead.
+    pub fn try_upgrade(this: Self) -> Result<RwLockWriteGuard<'a, R, T>, Self> {
+        let upgraded = this.rwlock.state.lock(|s| {
+            let mut s = s.borrow_mut();
+            if s.readers > 0 {
+                return false;
+            }
+            s.upgradable = false;
+            s.writer = true;
+            true
+        });
+
+        if upgraded {
+            let rwlock = this.rwlock;
+            // The lock has been upgraded, so the upgradable read lock must not be released.
+            mem::forget(this);
+            Ok(RwLockWriteGuard { rwlock })
+        } else {
+            Err(this)
+        }
+    }
+}
+
+impl<'a, R, T> Drop for RwLockUpgradableReadGuard<'a, R, T>
+where
+    R: RawMutex,
+    T: ?Sized,
+{
+    fn drop(&mut self) {
+        self.rwlock.state.lock(|s| {
+            let mut s = unwrap!(s.try_borrow_mut());
+            s.upgradable_unlock();
+        })
+    }
+}
+
+impl<'a, R, T> Deref for RwLockUpgradableReadGuard<'a, R, T>
+where
+    R: RawMutex,
+    T: ?Sized,
+{
+    type Target = T;
+    fn deref(&self) -> &Self::Target {
+        // Safety: the RwLockUpgradableReadGuard represents shared access to the contents
+        // of the read-write lock, so it's OK to get it.
+        unsafe { &*(self.rwlock.inner.get() as *const T) }
+    }
+}
+
+impl<'a, R, T> fmt::Debug for RwLockUpgradableReadGuard<'a, R, T>
+where
+    R: RawMutex,
+    T: ?Sized + fmt::Debug,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Debug::fmt(&**self, f)
+    }
+}
+
+impl<'a, R, T> fmt::Display for RwLockUpgradableReadGuard<'a, R, T>
+where
+    R: RawMutex,
+    T: ?Sized + fmt::Display,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Display::fmt(&**self, f)
+    }
+}
+
+/// Future returned by [`RwLock::write`] and [`RwLockUpgradableReadGuard::upgrade`].
+///
+/// Writers are counted while waiting so that a lock preferring writers can hold back new readers.
+/// This needs to be undone when the future is dropped, which is why this is not a `poll_fn`.
+struct WriteFuture<'a, R, T>
+where
+    R: RawMutex,
+    T: ?Sized,
+{
+    rwlock: &'a RwLock<R, T>,
+    /// Whether this future owns the upgradable read lock.
+    upgrading: bool,
+    /// Whether this future is counted in `writers_waiting`.
+    waiting: bool,
+}
+
+impl<'a, R, T> Unpin for WriteFuture<'a, R, T>
+where
+    R: RawMutex,
+    T: ?Sized,
+{
+}
+
+impl<'a, R, T> Future for WriteFuture<'a, R, T>
+where
+    R: RawMutex,
+    T: ?Sized,
+{
+    type Output = RwLockWriteGuard<'a, R, T>;
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let this = &mut *self;
+        let ready = this.rwlock.state.lock(|s| {
+            let mut s = s.borrow_mut();
+            // The upgradable read lock already excludes writers, only plain readers are left.
+            let available = if this.upgrading { s.readers == 0 } else { s.can_write() };
+            if available {
+                if this.waiting {
+                    s.writers_waiting -= 1;
+                    this.waiting = false;
+                }
+                if this.upgrading {
+                    s.upgradable = false;
+                    this.upgrading = false;
+                }
+                s.writer = true;
+                true
+            } else {
+                if !this.waiting {
+                    s.writers_waiting += 1;
+                    this.waiting = true;
+                }
+                s.write_waker.register(cx.waker());
+                false
+            }
+        });
+
+        if ready {
+            Poll::Ready(RwLockWriteGuard { rwlock: this.rwlock })
+        } else {
+            Poll::Pending
+        }
+    }
+}
+
+impl<'a, R, T> Drop for WriteFuture<'a, R, T>
+where
+    R: RawMutex,
+    T: ?Sized,
+{
+    fn drop(&mut self) {
+        if !self.waiting && !self.upgrading {
+            return;
+        }
+
+        self.rwlock.state.lock(|s| {
+            let mut s = unwrap!(s.try_borrow_mut());
+            if self.waiting {
+                s.writers_waiting -= 1;
+                // Readers held back by this writer may proceed, and the registered waker may have
+                // been ours, so hand the wakeup over to the other writers too.
+                if s.writers_waiting == 0 {
+                    s.read_waker.wake();
+                }
+                s.write_waker.wake();
+            }
+            if self.upgrading {
+                s.upgradable_unlock();
+            }
+        })
+    }
+}
+
+/// A handle to a read-locked `RwLock` that has had a function applied to it via
+/// [`RwLockReadGuard::map`] or [`MappedRwLockReadGuard::map`].
+///
+/// This can be used to hold a subfield of the protected data.
+#[clippy::has_significant_drop]
+pub struct MappedRwLockReadGuard<'a, M, T>
+where
+    M: RawMutex,
+    T: ?Sized,
+{
+    state: &'a BlockingMutex<M, RefCell<State>>,
+    value: *const T,
+}
+
+impl<'a, M, T> MappedRwLockReadGuard<'a, M, T>
+where
+    M: RawMutex,
+    T: ?Sized,
+{
+    /// Returns a locked view over a portion of the locked data.
+    pub fn map<U: ?Sized>(this: Self, fun: impl FnOnce(&T) -> &U) -> MappedRwLockReadGuard<'a, M, U> {
+        let state = this.state;
+        let value = fun(unsafe { &*this.value });
+        // Don't run the `drop` method for MappedRwLockReadGuard. The ownership of the underlying
+        // locked state is being moved to the returned MappedRwLockReadGuard.
+        mem::forget(this);
+        MappedRwLockReadGuard { state, value }
+    }
+}
+
+impl<'a, M, T> Deref for MappedRwLockReadGuard<'a, M, T>
+where
+    M: RawMutex,
+    T: ?Sized,
+{
+    type Target = T;
+    fn deref(&self) -> &Self::Target {
+        // Safety: the MappedRwLockReadGuard represents shared access to the contents
+        // of the read-write lock, so it's OK to get it.
+        unsafe { &*self.value }
+    }
+}
+
+impl<'a, M, T> Drop for MappedRwLockReadGuard<'a, M, T>
+where
+    M: RawMutex,
+    T: ?Sized,
+{
+    fn drop(&mut self) {
+        self.state.lock(|s| {
+            let mut s = unwrap!(s.try_borrow_mut());
+            s.read_unlock();
+        })
+    }
+}
+
+unsafe impl<M, T> Send for MappedRwLockReadGuard<'_, M, T>
+where
+    M: RawMutex + Sync,
+    T: Sync + ?Sized,
+{
+}
+
+unsafe impl<M, T> Sync for MappedRwLockReadGuard<'_, M, T>
+where
+    M: RawMutex + Sync,
+    T: Sync + ?Sized,
+{
+}
+
+impl<'a, M, T> fmt::Debug for MappedRwLockReadGuard<'a, M, T>
+where
+    M: RawMutex,
+    T: ?Sized + fmt::Debug,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Debug::fmt(&**self, f)
+    }
+}
+
+impl<'a, M, T> fmt::Display for MappedRwLockReadGuard<'a, M, T>
+where
+    M: RawMutex,
+    T: ?Sized + fmt::Display,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Display::fmt(&**self, f)
+    }
+}
+
+/// A handle to a write-locked `RwLock` that has had a function applied to it via
+/// [`RwLockWriteGuard::map`] or [`MappedRwLockWriteGuard::map`].
+///
+/// This can be used to hold a subfield of the protected data.
+#[clippy::has_significant_drop]
+pub struct MappedRwLockWriteGuard<'a, M, T>
+where
+    M: RawMutex,
+    T: ?Sized,
+{
+    state: &'a BlockingMutex<M, RefCell<State>>,
+    value: *mut T,
+}
+
+impl<'a, M, T> MappedRwLockWriteGuard<'a, M, T>
+where
+    M: RawMutex,
+    T: ?Sized,
+{
+    /// Returns a locked view over a portion of the locked data.
+    pub fn map<U: ?Sized>(this: Self, fun: impl FnOnce(&mut T) -> &mut U) -> MappedRwLockWriteGuard<'a, M, U> {
+        let state = this.state;
+        let value = fun(unsafe { &mut *this.value });
+        // Don't run the `drop` method for MappedRwLockWriteGuard. The ownership of the underlying
+        // locked state is being moved to the returned MappedRwLockWriteGuard.
+        mem::forget(this);
+        MappedRwLockWriteGuard { state, value }
+    }
+}
+
+impl<'a, M, T> Deref for MappedRwLockWriteGuard<'a, M, T>
+where
+    M: RawMutex,
+    T: ?Sized,
+{
+    type Target = T;
+    fn deref(&self) -> &Self::Target {
+        // Safety: the MappedRwLockWriteGuard represents exclusive access to the contents
+        // of the read-write lock, so it's OK to get it.
+        unsafe { &*self.value }
+    }
+}
+
+impl<'a, M, T> DerefMut for MappedRwLockWriteGuard<'a, M, T>
+where
+    M: RawMutex,
+    T: ?Sized,
+{
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        // Safety: the MappedRwLockWriteGuard represents exclusive access to the contents
+        // of the read-write lock, so it's OK to get it.
+        unsafe { &mut *self.value }
+    }
+}
+
+impl<'a, M, T> Drop for MappedRwLockWriteGuard<'a, M, T>
+where
+    M: RawMutex,
+    T: ?Sized,
+{
+    fn drop(&mut self) {
+        self.state.lock(|s| {
+            let mut s = unwrap!(s.try_borrow_mut());
+            s.write_unlock();
+        })
+    }
+}
+
+unsafe impl<M, T> Send for MappedRwLockWriteGuard<'_, M, T>
+where
+    M: RawMutex + Sync,
+    T: Send + ?Sized,
+{
+}
+
+unsafe impl<M, T> Sync for MappedRwLockWriteGuard<'_, M, T>
+where
+    M: RawMutex + Sync,
+    T: Sync + ?Sized,
+{
+}
+
+impl<'a, M, T> fmt::Debug for MappedRwLockWriteGuard<'a, M, T>
+where
+    M: RawMutex,
+    T: ?Sized + fmt::Debug,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Debug::fmt(&**self, f)
+    }
+}
+
+impl<'a, M, T> fmt::Display for MappedRwLockWriteGuard<'a, M, T>
+where
+    M: RawMutex,
+    T: ?Sized + fmt::Display,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Display::fmt(&**self, f)
+    }
+}
+
 #[cfg(test)]
 mod tests {
+    use core::pin::pin;
+    use core::task::Poll;
+
+    use futures_util::poll;
+
     use crate::blocking_mutex::raw::NoopRawMutex;
-    use crate::rwlock::RwLock;
+    use crate::rwlock::{Policy, RwLock, RwLockReadGuard, RwLockUpgradableReadGuard, RwLockWriteGuard};
 
     #[futures_test::test]
     async fn read_guard_releases_lock_when_dropped() {
@@ -383,4 +915,124 @@ mod tests {
 
         assert_eq!(*rwlock.read().await, [0, 2]);
     }
+
+    #[futures_test::test]
+    async fn mapped_guards_release_lock_when_dropped() {
+        let rwlock: RwLock<NoopRawMutex, [i32; 2]> = RwLock::new([0, 1]);
+
+        {
+            let guard = rwlock.write().await;
+            let mut mapped = RwLockWriteGuard::map(guard, |this| &mut this[1]);
+            assert_eq!(*mapped, 1);
+            *mapped = 2;
+            assert!(rwlock.try_read().is_err());
+        }
+
+        {
+            let guard = rwlock.read().await;
+            let mapped = RwLockReadGuard::map(guard, |this| &this[1]);
+            assert_eq!(*mapped, 2);
+            assert!(rwlock.try_write().is_err());
+            assert!(rwlock.try_read().is_ok());
+        }
+
+        assert_eq!(*rwlock.write().await, [0, 2]);
+    }
+
+    #[futures_test::test]
+    async fn reader_preferred_admits_readers_while_writer_waits() {
+        let rwlock: RwLock<NoopRawMutex, u8> = RwLock::new(0);
+
+        let reader = rwlock.read().await;
+        let mut writer = pin!(rwlock.write());
+        assert!(poll!(writer.as_mut()).is_pending());
+
+        // New readers still get in, the writer has to wait for all of them.
+        let reader2 = rwlock.read().await;
+        drop(reader);
+        assert!(poll!(writer.as_mut()).is_pending());
+        drop(reader2);
+        assert!(poll!(writer.as_mut()).is_ready());
+    }
+
+    #[futures_test::test]
+    async fn writer_preferred_holds_back_new_readers() {
+        let rwlock: RwLock<NoopRawMutex, u8> = RwLock::with_policy(0, Policy::WriterPreferred);
+
+        let reader = rwlock.read().await;
+        let mut writer = pin!(rwlock.write());
+        assert!(poll!(writer.as_mut()).is_pending());
+
+        let mut reader2 = pin!(rwlock.read());
+        assert!(poll!(reader2.as_mut()).is_pending());
+        assert!(rwlock.try_read().is_err());
+        assert!(rwlock.try_upgradable_read().is_err());
+
+        drop(reader);
+        let Poll::Ready(mut guard) = poll!(writer.as_mut()) else {
+            panic!("writer should have the lock");
+        };
+        *guard = 1;
+        assert!(poll!(reader2.as_mut()).is_pending());
+        drop(guard);
+        assert_eq!(poll!(reader2.as_mut()).map(|guard| *guard), Poll::Ready(1));
+    }
+
+    #[futures_test::test]
+    async fn cancelled_writer_releases_readers() {
+        let rwlock: RwLock<NoopRawMutex, u8> = RwLock::with_policy(0, Policy::WriterPreferred);
+
+        let reader = rwlock.read().await;
+        {
+            let mut writer = pin!(rwlock.write());
+            assert!(poll!(writer.as_mut()).is_pending());
+            assert!(rwlock.try_read().is_err());
+        }
+
+        assert!(rwlock.try_read().is_ok());
+        drop(reader);
+    }
+
+    #[futures_test::test]
+    async fn upgradable_read() {
+        let rwlock: RwLock<NoopRawMutex, u8> = RwLock::new(0);
+
+        let upgradable = rwlock.upgradable_read().await;
+        assert_eq!(*upgradable, 0);
+        // Plain readers are allowed alongside, writers and other upgradable readers are not.
+        let reader = rwlock.try_read().unwrap();
+        assert!(rwlock.try_upgradable_read().is_err());
+        assert!(rwlock.try_write().is_err());
+
+        let upgradable = RwLockUpgradableReadGuard::try_upgrade(upgradable).unwrap_err();
+        let mut upgrade = pin!(RwLockUpgradableReadGuard::upgrade(upgradable));
+        assert!(poll!(upgrade.as_mut()).is_pending());
+        drop(reader);
+        let Poll::Ready(mut guard) = poll!(upgrade.as_mut()) else {
+            panic!("upgrade should have completed");
+        };
+        *guard = 1;
+        assert!(rwlock.try_read().is_err());
+        drop(guard);
+
+        let upgradable = rwlock.try_upgradable_read().unwrap();
+        let guard = RwLockUpgradableReadGuard::try_upgrade(upgradable).unwrap();
+        assert_eq!(*guard, 1);
+    }
+
+    #[futures_test::test]
+    async fn cancelled_upgrade_releases_lock() {
+        let rwlock: RwLock<NoopRawMutex, u8> = RwLock::new(0);
+
+        let reader = rwlock.read().await;
+        {
+            let upgradable = rwlock.upgradable_read().await;
+            let mut upgrade = pin!(RwLockUpgradableReadGuard::upgrade(upgradable));
+            assert!(poll!(upgrade.as_mut()).is_pending());
+        }
+        drop(reader);
+
+        assert!(rwlock.try_upgradable_read().is_ok());
+        assert!(rwlock.try_write().is_ok());
+    }
 }
@@ -429,6 +429,11 @@ impl<const N: usize> FairSemaphoreState<N> {
     fn cancel(&mut self, ticket: Option<usize>) {
         if let Some(ticket) = ticket {
             self.set_waker(ticket, None);
+
+            // If the head of the queue gave up, it may have been woken already, so pass the wakeup on.
+            if ticket == self.next_ticket && self.permits > 0 {
+                self.wake();
+            }
         }
     }
 
@@ -613,9 +618,11 @@ mod tests {
 
     mod fair {
         use core::pin::pin;
+        use core::task::Context;
         use core::time::Duration;
 
         use futures_executor::ThreadPool;
+        use futures_test::task::new_count_waker;
         use futures_timer::Delay;
         use futures_util::poll;
         use futures_util::task::SpawnExt;
@@ -745,6 +752,32 @@ mod tests {
             assert!(c.is_ready());
         }
 
+        #[futures_test::test]
+        async fn cancel_wakes_next() {
+            let semaphore = FairSemaphore::<NoopRawMutex, 2>::new(0);
+            let (waker, count) = new_count_waker();
+
+            let c_fut = semaphore.acquire(1);
+            let mut c_fut = pin!(c_fut);
+            {
+                let b_fut = semaphore.acquire(1);
+                let mut b_fut = pin!(b_fut);
+                let b = poll!(b_fut.as_mut()); // Poll `b_fut` once so it is registered
+                assert!(b.is_pending());
+
+                let c = c_fut.as_mut().poll(&mut Context::from_waker(&waker));
+                assert!(c.is_pending()); // `c` is blocked behind `b`
+
+                semaphore.release(1);
+                assert_eq!(count, 0); // Only `b` is woken
+            }
+
+            // `b` was dropped without taking the permit, so `c` must be woken instead.
+            assert_eq!(count, 1);
+            let c = poll!(c_fut.as_mut());
+            assert!(c.is_ready());
+        }
+
         #[futures_test::test]
         async fn wakers() {
             let executor = ThreadPool::new().unwrap();
@@ -6,6 +6,8 @@ use core::marker::PhantomData;
 use core::ops::{Deref, DerefMut};
 use core::task::{Context, Poll};
 
+use heapless::Vec;
+
 use crate::blocking_mutex::Mutex;
 use crate::blocking_mutex::raw::RawMutex;
 use crate::waitqueue::MultiWakerRegistration;
@@ -65,19 +67,98 @@ use crate::waitqueue::MultiWakerRegistration;
 /// };
 /// block_on(f);
 /// ```
+///
+/// ## History
+///
+/// A `Watch` can also keep the last `H` values sent, the current one included, in a ring buffer.
+/// Receivers then get every value they missed from [`Rcv::changed`], oldest first, as long as it
+/// is still kept, instead of just the latest one. This lets receivers created later catch up on
+/// the recent changes. The initial value given to [`Watch::new_with`] is not part of the history,
+/// as it was not sent.
+///
+/// ```
+/// use embassy_sync::watch::Watch;
+/// use embassy_sync::blocking_mutex::raw::CriticalSectionRawMutex;
+///
+/// static WATCH: Watch<CriticalSectionRawMutex, u8, 2, 2> = Watch::new();
+///
+/// let snd = WATCH.sender();
+/// snd.send(10);
+/// snd.send(20);
+/// snd.send(30);
+///
+/// // A receiver created now gets the 2 last values sent, oldest first
+/// let mut rcv = WATCH.receiver().unwrap();
+/// assert_eq!(rcv.try_changed(), Some(20));
+/// assert_eq!(rcv.try_changed(), Some(30));
+/// assert_eq!(rcv.try_changed(), None);
+/// ```
 #[derive(Debug)]
-pub struct Watch<M: RawMutex, T: Clone, const N: usize> {
-    mutex: Mutex<M, RefCell<WatchState<T, N>>>,
+pub struct Watch<M: RawMutex, T: Clone, const N: usize, const H: usize = 0> {
+    mutex: Mutex<M, RefCell<WatchState<T, N, H>>>,
 }
 
 #[derive(Debug)]
-struct WatchState<T: Clone, const N: usize> {
+struct WatchState<T: Clone, const N: usize, const H: usize> {
     data: Option<T>,
+    /// The last values sent, with their message ID, in a ring buffer starting at `history_start`.
+    history: Vec<(u64, T), H>,
+    history_start: usize,
     current_id: u64,
     wakers: MultiWakerRegistration<N>,
     receiver_count: usize,
 }
 
+impl<T: Clone, const N: usize, const H: usize> WatchState<T, N, H> {
+    const fn new(data: Option<T>) -> Self {
+        Self {
+            data,
+            history: Vec::new(),
+            history_start: 0,
+            current_id: 0,
+            wakers: MultiWakerRegistration::new(),
+            receiver_count: 0,
+        }
+    }
+
+    /// Mark the current value as sent, keeping it in the history, dropping the oldest value if it
+    /// is full.
+    fn sent(&mut self) {
+        self.current_id += 1;
+        if let Some(data) = self.data.as_ref().filter(|_| H > 0) {
+            let entry = (self.current_id, data.clone());
+            if self.history.is_full() {
+                self.history[self.history_start] = entry;
+                self.history_start = (self.history_start + 1) % H;
+            } else {
+                // Not full, so this can't fail
+                let _ = self.history.push(entry);
+            }
+        }
+        self.wakers.wake();
+    }
+
+    /// The values in the history, with their message ID, oldest first.
+    fn history(&self) -> impl Iterator<Item = &(u64, T)> {
+        let (newest, oldest) = self.history.split_at(self.history_start);
+        oldest.iter().chain(newest)
+    }
+
+    /// Forget all the values in the history.
+    fn clear_history(&mut self) {
+        self.history.clear();
+        self.history_start = 0;
+    }
+
+    /// The oldest value in the history sent after message `id` and matching `f`, with its
+    /// message ID.
+    fn next_in_history(&self, id: u64, f: &mut dyn Fn(&T) -> bool) -> Option<(u64, &T)> {
+        self.history()
+            .find(|(at, data)| *at > id && f(data))
+            .map(|(at, data)| (*at, data))
+    }
+}
+
 trait SealedWatchBehavior<T> {
     /// Poll the `Watch` for the current value, making it as seen.
     fn poll_get(&self, id: &mut u64, cx: &mut Context<'_>) -> Poll<T>;
@@ -105,9 +186,12 @@ trait SealedWatchBehavior<T> {
     /// ## This method should not be called by the user.
     fn drop_receiver(&self);
 
-    /// Clears the value of the `Watch`.
+    /// Clears the value and the history of the `Watch`.
     fn clear(&self);
 
+    /// Calls `f` with each value kept in the history of the `Watch`, oldest first.
+    fn history(&self, f: &mut dyn FnMut(&T));
+
     /// Sends a new value to the `Watch`.
     fn send(&self, val: T);
 
@@ -134,7 +218,7 @@ pub trait WatchBehavior<T: Clone>: SealedWatchBehavior<T> {
     fn contains_value(&self) -> bool;
 }
 
-impl<M: RawMutex, T: Clone, const N: usize> SealedWatchBehavior<T> for Watch<M, T, N> {
+impl<M: RawMutex, T: Clone, const N: usize, const H: usize> SealedWatchBehavior<T> for Watch<M, T, N, H> {
     fn poll_get(&self, id: &mut u64, cx: &mut Context<'_>) -> Poll<T> {
         self.mutex.lock(|state| {
             let mut s = state.borrow_mut();
@@ -170,6 +254,10 @@ impl<M: RawMutex, T: Clone, const N: usize> SealedWatchBehavior<T> for Watch<M,
     fn poll_changed(&self, id: &mut u64, cx: &mut Context<'_>) -> Poll<T> {
         self.mutex.lock(|state| {
             let mut s = state.borrow_mut();
+            if let Some((at, data)) = s.next_in_history(*id, &mut |_| true) {
+                *id = at;
+                return Poll::Ready(data.clone());
+            }
             match (&s.data, s.current_id > *id) {
                 (Some(data), true) => {
                     *id = s.current_id;
@@ -186,6 +274,10 @@ impl<M: RawMutex, T: Clone, const N: usize> SealedWatchBehavior<T> for Watch<M,
     fn try_changed(&self, id: &mut u64) -> Option<T> {
         self.mutex.lock(|state| {
             let s = state.borrow();
+            if let Some((at, data)) = s.next_in_history(*id, &mut |_| true) {
+                *id = at;
+                return Some(data.clone());
+            }
             match s.current_id > *id {
                 true => {
                     *id = s.current_id;
@@ -199,6 +291,10 @@ impl<M: RawMutex, T: Clone, const N: usize> SealedWatchBehavior<T> for Watch<M,
     fn poll_changed_and(&self, id: &mut u64, f: &mut dyn Fn(&T) -> bool, cx: &mut Context<'_>) -> Poll<T> {
         self.mutex.lock(|state| {
             let mut s = state.borrow_mut();
+            if let Some((at, data)) = s.next_in_history(*id, f) {
+                *id = at;
+                return Poll::Ready(data.clone());
+            }
             match (&s.data, s.current_id > *id) {
                 (Some(data), true) if f(data) => {
                     *id = s.current_id;
@@ -215,6 +311,10 @@ impl<M: RawMutex, T: Clone, const N: usize> SealedWatchBehavior<T> for Watch<M,
     fn try_changed_and(&self, id: &mut u64, f: &mut dyn Fn(&T) -> bool) -> Option<T> {
         self.mutex.lock(|state| {
             let s = state.borrow();
+            if let Some((at, data)) = s.next_in_history(*id, f) {
+                *id = at;
+                return Some(data.clone());
+            }
             match (&s.data, s.current_id > *id) {
                 (Some(data), true) if f(data) => {
                     *id = s.current_id;
@@ -236,15 +336,20 @@ impl<M: RawMutex, T: Clone, const N: usize> SealedWatchBehavior<T> for Watch<M,
         self.mutex.lock(|state| {
             let mut s = state.borrow_mut();
             s.data = None;
+            s.clear_history();
         })
     }
 
+    fn history(&self, f: &mut dyn FnMut(&T)) {
+        self.mutex
+            .lock(|state| state.borrow().history().for_each(|(_, data)| f(data)))
+    }
+
     fn send(&self, val: T) {
         self.mutex.lock(|state| {
             let mut s = state.borrow_mut();
-            s.data = Some(val);
-            s.current_id += 1;
-            s.wakers.wake();
+            s.data.replace(val);
+            s.sent();
         })
     }
 
@@ -252,8 +357,7 @@ impl<M: RawMutex, T: Clone, const N: usize> SealedWatchBehavior<T> for Watch<M,
         self.mutex.lock(|state| {
             let mut s = state.borrow_mut();
             f(&mut s.data);
-            s.current_id += 1;
-            s.wakers.wake();
+            s.sent();
         })
     }
 
@@ -261,14 +365,13 @@ impl<M: RawMutex, T: Clone, const N: usize> SealedWatchBehavior<T> for Watch<M,
         self.mutex.lock(|state| {
             let mut s = state.borrow_mut();
             if f(&mut s.data) {
-                s.current_id += 1;
-                s.wakers.wake();
+                s.sent();
             }
         })
     }
 }
 
-impl<M: RawMutex, T: Clone, const N: usize> WatchBehavior<T> for Watch<M, T, N> {
+impl<M: RawMutex, T: Clone, const N: usize, const H: usize> WatchBehavior<T> for Watch<M, T, N, H> {
     fn try_get(&self, id: Option<&mut u64>) -> Option<T> {
         self.mutex.lock(|state| {
             let s = state.borrow();
@@ -299,33 +402,23 @@ impl<M: RawMutex, T: Clone, const N: usize> WatchBehavior<T> for Watch<M, T, N>
     }
 }
 
-impl<M: RawMutex, T: Clone, const N: usize> Watch<M, T, N> {
-    /// Create a new `Watch` channel for `N` receivers.
+impl<M: RawMutex, T: Clone, const N: usize, const H: usize> Watch<M, T, N, H> {
+    /// Create a new `Watch` channel for `N` receivers, keeping the last `H` values sent.
     pub const fn new() -> Self {
         Self {
-            mutex: Mutex::new(RefCell::new(WatchState {
-                data: None,
-                current_id: 0,
-                wakers: MultiWakerRegistration::new(),
-                receiver_count: 0,
-            })),
+            mutex: Mutex::new(RefCell::new(WatchState::new(None))),
         }
     }
 
     /// Create a new `Watch` channel with default data.
     pub const fn new_with(data: T) -> Self {
         Self {
-            mutex: Mutex::new(RefCell::new(WatchState {
-                data: Some(data),
-                current_id: 0,
-                wakers: MultiWakerRegistration::new(),
-                receiver_count: 0,
-            })),
+            mutex: Mutex::new(RefCell::new(WatchState::new(Some(data)))),
         }
     }
 
     /// Create a new [`Sender`] for the `Watch`.
-    pub fn sender(&self) -> Sender<'_, M, T, N> {
+    pub fn sender(&self) -> Sender<'_, M, T, N, H> {
         Sender(Snd::new(self))
     }
 
@@ -336,7 +429,7 @@ impl<M: RawMutex, T: Clone, const N: usize> Watch<M, T, N> {
 
     /// Try to create a new [`Receiver`] for the `Watch`. If the
     /// maximum number of receivers has been reached, `None` is returned.
-    pub fn receiver(&self) -> Option<Receiver<'_, M, T, N>> {
+    pub fn receiver(&self) -> Option<Receiver<'_, M, T, N, H>> {
         self.mutex.lock(|state| {
             let mut s = state.borrow_mut();
             if s.receiver_count < N {
@@ -363,7 +456,7 @@ impl<M: RawMutex, T: Clone, const N: usize> Watch<M, T, N> {
     }
 
     /// Try to create a new [`AnonReceiver`] for the `Watch`.
-    pub fn anon_receiver(&self) -> AnonReceiver<'_, M, T, N> {
+    pub fn anon_receiver(&self) -> AnonReceiver<'_, M, T, N, H> {
         AnonReceiver(AnonRcv::new(self, 0))
     }
 
@@ -384,6 +477,12 @@ impl<M: RawMutex, T: Clone, const N: usize> Watch<M, T, N> {
         WatchBehavior::try_get(self, None)
     }
 
+    /// Returns the last `H` values sent, oldest first.
+    pub fn history(&self) -> Vec<T, H> {
+        self.mutex
+            .lock(|state| state.borrow().history().map(|(_, data)| data.clone()).collect())
+    }
+
     /// Tries to get the value of the `Watch` if it matches the predicate function `f`.
     pub fn try_get_and<F>(&self, mut f: F) -> Option<T>
     where
@@ -423,7 +522,7 @@ impl<'a, T: Clone, W: WatchBehavior<T> + ?Sized> Snd<'a, T, W> {
         self.watch.send(val)
     }
 
-    /// Clears the value of the `Watch`.
+    /// Clears the value and the history of the `Watch`.
     /// This will cause calls to [`Rcv::get`] to be pending.
     pub fn clear(&self) {
         self.watch.clear()
@@ -471,36 +570,36 @@ impl<'a, T: Clone, W: WatchBehavior<T> + ?Sized> Snd<'a, T, W> {
 /// For a simpler type definition, consider [`DynSender`] at the expense of
 /// some runtime performance due to dynamic dispatch.
 #[derive(Debug)]
-pub struct Sender<'a, M: RawMutex, T: Clone, const N: usize>(Snd<'a, T, Watch<M, T, N>>);
+pub struct Sender<'a, M: RawMutex, T: Clone, const N: usize, const H: usize = 0>(Snd<'a, T, Watch<M, T, N, H>>);
 
-impl<'a, M: RawMutex, T: Clone, const N: usize> Clone for Sender<'a, M, T, N> {
+impl<'a, M: RawMutex, T: Clone, const N: usize, const H: usize> Clone for Sender<'a, M, T, N, H> {
     fn clone(&self) -> Self {
         Self(self.0.clone())
     }
 }
 
-impl<'a, M: RawMutex, T: Clone, const N: usize> Sender<'a, M, T, N> {
+impl<'a, M: RawMutex, T: Clone, const N: usize, const H: usize> Sender<'a, M, T, N, H> {
     /// Converts the `Sender` into a [`DynSender`].
     pub fn as_dyn(self) -> DynSender<'a, T> {
         DynSender(Snd::new(self.watch))
     }
 }
 
-impl<'a, M: RawMutex, T: Clone, const N: usize> Into<DynSender<'a, T>> for Sender<'a, M, T, N> {
+impl<'a, M: RawMutex, T: Clone, const N: usize, const H: usize> Into<DynSender<'a, T>> for Sender<'a, M, T, N, H> {
     fn into(self) -> DynSender<'a, T> {
         self.as_dyn()
     }
 }
 
-impl<'a, M: RawMutex, T: Clone, const N: usize> Deref for Sender<'a, M, T, N> {
-    type Target = Snd<'a, T, Watch<M, T, N>>;
+impl<'a, M: RawMutex, T: Clone, const N: usize, const H: usize> Deref for Sender<'a, M, T, N, H> {
+    type Target = Snd<'a, T, Watch<M, T, N, H>>;
 
     fn deref(&self) -> &Self::Target {
         &self.0
     }
 }
 
-impl<'a, M: RawMutex, T: Clone, const N: usize> DerefMut for Sender<'a, M, T, N> {
+impl<'a, M: RawMutex, T: Clone, const N: usize, const H: usize> DerefMut for Sender<'a, M, T, N, H> {
     fn deref_mut(&mut self) -> &mut Self::Target {
         &mut self.0
     }
@@ -617,6 +716,15 @@ impl<'a, T: Clone, W: WatchBehavior<T> + ?Sized> Rcv<'a, T, W> {
     pub fn contains_value(&self) -> bool {
         self.watch.contains_value()
     }
+
+    /// Calls `f` with each value kept in the history of the `Watch`, oldest first. This doesn't
+    /// mark anything as seen.
+    pub fn history<F>(&self, mut f: F)
+    where
+        F: FnMut(&T),
+    {
+        self.watch.history(&mut f)
+    }
 }
 
 impl<'a, T: Clone, W: WatchBehavior<T> + ?Sized> Drop for Rcv<'a, T, W> {
@@ -676,12 +784,21 @@ impl<'a, T: Clone, W: WatchBehavior<T> + ?Sized> AnonRcv<'a, T, W> {
     pub fn contains_value(&self) -> bool {
         self.watch.contains_value()
     }
+
+    /// Calls `f` with each value kept in the history of the `Watch`, oldest first. This doesn't
+    /// mark anything as seen.
+    pub fn history<F>(&self, mut f: F)
+    where
+        F: FnMut(&T),
+    {
+        self.watch.history(&mut f)
+    }
 }
 
 /// A receiver of a `Watch` channel.
-pub struct Receiver<'a, M: RawMutex, T: Clone, const N: usize>(Rcv<'a, T, Watch<M, T, N>>);
+pub struct Receiver<'a, M: RawMutex, T: Clone, const N: usize, const H: usize = 0>(Rcv<'a, T, Watch<M, T, N, H>>);
 
-impl<'a, M: RawMutex, T: Clone, const N: usize> Receiver<'a, M, T, N> {
+impl<'a, M: RawMutex, T: Clone, const N: usize, const H: usize> Receiver<'a, M, T, N, H> {
     /// Converts the `Receiver` into a [`DynReceiver`].
     pub fn as_dyn(self) -> DynReceiver<'a, T> {
         let rcv = DynReceiver(Rcv::new(self.0.watch, self.at_id));
@@ -690,21 +807,21 @@ impl<'a, M: RawMutex, T: Clone, const N: usize> Receiver<'a, M, T, N> {
     }
 }
 
-impl<'a, M: RawMutex, T: Clone, const N: usize> Into<DynReceiver<'a, T>> for Receiver<'a, M, T, N> {
+impl<'a, M: RawMutex, T: Clone, const N: usize, const H: usize> Into<DynReceiver<'a, T>> for Receiver<'a, M, T, N, H> {
     fn into(self) -> DynReceiver<'a, T> {
         self.as_dyn()
     }
 }
 
-impl<'a, M: RawMutex, T: Clone, const N: usize> Deref for Receiver<'a, M, T, N> {
-    type Target = Rcv<'a, T, Watch<M, T, N>>;
+impl<'a, M: RawMutex, T: Clone, const N: usize, const H: usize> Deref for Receiver<'a, M, T, N, H> {
+    type Target = Rcv<'a, T, Watch<M, T, N, H>>;
 
     fn deref(&self) -> &Self::Target {
         &self.0
     }
 }
 
-impl<'a, M: RawMutex, T: Clone, const N: usize> DerefMut for Receiver<'a, M, T, N> {
+impl<'a, M: RawMutex, T: Clone, const N: usize, const H: usize> DerefMut for Receiver<'a, M, T, N, H> {
     fn deref_mut(&mut self) -> &mut Self::Target {
         &mut self.0
     }
@@ -732,9 +849,11 @@ impl<'a, T: Clone> DerefMut for DynReceiver<'a, T> {
 
 /// A receiver of a `Watch` channel that cannot `.await` values.
 #[derive(Debug)]
-pub struct AnonReceiver<'a, M: RawMutex, T: Clone, const N: usize>(AnonRcv<'a, T, Watch<M, T, N>>);
+pub struct AnonReceiver<'a, M: RawMutex, T: Clone, const N: usize, const H: usize = 0>(
+    AnonRcv<'a, T, Watch<M, T, N, H>>,
+);
 
-impl<'a, M: RawMutex, T: Clone, const N: usize> AnonReceiver<'a, M, T, N> {
+impl<'a, M: RawMutex, T: Clone, const N: usize, const H: usize> AnonReceiver<'a, M, T, N, H> {
     /// Converts the `Receiver` into a [`DynReceiver`].
     pub fn as_dyn(self) -> DynAnonReceiver<'a, T> {
         let rcv = DynAnonReceiver(AnonRcv::new(self.0.watch, self.at_id));
@@ -743,21 +862,23 @@ impl<'a, M: RawMutex, T: Clone, const N: usize> AnonReceiver<'a, M, T, N> {
     }
 }
 
-impl<'a, M: RawMutex, T: Clone, const N: usize> Into<DynAnonReceiver<'a, T>> for AnonReceiver<'a, M, T, N> {
+impl<'a, M: RawMutex, T: Clone, const N: usize, const H: usize> Into<DynAnonReceiver<'a, T>>
+    for AnonReceiver<'a, M, T, N, H>
+{
     fn into(self) -> DynAnonReceiver<'a, T> {
         self.as_dyn()
     }
 }
 
-impl<'a, M: RawMutex, T: Clone, const N: usize> Deref for AnonReceiver<'a, M, T, N> {
-    type Target = AnonRcv<'a, T, Watch<M, T, N>>;
+impl<'a, M: RawMutex, T: Clone, const N: usize, const H: usize> Deref for AnonReceiver<'a, M, T, N, H> {
+    type Target = AnonRcv<'a, T, Watch<M, T, N, H>>;
 
     fn deref(&self) -> &Self::Target {
         &self.0
     }
 }
 
-impl<'a, M: RawMutex, T: Clone, const N: usize> DerefMut for AnonReceiver<'a, M, T, N> {
+impl<'a, M: RawMutex, T: Clone, const N: usize, const H: usize> DerefMut for AnonReceiver<'a, M, T, N, H> {
     fn deref_mut(&mut self) -> &mut Self::Target {
         &mut self.0
     }
@@ -785,10 +906,13 @@ impl<'a, T: Clone> DerefMut for DynAnonReceiver<'a, T> {
 
 #[cfg(test)]
 mod tests {
+    use core::sync::atomic::{AtomicUsize, Ordering};
+    use std::vec::Vec;
+
     use futures_executor::block_on;
 
     use super::Watch;
-    use crate::blocking_mutex::raw::CriticalSectionRawMutex;
+    use crate::blocking_mutex::raw::{CriticalSectionRawMutex, NoopRawMutex};
 
     #[test]
     fn multiple_sends() {
@@ -1102,6 +1226,125 @@ mod tests {
         block_on(f);
     }
 
+    #[test]
+    fn history() {
+        static WATCH: Watch<CriticalSectionRawMutex, u8, 1, 3> = Watch::new();
+
+        let snd = WATCH.sender();
+        assert!(WATCH.history().is_empty());
+
+        snd.send(10);
+        snd.send_modify(|v| *v = Some(20));
+        // Unmodified values are not kept
+        snd.send_if_modified(|_| false);
+        snd.send_if_modified(|v| {
+            *v = Some(30);
+            true
+        });
+        assert_eq!(WATCH.history(), [10, 20, 30]);
+
+        // The oldest values are dropped once the history is full
+        for v in (40..=100).step_by(10) {
+            snd.send(v);
+        }
+        assert_eq!(WATCH.history(), [80, 90, 100]);
+
+        let rcv = WATCH.anon_receiver();
+        let mut history = Vec::new();
+        rcv.history(|v| history.push(*v));
+        assert_eq!(history, [80, 90, 100]);
+
+        snd.clear();
+        assert!(WATCH.history().is_empty());
+    }
+
+    #[test]
+    fn history_clones_only_sent_values() {
+        static CLONES: AtomicUsize = AtomicUsize::new(0);
+
+        #[derive(Debug, PartialEq)]
+        struct Counted(u8);
+
+        impl Clone for Counted {
+            fn clone(&self) -> Self {
+                CLONES.fetch_add(1, Ordering::Relaxed);
+                Counted(self.0)
+            }
+        }
+
+        let watch: Watch<NoopRawMutex, Counted, 1, 2> = Watch::new();
+        let snd = watch.sender();
+
+        snd.send(Counted(1));
+        assert_eq!(CLONES.swap(0, Ordering::Relaxed), 1);
+
+        snd.send_if_modified(|_| false);
+        assert_eq!(CLONES.swap(0, Ordering::Relaxed), 0);
+
+        snd.send_if_modified(|v| {
+            v.as_mut().unwrap().0 = 2;
+            true
+        });
+        assert_eq!(CLONES.swap(0, Ordering::Relaxed), 1);
+
+        snd.send_modify(|v| *v = Some(Counted(3)));
+        assert_eq!(CLONES.swap(0, Ordering::Relaxed), 1);
+
+        // Without a history, nothing is cloned
+        let watch: Watch<NoopRawMutex, Counted, 1> = Watch::new();
+        let snd = watch.sender();
+        snd.send(Counted(1));
+        snd.send_if_modified(|_| false);
+        snd.send_modify(|v| *v = Some(Counted(2)));
+        assert_eq!(CLONES.load(Ordering::Relaxed), 0);
+    }
+
+    #[test]
+    fn history_with_initial_value() {
+        let watch: Watch<NoopRawMutex, u8, 1, 2> = Watch::new_with(10);
+        let mut rcv = watch.receiver().unwrap();
+
+        // The initial value was not sent
+        assert!(watch.history().is_empty());
+        assert_eq!(rcv.try_changed(), None);
+        assert_eq!(rcv.try_get(), Some(10));
+
+        watch.sender().send(20);
+        assert_eq!(watch.history(), [20]);
+        assert_eq!(rcv.try_changed(), Some(20));
+    }
+
+    #[test]
+    fn late_receiver_catches_up() {
+        let f = async {
+            static WATCH: Watch<CriticalSectionRawMutex, u8, 2, 3> = Watch::new();
+
+            let snd = WATCH.sender();
+            for v in 1..=5 {
+                snd.send(v);
+            }
+
+            // A receiver created now gets the values still kept in the history, oldest first
+            let mut rcv = WATCH.receiver().unwrap();
+            assert_eq!(rcv.changed().await, 3);
+            assert_eq!(rcv.changed().await, 4);
+            assert_eq!(rcv.changed_and(|v| *v == 5).await, 5);
+            assert_eq!(rcv.try_changed(), None);
+
+            // A receiver catching up with a predicate skips the values not matching it
+            let mut rcv = WATCH.dyn_receiver().unwrap();
+            assert_eq!(rcv.changed_and(|v| v % 2 == 0).await, 4);
+            assert_eq!(rcv.try_changed(), Some(5));
+
+            // Getting the current value skips the history
+            snd.send(6);
+            let mut rcv = WATCH.anon_receiver();
+            assert_eq!(rcv.try_get(), Some(6));
+            assert_eq!(rcv.try_changed(), None);
+        };
+        block_on(f);
+    }
+
     #[test]
     fn contains_value() {
         let f = async {
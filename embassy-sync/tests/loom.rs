@@ -0,0 +1,301 @@
+//! Model-checked concurrency tests.
+//!
+//! These tests explore the thread interleavings of the primitives with [loom](https://docs.rs/loom),
+//! catching lost wakeups and capacity-accounting bugs that single-threaded unit tests cannot see.
+//!
+//! Run them with:
+//!
+//! ```text
+//! RUSTFLAGS="--cfg loom" cargo test --test loom --release
+//! ```
+#![cfg(loom)]
+
+use std::future::Future;
+use std::pin::pin;
+use std::sync::OnceLock;
+use std::task::{Context, Poll, RawWaker, RawWakerVTable, Waker};
+
+use embassy_sync::blocking_mutex::raw::RawMutex;
+use embassy_sync::pubsub::{PubSubChannel, WaitResult};
+use embassy_sync::rwlock::{Policy, RwLock, RwLockUpgradableReadGuard};
+use embassy_sync::semaphore::{FairSemaphore, Semaphore};
+use embassy_sync::watch::Watch;
+use futures_util::FutureExt;
+use loom::sync::{Arc, Notify};
+use loom::thread;
+
+/// A [`RawMutex`] backed by a loom mutex, so the model checker sees every lock acquisition.
+///
+/// The underlying loom mutex is created lazily on first use, because loom objects cannot be
+/// created in const context. Instances must therefore never outlive a single `loom::model`
+/// execution, i.e. they must not be placed in a `static`.
+///
+/// Unlike [`CriticalSectionRawMutex`](embassy_sync::blocking_mutex::raw::CriticalSectionRawMutex)
+/// this mutex is not reentrant. None of the primitives lock reentrantly, and loom will report a
+/// deadlock if that ever changes.
+struct LoomRawMutex {
+    inner: OnceLock<loom::sync::Mutex<()>>,
+}
+
+unsafe impl RawMutex for LoomRawMutex {
+    const INIT: Self = Self { inner: OnceLock::new() };
+
+    fn lock<R>(&self, f: impl FnOnce() -> R) -> R {
+        let _guard = self.inner.get_or_init(|| loom::sync::Mutex::new(())).lock().unwrap();
+        f()
+    }
+}
+
+/// Drive a future to completion on the current loom thread.
+///
+/// `loom::future::block_on` is not used because its wakers never compare equal under
+/// [`Waker::will_wake`], which makes `MultiWakerRegistration` wake the polling task on every
+/// registration and the model never terminates. Using a single static vtable avoids that.
+fn block_on<F: Future>(f: F) -> F::Output {
+    static VTABLE: RawWakerVTable = RawWakerVTable::new(
+        |data| {
+            unsafe { std::sync::Arc::increment_strong_count(data as *const Notify) };
+            RawWaker::new(data, &VTABLE)
+        },
+        |data| unsafe { std::sync::Arc::from_raw(data as *const Notify) }.notify(),
+        |data| unsafe { &*(data as *const Notify) }.notify(),
+        |data| unsafe { std::sync::Arc::decrement_strong_count(data as *const Notify) },
+    );
+
+    let notify = std::sync::Arc::new(Notify::new());
+    let data = std::sync::Arc::into_raw(notify.clone()) as *const ();
+    let waker = unsafe { Waker::from_raw(RawWaker::new(data, &VTABLE)) };
+    let mut cx = Context::from_waker(&waker);
+
+    let mut f = pin!(f);
+    loop {
+        if let Poll::Ready(val) = f.as_mut().poll(&mut cx) {
+            return val;
+        }
+        notify.wait();
+    }
+}
+
+#[test]
+fn watch_send_changed() {
+    loom::model(|| {
+        let watch = Arc::new(Watch::<LoomRawMutex, u8, 1>::new());
+        let mut rcv = watch.receiver().unwrap();
+
+        let sender = {
+            let watch = watch.clone();
+            thread::spawn(move || {
+                let snd = watch.sender();
+                snd.send(1);
+                snd.send(2);
+            })
+        };
+
+        // Whatever interleaving happens, the receiver must eventually observe the latest value.
+        block_on(async { while rcv.changed().await != 2 {} });
+        sender.join().unwrap();
+        assert_eq!(rcv.try_changed(), None);
+    });
+}
+
+#[test]
+fn watch_receiver_drop_frees_slot() {
+    loom::model(|| {
+        let watch = Arc::new(Watch::<LoomRawMutex, u8, 1>::new());
+
+        let dropper = {
+            let watch = watch.clone();
+            thread::spawn(move || {
+                let mut rcv = watch.receiver().unwrap();
+                let _ = rcv.try_changed();
+            })
+        };
+        watch.sender().send(1);
+        dropper.join().unwrap();
+
+        // The dropped receiver must have given its slot back, regardless of the interleaving.
+        let mut rcv = watch.receiver().unwrap();
+        assert!(watch.receiver().is_none());
+        assert_eq!(rcv.try_changed(), Some(1));
+    });
+}
+
+#[test]
+fn pubsub_publish_waits_for_space() {
+    loom::model(|| {
+        let channel = Arc::new(PubSubChannel::<LoomRawMutex, u8, 1, 1, 1>::new());
+        let mut sub = channel.subscriber().unwrap();
+
+        let publisher = {
+            let channel = channel.clone();
+            thread::spawn(move || {
+                let publisher = channel.publisher().unwrap();
+                block_on(async {
+                    publisher.publish(1).await;
+                    publisher.publish(2).await;
+                });
+            })
+        };
+
+        block_on(async {
+            assert_eq!(sub.next_message().await, WaitResult::Message(1));
+            assert_eq!(sub.next_message().await, WaitResult::Message(2));
+        });
+        publisher.join().unwrap();
+        assert!(channel.is_empty());
+    });
+}
+
+#[test]
+fn pubsub_subscriber_drop_wakes_publisher() {
+    loom::model(|| {
+        let channel = Arc::new(PubSubChannel::<LoomRawMutex, u8, 1, 1, 1>::new());
+        let sub = channel.subscriber().unwrap();
+
+        let publisher = {
+            let channel = channel.clone();
+            thread::spawn(move || {
+                let publisher = channel.publisher().unwrap();
+                block_on(async {
+                    publisher.publish(1).await;
+                    // Only completes once the unread message is released by the dropped subscriber.
+                    publisher.publish(2).await;
+                });
+            })
+        };
+
+        drop(sub);
+        publisher.join().unwrap();
+        assert_eq!(channel.len(), 0);
+        assert_eq!(channel.free_capacity(), 1);
+    });
+}
+
+#[test]
+fn fair_semaphore_contended_acquire() {
+    loom::model(|| {
+        let semaphore = Arc::new(FairSemaphore::<LoomRawMutex, 2>::new(1));
+
+        let other = {
+            let semaphore = semaphore.clone();
+            thread::spawn(move || {
+                let _permit = block_on(semaphore.acquire(1)).unwrap();
+            })
+        };
+
+        {
+            let _permit = block_on(semaphore.acquire(1)).unwrap();
+        }
+        other.join().unwrap();
+
+        // Every acquired permit must have been returned.
+        assert!(semaphore.try_acquire(1).is_some());
+        assert!(semaphore.try_acquire(2).is_none());
+    });
+}
+
+#[test]
+fn fair_semaphore_cancelled_acquire_wakes_next() {
+    loom::model(|| {
+        let semaphore = Arc::new(FairSemaphore::<LoomRawMutex, 2>::new(0));
+
+        let canceller = {
+            let semaphore = semaphore.clone();
+            thread::spawn(move || {
+                // Poll once, possibly taking the head of the wait queue, then drop the future.
+                let _ = semaphore.acquire(1).now_or_never();
+            })
+        };
+        let releaser = {
+            let semaphore = semaphore.clone();
+            thread::spawn(move || semaphore.release(1))
+        };
+
+        // If the cancelled waiter was at the head of the queue, dropping it must hand the wakeup over.
+        let permit = block_on(semaphore.acquire(1));
+        canceller.join().unwrap();
+        releaser.join().unwrap();
+        drop(permit);
+
+        assert!(semaphore.try_acquire(1).is_some());
+    });
+}
+
+#[test]
+fn rwlock_reader_and_writer() {
+    loom::model(|| {
+        let lock = Arc::new(RwLock::<LoomRawMutex, (u8, u8)>::new((0, 0)));
+
+        let writer = {
+            let lock = lock.clone();
+            thread::spawn(move || {
+                block_on(async {
+                    let mut guard = lock.write().await;
+                    guard.0 += 1;
+                    guard.1 += 1;
+                })
+            })
+        };
+
+        block_on(async {
+            let guard = lock.read().await;
+            // A reader must never observe a half-applied write.
+            assert_eq!(guard.0, guard.1);
+        });
+        writer.join().unwrap();
+
+        // Both guards must have been released.
+        assert_eq!(*lock.try_write().unwrap(), (1, 1));
+    });
+}
+
+#[test]
+fn rwlock_concurrent_readers() {
+    loom::model(|| {
+        let lock = Arc::new(RwLock::<LoomRawMutex, u8>::new(0));
+
+        let reader = {
+            let lock = lock.clone();
+            thread::spawn(move || {
+                let guard = block_on(lock.read());
+                assert_eq!(*guard, 0);
+            })
+        };
+
+        let guard = block_on(lock.read());
+        assert_eq!(*guard, 0);
+        drop(guard);
+        reader.join().unwrap();
+
+        assert!(lock.try_write().is_ok());
+    });
+}
+
+#[test]
+fn rwlock_upgrade_excludes_writers() {
+    loom::model(|| {
+        let lock = Arc::new(RwLock::<LoomRawMutex, u8>::with_policy(0, Policy::WriterPreferred));
+
+        let writer = {
+            let lock = lock.clone();
+            thread::spawn(move || {
+                block_on(async {
+                    let mut guard = lock.write().await;
+                    *guard += 1;
+                })
+            })
+        };
+
+        block_on(async {
+            let guard = lock.upgradable_read().await;
+            let value = *guard;
+            let mut guard = RwLockUpgradableReadGuard::upgrade(guard).await;
+            // No writer may sneak in between the upgradable read and the upgrade.
+            assert_eq!(*guard, value);
+            *guard += 1;
+        });
+        writer.join().unwrap();
+
+        assert_eq!(*lock.try_read().unwrap(), 2);
+    });
+}
@@ -0,0 +1,37 @@
+//! Secondary, high-resolution time driver.
+//!
+//! The global time driver often has to run from a low-power clock, such as a 32.768 kHz RTC that
+//! keeps running in deep sleep, which is too coarse for short precise delays. A program can
+//! register a second driver, backed by a fast timer, with [`high_res_time_driver_impl`](crate::high_res_time_driver_impl).
+//! Unlike the global driver, its tick rate is set by the driver at runtime, and not by Cargo
+//! features.
+//!
+//! The high-resolution driver must not use the integrated timer queue of `embassy-time-queue-utils`,
+//! since each task only has a single integrated queue item, which is used by the global driver.
+//! It must keep its own list of wakers instead.
+
+use core::task::Waker;
+
+unsafe extern "Rust" {
+    fn _embassy_time_high_res_tick_hz() -> u64;
+    fn _embassy_time_high_res_now() -> u64;
+    fn _embassy_time_high_res_schedule_wake(at: u64, waker: &Waker);
+}
+
+/// Ticks per second of the high-resolution time base.
+#[inline]
+pub fn tick_hz() -> u64 {
+    unsafe { _embassy_time_high_res_tick_hz() }
+}
+
+/// See [`Driver::now`](crate::Driver::now)
+#[inline]
+pub fn now() -> u64 {
+    unsafe { _embassy_time_high_res_now() }
+}
+
+/// Schedule the given waker to be woken at `at`, in high-resolution ticks.
+#[inline]
+pub fn schedule_wake(at: u64, waker: &Waker) {
+    unsafe { _embassy_time_high_res_schedule_wake(at, waker) }
+}
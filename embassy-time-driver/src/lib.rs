@@ -84,6 +84,33 @@
 //! }
 //! ```
 //!
+//! ## Implementing a high-resolution driver
+//!
+//! A second driver can run alongside the global one, to provide a faster time base for short
+//! precise delays, while the global driver uses a low-power clock. It implements the same
+//! [`Driver`] trait, is registered with [`high_res_time_driver_impl`] along with its tick rate,
+//! and is used through `embassy-time`'s `high-res` feature. See the [`high_res`] module.
+//!
+//! ```
+//! use core::task::Waker;
+//!
+//! use embassy_time_driver::Driver;
+//!
+//! struct MyFastTimer{} // not public!
+//!
+//! impl Driver for MyFastTimer {
+//!     fn now(&self) -> u64 {
+//!         todo!()
+//!     }
+//!
+//!     fn schedule_wake(&self, at: u64, waker: &Waker) {
+//!         todo!()
+//!     }
+//! }
+//!
+//! embassy_time_driver::high_res_time_driver_impl!(static FAST_TIMER: MyFastTimer = MyFastTimer{}, tick_hz = 16_000_000);
+//! ```
+//!
 //! # Linkage details
 //!
 //! Instead of the usual "trait + generic params" approach, calls from embassy to the driver are done via `extern` functions.
@@ -107,6 +134,7 @@
 
 use core::task::Waker;
 
+pub mod high_res;
 mod tick;
 
 /// Ticks per second of the global timebase.
@@ -171,3 +199,31 @@ macro_rules! time_driver_impl {
         }
     };
 }
+
+/// Set the high-resolution time Driver implementation, and its tick rate.
+///
+/// See the module documentation for an example.
+#[macro_export]
+macro_rules! high_res_time_driver_impl {
+    (static $name:ident: $t: ty = $val:expr, tick_hz = $tick_hz:expr) => {
+        static $name: $t = $val;
+
+        #[unsafe(no_mangle)]
+        #[inline]
+        fn _embassy_time_high_res_tick_hz() -> u64 {
+            $tick_hz
+        }
+
+        #[unsafe(no_mangle)]
+        #[inline]
+        fn _embassy_time_high_res_now() -> u64 {
+            <$t as $crate::Driver>::now(&$name)
+        }
+
+        #[unsafe(no_mangle)]
+        #[inline]
+        fn _embassy_time_high_res_schedule_wake(at: u64, waker: &core::task::Waker) {
+            <$t as $crate::Driver>::schedule_wake(&$name, at, waker);
+        }
+    };
+}
@@ -0,0 +1,64 @@
+//! Compares the timer queues on a steady load of timers expiring and being rescheduled.
+//!
+//! The queue being benchmarked is selected with the crate features:
+//!
+//! ```text
+//! cargo bench --features generic-queue-128
+//! cargo bench
+//! cargo bench --features pairing-heap-queue
+//! ```
+
+use std::hint::black_box;
+use std::task::Waker;
+use std::time::Instant;
+
+use embassy_time_queue_utils::Queue;
+
+#[path = "../tests/common/mod.rs"]
+mod common;
+use common::{rng, take_woken, tasks};
+
+/// Returns the average time in nanoseconds to dequeue an expired timer and schedule it again,
+/// with `timers` timers pending.
+fn bench(timers: usize, rounds: usize) -> f64 {
+    let wakers: Vec<Waker> = tasks(timers).into_iter().map(|(_, waker)| waker).collect();
+    let mut queue = Queue::new();
+    let mut state = 0x2545_f491_4f6c_dd1d;
+    let mut now = 0;
+
+    for waker in &wakers {
+        queue.schedule_wake(rng(&mut state) % 1_000_000, waker);
+    }
+
+    let start = Instant::now();
+    for _ in 0..rounds {
+        now = black_box(queue.next_expiration(now));
+        let woken = take_woken();
+        for index in woken {
+            queue.schedule_wake(now + 1 + rng(&mut state) % 1_000_000, &wakers[index]);
+        }
+    }
+    start.elapsed().as_nanos() as f64 / rounds as f64
+}
+
+fn main() {
+    let max_timers = if cfg!(any(
+        feature = "generic-queue-8",
+        feature = "generic-queue-16",
+        feature = "generic-queue-32",
+        feature = "generic-queue-64",
+        feature = "generic-queue-128"
+    )) {
+        // Don't overflow the generic queue, it would wake timers early.
+        128
+    } else {
+        4096
+    };
+
+    for timers in [8, 32, 128, 512, 4096] {
+        if timers > max_timers {
+            break;
+        }
+        println!("{timers:>5} timers: {:>9.1} ns per timer", bench(timers, 200_000));
+    }
+}
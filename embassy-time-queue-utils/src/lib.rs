@@ -4,10 +4,14 @@
 
 #[cfg(feature = "_generic-queue")]
 pub mod queue_generic;
+#[cfg(all(feature = "pairing-heap-queue", not(feature = "_generic-queue")))]
+pub mod queue_heap;
 #[cfg(not(feature = "_generic-queue"))]
 pub mod queue_integrated;
 
 #[cfg(feature = "_generic-queue")]
 pub use queue_generic::Queue;
-#[cfg(not(feature = "_generic-queue"))]
+#[cfg(all(feature = "pairing-heap-queue", not(feature = "_generic-queue")))]
+pub use queue_heap::Queue;
+#[cfg(not(any(feature = "pairing-heap-queue", feature = "_generic-queue")))]
 pub use queue_integrated::Queue;
@@ -0,0 +1,212 @@
+//! Timer queue operations, using a pairing heap.
+//!
+//! Like [`queue_integrated`](crate::queue_integrated), the items of this queue are integrated into
+//! tasks, but they are kept in a pairing heap instead of an unsorted list. Scheduling a timer is
+//! O(1) and dequeuing an expired one is amortized O(log n), instead of walking every queued timer
+//! each time the queue is updated. This makes a difference once there are many pending timers, at
+//! the cost of two more words per task for the bigger queue item.
+use core::cell::Cell;
+use core::ptr::NonNull;
+use core::task::Waker;
+
+use embassy_executor_timer_queue::TimerQueueItem;
+
+type Link = Cell<Option<NonNull<QueueItem>>>;
+
+/// An item in the timer queue.
+#[derive(Default)]
+struct QueueItem {
+    /// The first child of this item, expiring after it.
+    child: Link,
+
+    /// The next sibling of this item.
+    sibling: Link,
+
+    /// The parent of this item if it is its first child, its previous sibling otherwise.
+    ///
+    /// Only the root of the heap has a value of `None`.
+    prev: Link,
+
+    /// The time at which this item expires.
+    expires_at: Cell<u64>,
+
+    /// The registered waker. If Some, the item is enqueued in the timer queue.
+    waker: Cell<Option<Waker>>,
+}
+
+unsafe impl Sync for QueueItem {}
+
+impl QueueItem {
+    fn unlink(&self) {
+        self.sibling.set(None);
+        self.prev.set(None);
+    }
+}
+
+/// A timer queue, with items integrated into tasks and kept in a pairing heap.
+///
+/// # Safety
+///
+/// **This Queue is only safe when there is a single integrated queue in the system.**
+///
+/// If there are multiple integrated queues, additional checks are necessary to ensure that a Waker
+/// is not attempted to be enqueued in multiple queues.
+pub struct Queue {
+    root: Link,
+}
+
+impl core::fmt::Debug for Queue {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.debug_struct("Queue").finish()
+    }
+}
+
+unsafe impl Send for Queue {}
+unsafe impl Sync for Queue {}
+
+impl Default for Queue {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Queue {
+    /// Creates a new timer queue.
+    pub const fn new() -> Self {
+        Self { root: Cell::new(None) }
+    }
+
+    /// Schedules a task to run at a specific time.
+    ///
+    /// If this function returns `true`, the called should find the next expiration time and set
+    /// a new alarm for that time.
+    pub fn schedule_wake(&mut self, at: u64, waker: &Waker) -> bool {
+        let item = unsafe {
+            // Safety: the `&mut self`, along with the Safety note of the Queue, are sufficient to
+            // ensure that this function creates the only reference to the queue item.
+            TimerQueueItem::from_embassy_waker(waker)
+        };
+        let item = unsafe { item.as_ref::<QueueItem>() };
+        let item_ptr = NonNull::from(item);
+
+        // The waker is always stored in its own queue item, so we only need to set it when the
+        // item is not in the queue yet.
+        let stored = item.waker.take();
+        let enqueued = stored.is_some();
+        item.waker.set(Some(stored.unwrap_or_else(|| waker.clone())));
+
+        if enqueued {
+            if at > item.expires_at.get() {
+                // Queue item does not need to be updated, the task will be scheduled to be woken
+                // before the new expiration.
+                return false;
+            }
+            item.expires_at.set(at);
+            // Move the item up to the root list, since it may now expire before its parent.
+            if self.root.get() != Some(item_ptr) {
+                self.detach(item);
+                self.root.set(Some(self.meld(self.root.get(), item_ptr)));
+            }
+        } else {
+            item.expires_at.set(at);
+            item.child.set(None);
+            item.unlink();
+            self.root.set(Some(self.meld(self.root.get(), item_ptr)));
+        }
+
+        true
+    }
+
+    /// Dequeues expired timers and returns the next alarm time.
+    ///
+    /// Tasks that never expire will be removed, but they will not be woken.
+    pub fn next_expiration(&mut self, now: u64) -> u64 {
+        while let Some(root) = self.root.get() {
+            let root = unsafe { root.as_ref() };
+            let expires_at = root.expires_at.get();
+            if expires_at > now && expires_at != u64::MAX {
+                return expires_at;
+            }
+
+            self.root.set(self.merge_pairs(root.child.take()));
+            let waker = root.waker.take();
+            if expires_at <= now {
+                // Timer expired, process task.
+                if let Some(waker) = waker {
+                    waker.wake();
+                }
+            }
+        }
+
+        u64::MAX
+    }
+
+    /// Removes a non-root item, along with its children, from the heap.
+    fn detach(&self, item: &QueueItem) {
+        let prev = unsafe { item.prev.get().unwrap().as_ref() };
+        let sibling = item.sibling.get();
+        if prev.child.get() == Some(NonNull::from(item)) {
+            prev.child.set(sibling);
+        } else {
+            prev.sibling.set(sibling);
+        }
+        if let Some(sibling) = sibling {
+            unsafe { sibling.as_ref() }.prev.set(item.prev.get());
+        }
+        item.unlink();
+    }
+
+    /// Melds two heaps, returning the root of the result.
+    fn meld(&self, a: Option<NonNull<QueueItem>>, b: NonNull<QueueItem>) -> NonNull<QueueItem> {
+        let Some(a) = a else {
+            return b;
+        };
+        let (parent, child) = unsafe {
+            if b.as_ref().expires_at.get() < a.as_ref().expires_at.get() {
+                (b, a)
+            } else {
+                (a, b)
+            }
+        };
+        let (parent_ref, child_ref) = unsafe { (parent.as_ref(), child.as_ref()) };
+
+        let first = parent_ref.child.replace(Some(child));
+        child_ref.sibling.set(first);
+        child_ref.prev.set(Some(parent));
+        if let Some(first) = first {
+            unsafe { first.as_ref() }.prev.set(Some(child));
+        }
+        parent
+    }
+
+    /// Melds a list of siblings into a single heap, in two passes, returning its root.
+    fn merge_pairs(&self, first: Option<NonNull<QueueItem>>) -> Option<NonNull<QueueItem>> {
+        // Meld siblings pairwise from left to right, stacking the results through `sibling`.
+        let mut stack = None;
+        let mut next = first;
+        while let Some(a) = next {
+            let a_ref = unsafe { a.as_ref() };
+            let b = a_ref.sibling.get();
+            next = b.and_then(|b| unsafe { b.as_ref() }.sibling.get());
+            a_ref.unlink();
+            let melded = match b {
+                Some(b) => {
+                    unsafe { b.as_ref() }.unlink();
+                    self.meld(Some(a), b)
+                }
+                None => a,
+            };
+            unsafe { melded.as_ref() }.sibling.set(stack);
+            stack = Some(melded);
+        }
+
+        // Meld the results from right to left.
+        let mut root = None;
+        while let Some(item) = stack {
+            let item_ref = unsafe { item.as_ref() };
+            stack = item_ref.sibling.take();
+            root = Some(self.meld(root, item));
+        }
+        root
+    }
+}
@@ -0,0 +1,63 @@
+//! Tasks and wakers standing in for an executor, shared by the queue tests and benchmarks.
+
+// Not every test or benchmark uses all of it.
+#![allow(dead_code)]
+
+use std::cell::RefCell;
+use std::sync::atomic::{AtomicU32, Ordering};
+use std::task::{RawWaker, RawWakerVTable, Waker};
+
+use embassy_executor_timer_queue::TimerQueueItem;
+
+/// Stands in for an executor task, holding its timer queue item.
+pub struct Task {
+    item: TimerQueueItem,
+    pub index: usize,
+    pub woken: AtomicU32,
+}
+
+thread_local! {
+    static WOKEN: RefCell<Vec<usize>> = const { RefCell::new(Vec::new()) };
+}
+
+fn wake(data: *const ()) {
+    let task = unsafe { &*(data as *const Task) };
+    task.woken.fetch_add(1, Ordering::Relaxed);
+    WOKEN.with_borrow_mut(|woken| woken.push(task.index));
+}
+
+static VTABLE: RawWakerVTable = RawWakerVTable::new(|data| RawWaker::new(data, &VTABLE), wake, wake, |_| {});
+
+#[unsafe(no_mangle)]
+fn __embassy_time_queue_item_from_waker(waker: &Waker) -> &'static mut TimerQueueItem {
+    let task = waker.data() as *mut Task;
+    unsafe { &mut (*task).item }
+}
+
+/// Creates `n` tasks, along with a waker for each.
+pub fn tasks(n: usize) -> Vec<(&'static Task, Waker)> {
+    (0..n)
+        .map(|index| {
+            let task: &'static Task = Box::leak(Box::new(Task {
+                item: TimerQueueItem::new(),
+                index,
+                woken: AtomicU32::new(0),
+            }));
+            let waker = unsafe { Waker::new(task as *const Task as *const (), &VTABLE) };
+            (task, waker)
+        })
+        .collect()
+}
+
+/// Returns the indices of the tasks woken on this thread since the last call.
+pub fn take_woken() -> Vec<usize> {
+    WOKEN.with_borrow_mut(core::mem::take)
+}
+
+/// xorshift64, good enough to spread timers around.
+pub fn rng(state: &mut u64) -> u64 {
+    *state ^= *state << 13;
+    *state ^= *state >> 7;
+    *state ^= *state << 17;
+    *state
+}
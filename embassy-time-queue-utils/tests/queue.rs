@@ -0,0 +1,64 @@
+#![cfg(not(feature = "_generic-queue"))]
+
+use std::collections::HashMap;
+use std::sync::atomic::Ordering;
+
+use embassy_time_queue_utils::Queue;
+
+mod common;
+use common::{rng, tasks};
+
+#[test]
+fn test_expires_in_order() {
+    let tasks = tasks(4);
+    let mut queue = Queue::new();
+
+    assert!(queue.schedule_wake(30, &tasks[0].1));
+    assert!(queue.schedule_wake(10, &tasks[1].1));
+    assert!(queue.schedule_wake(20, &tasks[2].1));
+    assert!(queue.schedule_wake(u64::MAX, &tasks[3].1));
+    // A later expiration than the one already scheduled doesn't change anything.
+    assert!(!queue.schedule_wake(40, &tasks[0].1));
+
+    assert_eq!(queue.next_expiration(0), 10);
+    assert_eq!(queue.next_expiration(15), 20);
+    assert_eq!(tasks[1].0.woken.load(Ordering::Relaxed), 1);
+    assert_eq!(queue.next_expiration(30), u64::MAX);
+    assert_eq!(tasks[0].0.woken.load(Ordering::Relaxed), 1);
+    assert_eq!(tasks[2].0.woken.load(Ordering::Relaxed), 1);
+    // Tasks that never expire are dropped without being woken.
+    assert_eq!(tasks[3].0.woken.load(Ordering::Relaxed), 0);
+}
+
+#[test]
+fn test_matches_model() {
+    let tasks = tasks(200);
+    let mut queue = Queue::new();
+    let mut model: HashMap<usize, u64> = HashMap::new();
+    let mut woken = vec![0; tasks.len()];
+    let mut state = 0x2545_f491_4f6c_dd1d;
+    let mut now = 0;
+
+    for _ in 0..20_000 {
+        if !rng(&mut state).is_multiple_of(4) {
+            let i = rng(&mut state) as usize % tasks.len();
+            let at = now + rng(&mut state) % 1000;
+            let at = model.get(&i).map_or(at, |&prev| prev.min(at));
+            model.insert(i, at);
+            queue.schedule_wake(at, &tasks[i].1);
+        } else {
+            now += rng(&mut state) % 100;
+            model.retain(|&i, &mut at| {
+                if at <= now {
+                    woken[i] += 1;
+                }
+                at > now
+            });
+            let next = model.values().copied().min().unwrap_or(u64::MAX);
+            assert_eq!(queue.next_expiration(now), next);
+            for (i, (task, _)) in tasks.iter().enumerate() {
+                assert_eq!(task.woken.load(Ordering::Relaxed), woken[i], "task {i}");
+            }
+        }
+    }
+}
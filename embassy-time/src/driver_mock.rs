@@ -63,6 +63,19 @@ impl MockDriver {
             inner.queue.next_expiration(inner.now.as_ticks());
         })
     }
+
+    /// Returns the time of the earliest scheduled wake, if any.
+    ///
+    /// Wakes that are already due are performed first, so the returned time is always in the future.
+    pub fn next_alarm(&self) -> Option<Instant> {
+        critical_section::with(|cs| {
+            let inner = &mut *self.0.borrow_ref_mut(cs);
+            match inner.queue.next_expiration(inner.now.as_ticks()) {
+                u64::MAX => None,
+                at => Some(Instant::from_ticks(at)),
+            }
+        })
+    }
 }
 
 impl Driver for MockDriver {
@@ -144,4 +157,25 @@ mod tests {
         driver.advance(Duration::from_secs(1));
         assert_eq!(true, CALLBACK_CALLED.load(Ordering::Relaxed));
     }
+
+    #[test]
+    #[serial]
+    fn test_next_alarm() {
+        setup();
+
+        struct MockWaker;
+
+        impl Wake for MockWaker {
+            fn wake(self: Arc<Self>) {}
+        }
+        let waker = Arc::new(MockWaker).into();
+
+        let driver = MockDriver::get();
+        assert_eq!(driver.next_alarm(), None);
+
+        driver.schedule_wake(driver.now() + 10, &waker);
+        assert_eq!(driver.next_alarm(), Some(Instant::from_ticks(10)));
+        driver.advance(Duration::from_ticks(10));
+        assert_eq!(driver.next_alarm(), None);
+    }
 }
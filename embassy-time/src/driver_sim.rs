@@ -0,0 +1,370 @@
+use core::future::Future;
+use core::pin::pin;
+use core::task::{Context, Poll, Waker};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex, MutexGuard};
+use std::task::Wake;
+use std::vec::Vec;
+
+use embassy_time_driver::Driver;
+
+use crate::{Duration, Instant};
+
+/// A simulated time driver for `std`, where time jumps straight to the next scheduled wake
+/// whenever the code under test is idle.
+///
+/// Unlike the [`MockDriver`](crate::MockDriver), tests don't need to advance the time
+/// themselves: call [`advance_to_next_wake()`](Self::advance_to_next_wake) whenever the executor
+/// has no task ready to run, or run the test with [`block_on()`](Self::block_on), which does so.
+/// Timeouts and delays of any length then complete instantly, in a deterministic order.
+///
+/// Every wake is recorded in a [`timeline()`](Self::timeline). Wakes due at the same instant
+/// happen in the order they were scheduled, unless a seed is set with
+/// [`set_seed()`](Self::set_seed), which shuffles them to shake out races between tasks.
+///
+/// # Example
+///
+/// ```ignore
+/// use embassy_time::{Duration, Instant, SimDriver, Timer};
+///
+/// fn test_sleep() {
+///     let driver = SimDriver::get();
+///     driver.reset();
+///     driver.block_on(async {
+///         Timer::after(Duration::from_secs(3600)).await;
+///     });
+///     assert_eq!(Instant::now(), Instant::from_secs(3600));
+///     assert_eq!(driver.timeline().len(), 1);
+/// }
+/// ```
+#[derive(Debug)]
+pub struct SimDriver(Mutex<InnerSimDriver>);
+
+embassy_time_driver::time_driver_impl!(static DRIVER: SimDriver = SimDriver::new());
+
+/// A wake performed by the [`SimDriver`], as recorded in its [`timeline()`](SimDriver::timeline).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SimWake {
+    /// Time at which the wake was scheduled.
+    pub scheduled_at: Instant,
+    /// Time the wake was scheduled for.
+    pub deadline: Instant,
+    /// Time at which the waker was woken.
+    pub woken_at: Instant,
+}
+
+impl SimDriver {
+    /// Creates a new simulated time driver.
+    pub const fn new() -> Self {
+        Self(Mutex::new(InnerSimDriver::new()))
+    }
+
+    /// Gets a reference to the global simulated time driver.
+    pub fn get() -> &'static SimDriver {
+        &DRIVER
+    }
+
+    fn lock(&self) -> MutexGuard<'_, InnerSimDriver> {
+        // Tests reset the driver before using it, so a test that panicked holding the lock is harmless.
+        self.0.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    /// Resets the driver, dropping all scheduled wakes, the timeline and the seed, and resetting
+    /// the current time to 0.
+    pub fn reset(&self) {
+        *self.lock() = InnerSimDriver::new();
+    }
+
+    /// Sets the seed used to shuffle the wakes due at the same instant, or `None` to perform them
+    /// in the order they were scheduled.
+    pub fn set_seed(&self, seed: Option<u64>) {
+        self.lock().rng = seed;
+    }
+
+    /// Advances the time by the specified [`Duration`], waking the wakers that are due.
+    pub fn advance(&self, duration: Duration) {
+        let wakers = {
+            let mut inner = self.lock();
+            inner.now += duration;
+            inner.take_due()
+        };
+        wakers.into_iter().for_each(Waker::wake);
+    }
+
+    /// Advances the time to the earliest scheduled wake, if any, and wakes the wakers that are due.
+    ///
+    /// Returns whether there was a scheduled wake, so that this can be called whenever the
+    /// executor is idle, and used to detect that it would otherwise sleep forever.
+    pub fn advance_to_next_wake(&self) -> bool {
+        let wakers = {
+            let mut inner = self.lock();
+            let Some(next) = inner.alarms.iter().map(|alarm| alarm.at).min() else {
+                return false;
+            };
+            inner.now = inner.now.max(Instant::from_ticks(next));
+            inner.take_due()
+        };
+        wakers.into_iter().for_each(Waker::wake);
+        true
+    }
+
+    /// Returns the time of the earliest scheduled wake, if any.
+    pub fn next_alarm(&self) -> Option<Instant> {
+        self.lock()
+            .alarms
+            .iter()
+            .map(|alarm| Instant::from_ticks(alarm.at))
+            .min()
+    }
+
+    /// Returns the wakes performed since the last reset, in order.
+    pub fn timeline(&self) -> Vec<SimWake> {
+        self.lock().timeline.clone()
+    }
+
+    /// Runs a future to completion, advancing the time to the next scheduled wake whenever it's
+    /// pending and hasn't been woken.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the future is pending, hasn't been woken and no wake is scheduled, as it would
+    /// never complete in simulated time.
+    pub fn block_on<F: Future>(&self, fut: F) -> F::Output {
+        struct Flag(AtomicBool);
+
+        impl Wake for Flag {
+            fn wake(self: Arc<Self>) {
+                self.0.store(true, Ordering::Release);
+            }
+        }
+
+        let flag = Arc::new(Flag(AtomicBool::new(true)));
+        let waker = Waker::from(flag.clone());
+        let mut cx = Context::from_waker(&waker);
+        let mut fut = pin!(fut);
+        loop {
+            if flag.0.swap(false, Ordering::Acquire) {
+                if let Poll::Ready(output) = fut.as_mut().poll(&mut cx) {
+                    return output;
+                }
+            } else if !self.advance_to_next_wake() {
+                panic!("future is pending with no wake scheduled, it would never complete");
+            }
+        }
+    }
+}
+
+impl Driver for SimDriver {
+    fn now(&self) -> u64 {
+        self.lock().now.as_ticks()
+    }
+
+    fn schedule_wake(&self, at: u64, waker: &Waker) {
+        let wakers = {
+            let mut inner = self.lock();
+            let scheduled_at = inner.now.as_ticks();
+            match inner.alarms.iter_mut().find(|alarm| alarm.waker.will_wake(waker)) {
+                Some(alarm) if at < alarm.at => {
+                    alarm.at = at;
+                    alarm.scheduled_at = scheduled_at;
+                }
+                Some(_) => {}
+                None => inner.alarms.push(Alarm {
+                    at,
+                    scheduled_at,
+                    waker: waker.clone(),
+                }),
+            }
+            // wake it if it's in the past.
+            inner.take_due()
+        };
+        wakers.into_iter().for_each(Waker::wake);
+    }
+}
+
+#[derive(Debug)]
+struct Alarm {
+    at: u64,
+    scheduled_at: u64,
+    waker: Waker,
+}
+
+#[derive(Debug)]
+struct InnerSimDriver {
+    now: Instant,
+    /// Scheduled wakes, in the order they were scheduled.
+    alarms: Vec<Alarm>,
+    timeline: Vec<SimWake>,
+    /// State of the generator shuffling simultaneous wakes, if seeded.
+    rng: Option<u64>,
+}
+
+impl InnerSimDriver {
+    const fn new() -> Self {
+        Self {
+            now: Instant::from_ticks(0),
+            alarms: Vec::new(),
+            timeline: Vec::new(),
+            rng: None,
+        }
+    }
+
+    /// Removes the alarms that are due, recording them in the timeline, and returns their wakers
+    /// in the order they must be woken.
+    fn take_due(&mut self) -> Vec<Waker> {
+        let now = self.now.as_ticks();
+        let (mut due, pending): (Vec<_>, Vec<_>) = self.alarms.drain(..).partition(|alarm| alarm.at <= now);
+        self.alarms = pending;
+
+        // The sort is stable, so that simultaneous wakes keep the order they were scheduled in.
+        due.sort_by_key(|alarm| alarm.at);
+        if let Some(state) = &mut self.rng {
+            for group in due.chunk_by_mut(|a, b| a.at == b.at) {
+                // Fisher-Yates shuffle.
+                for i in (1..group.len()).rev() {
+                    let j = (splitmix64(state) % (i as u64 + 1)) as usize;
+                    group.swap(i, j);
+                }
+            }
+        }
+
+        due.into_iter()
+            .map(|alarm| {
+                self.timeline.push(SimWake {
+                    scheduled_at: Instant::from_ticks(alarm.scheduled_at),
+                    deadline: Instant::from_ticks(alarm.at),
+                    woken_at: self.now,
+                });
+                alarm.waker
+            })
+            .collect()
+    }
+}
+
+/// splitmix64, which works with any seed.
+fn splitmix64(state: &mut u64) -> u64 {
+    *state = state.wrapping_add(0x9e37_79b9_7f4a_7c15);
+    let mut z = *state;
+    z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+    z ^ (z >> 31)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Mutex;
+
+    use serial_test::serial;
+
+    use super::*;
+    use crate::Timer;
+
+    fn setup() -> &'static SimDriver {
+        DRIVER.reset();
+        &DRIVER
+    }
+
+    /// Records the id of the wakers in the order they are woken.
+    struct Recorder {
+        id: u32,
+        order: Arc<Mutex<Vec<u32>>>,
+    }
+
+    impl Wake for Recorder {
+        fn wake(self: Arc<Self>) {
+            self.order.lock().unwrap().push(self.id);
+        }
+    }
+
+    /// Schedules wakers named by `ids` at the same instant, returning the order they are woken in.
+    fn wake_order(driver: &SimDriver, ids: &[u32]) -> Vec<u32> {
+        let order = Arc::new(Mutex::new(Vec::new()));
+        for &id in ids {
+            let waker = Arc::new(Recorder {
+                id,
+                order: order.clone(),
+            })
+            .into();
+            driver.schedule_wake(10, &waker);
+        }
+        assert!(driver.advance_to_next_wake());
+        order.lock().unwrap().clone()
+    }
+
+    #[test]
+    #[serial]
+    fn test_jumps_to_next_wake() {
+        let driver = setup();
+
+        driver.block_on(async {
+            Timer::after(Duration::from_secs(3600)).await;
+            Timer::after(Duration::from_millis(5)).await;
+        });
+        assert_eq!(Instant::now(), Instant::from_millis(3_600_005));
+        assert_eq!(
+            driver.timeline(),
+            [
+                SimWake {
+                    scheduled_at: Instant::from_ticks(0),
+                    deadline: Instant::from_secs(3600),
+                    woken_at: Instant::from_secs(3600),
+                },
+                SimWake {
+                    scheduled_at: Instant::from_secs(3600),
+                    deadline: Instant::from_millis(3_600_005),
+                    woken_at: Instant::from_millis(3_600_005),
+                },
+            ]
+        );
+        assert!(!driver.advance_to_next_wake());
+    }
+
+    #[test]
+    #[serial]
+    fn test_advance() {
+        let driver = setup();
+        let waker = Arc::new(Recorder {
+            id: 0,
+            order: Arc::new(Mutex::new(Vec::new())),
+        })
+        .into();
+
+        driver.schedule_wake(30, &waker);
+        driver.schedule_wake(20, &waker);
+        assert_eq!(driver.next_alarm(), Some(Instant::from_ticks(20)));
+        driver.advance(Duration::from_ticks(25));
+        assert_eq!(driver.next_alarm(), None);
+        assert_eq!(driver.timeline().len(), 1);
+        assert_eq!(driver.timeline()[0].woken_at, Instant::from_ticks(25));
+    }
+
+    #[test]
+    #[serial]
+    fn test_seeded_wake_order() {
+        let driver = setup();
+        let ids: Vec<u32> = (0..8).collect();
+        assert_eq!(wake_order(driver, &ids), ids);
+
+        let mut orders = Vec::new();
+        for seed in 0..4 {
+            let driver = setup();
+            driver.set_seed(Some(seed));
+            let order = wake_order(driver, &ids);
+
+            // The same seed gives the same order.
+            let driver = setup();
+            driver.set_seed(Some(seed));
+            assert_eq!(wake_order(driver, &ids), order);
+            orders.push(order);
+        }
+        assert!(orders.iter().any(|order| *order != ids));
+    }
+
+    #[test]
+    #[serial]
+    #[should_panic(expected = "never complete")]
+    fn test_detects_deadlock() {
+        let driver = setup();
+        driver.block_on(core::future::pending::<()>());
+    }
+}
@@ -0,0 +1,363 @@
+use core::fmt;
+use core::future::Future;
+use core::ops::{Add, AddAssign, Sub, SubAssign};
+use core::pin::Pin;
+use core::task::{Context, Poll};
+
+use crate::{Instant, TICK_HZ};
+
+const NANOS_PER_SEC: u128 = 1_000_000_000;
+
+/// An instant in time of the high-resolution time base.
+///
+/// The high-resolution time base is provided by a second time driver, registered with
+/// `embassy_time_driver::high_res_time_driver_impl!`, typically backed by a fast timer. It is used
+/// for short precise delays, while the global time driver behind [`Instant`] can use a low-power
+/// clock for long sleeps.
+///
+/// Its tick rate is set by the driver at runtime, so durations in the high-resolution time base are
+/// [`core::time::Duration`]s, which have a resolution of a nanosecond, rather than [`Duration`](crate::Duration)s.
+/// Convert instants between the two time bases with [`to_instant()`](Self::to_instant) and
+/// [`from_instant()`](Self::from_instant).
+#[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct HighResInstant {
+    ticks: u64,
+}
+
+impl HighResInstant {
+    /// The smallest (earliest) value that can be represented by the `HighResInstant` type.
+    pub const MIN: HighResInstant = HighResInstant { ticks: u64::MIN };
+    /// The largest (latest) value that can be represented by the `HighResInstant` type.
+    pub const MAX: HighResInstant = HighResInstant { ticks: u64::MAX };
+
+    /// Returns the current high-resolution time.
+    #[inline]
+    pub fn now() -> HighResInstant {
+        HighResInstant {
+            ticks: embassy_time_driver::high_res::now(),
+        }
+    }
+
+    /// Ticks per second of the high-resolution time base.
+    #[inline]
+    pub fn tick_hz() -> u64 {
+        embassy_time_driver::high_res::tick_hz()
+    }
+
+    /// Create a high-resolution instant from a tick count.
+    pub const fn from_ticks(ticks: u64) -> Self {
+        Self { ticks }
+    }
+
+    /// Tick count of the high-resolution time base.
+    pub const fn as_ticks(&self) -> u64 {
+        self.ticks
+    }
+
+    /// Nanoseconds since the start of the high-resolution time base.
+    pub fn as_nanos(&self) -> u64 {
+        (self.ticks as u128 * NANOS_PER_SEC / Self::tick_hz() as u128) as u64
+    }
+
+    /// Microseconds since the start of the high-resolution time base.
+    pub fn as_micros(&self) -> u64 {
+        self.as_nanos() / 1_000
+    }
+
+    /// Duration between this instant and an earlier one.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `earlier` is later than this instant.
+    pub fn duration_since(&self, earlier: HighResInstant) -> core::time::Duration {
+        unwrap!(
+            self.checked_duration_since(earlier),
+            "earlier instant is later than self"
+        )
+    }
+
+    /// Duration between this instant and an earlier one, or `None` if `earlier` is later than
+    /// this instant.
+    pub fn checked_duration_since(&self, earlier: HighResInstant) -> Option<core::time::Duration> {
+        let ticks = self.ticks.checked_sub(earlier.ticks)?;
+        let nanos = ticks as u128 * NANOS_PER_SEC / Self::tick_hz() as u128;
+        Some(core::time::Duration::from_nanos(nanos as u64))
+    }
+
+    /// Returns the duration elapsed since this instant.
+    pub fn elapsed(&self) -> core::time::Duration {
+        Self::now().duration_since(*self)
+    }
+
+    /// Adds a duration, rounded up to a whole tick, returning `None` on overflow.
+    pub fn checked_add(&self, duration: core::time::Duration) -> Option<HighResInstant> {
+        self.ticks
+            .checked_add(duration_to_ticks(duration)?)
+            .map(Self::from_ticks)
+    }
+
+    /// Subtracts a duration, rounded up to a whole tick, returning `None` on overflow.
+    pub fn checked_sub(&self, duration: core::time::Duration) -> Option<HighResInstant> {
+        self.ticks
+            .checked_sub(duration_to_ticks(duration)?)
+            .map(Self::from_ticks)
+    }
+
+    /// Converts to an [`Instant`] of the global time base.
+    ///
+    /// The two time bases are compared by reading both clocks at once, so the result is only as
+    /// precise as the tick rate of the global time base.
+    pub fn to_instant(&self) -> Instant {
+        let (high_res, instant) = critical_section::with(|_| (Self::now(), Instant::now()));
+        let offset = self.ticks as i128 - high_res.ticks as i128;
+        let offset = offset * TICK_HZ as i128 / Self::tick_hz() as i128;
+        let ticks = (instant.as_ticks() as i128 + offset).clamp(0, u64::MAX as i128);
+        Instant::from_ticks(ticks as u64)
+    }
+
+    /// Converts an [`Instant`] of the global time base to the high-resolution time base.
+    ///
+    /// The two time bases are compared by reading both clocks at once, so the result is only as
+    /// precise as the tick rate of the global time base.
+    pub fn from_instant(instant: Instant) -> Self {
+        let (high_res, now) = critical_section::with(|_| (Self::now(), Instant::now()));
+        let offset = instant.as_ticks() as i128 - now.as_ticks() as i128;
+        let offset = offset * Self::tick_hz() as i128 / TICK_HZ as i128;
+        let ticks = (high_res.ticks as i128 + offset).clamp(0, u64::MAX as i128);
+        Self::from_ticks(ticks as u64)
+    }
+}
+
+/// Number of high-resolution ticks in `duration`, rounded up.
+fn duration_to_ticks(duration: core::time::Duration) -> Option<u64> {
+    let ticks = (duration.as_nanos() * HighResInstant::tick_hz() as u128).div_ceil(NANOS_PER_SEC);
+    u64::try_from(ticks).ok()
+}
+
+impl Add<core::time::Duration> for HighResInstant {
+    type Output = HighResInstant;
+
+    fn add(self, other: core::time::Duration) -> HighResInstant {
+        unwrap!(self.checked_add(other), "overflow when adding duration to instant")
+    }
+}
+
+impl AddAssign<core::time::Duration> for HighResInstant {
+    fn add_assign(&mut self, other: core::time::Duration) {
+        *self = *self + other;
+    }
+}
+
+impl Sub<core::time::Duration> for HighResInstant {
+    type Output = HighResInstant;
+
+    fn sub(self, other: core::time::Duration) -> HighResInstant {
+        unwrap!(
+            self.checked_sub(other),
+            "overflow when subtracting duration from instant"
+        )
+    }
+}
+
+impl SubAssign<core::time::Duration> for HighResInstant {
+    fn sub_assign(&mut self, other: core::time::Duration) {
+        *self = *self - other;
+    }
+}
+
+impl Sub<HighResInstant> for HighResInstant {
+    type Output = core::time::Duration;
+
+    fn sub(self, other: HighResInstant) -> core::time::Duration {
+        self.duration_since(other)
+    }
+}
+
+impl fmt::Display for HighResInstant {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} high-res ticks", self.ticks)
+    }
+}
+
+/// A future that completes at a specified [`HighResInstant`].
+///
+/// Use it instead of [`Timer`](crate::Timer) for delays shorter than, or not a multiple of, the
+/// tick period of the global time base.
+#[must_use = "futures do nothing unless you `.await` or poll them"]
+#[derive(Debug)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct HighResTimer {
+    expires_at: HighResInstant,
+    yielded_once: bool,
+}
+
+impl HighResTimer {
+    /// Expire at the specified [`HighResInstant`].
+    /// Will expire immediately if the instant is in the past.
+    pub fn at(expires_at: HighResInstant) -> Self {
+        Self {
+            expires_at,
+            yielded_once: false,
+        }
+    }
+
+    /// Expire after the specified duration, rounded up to a whole high-resolution tick.
+    pub fn after(duration: core::time::Duration) -> Self {
+        Self::at(HighResInstant::now() + duration)
+    }
+
+    /// Expire after the specified number of high-resolution ticks.
+    ///
+    /// A timer that would expire after the end of the time base never expires.
+    pub fn after_ticks(ticks: u64) -> Self {
+        Self::at(HighResInstant::from_ticks(
+            HighResInstant::now().ticks.saturating_add(ticks),
+        ))
+    }
+
+    /// Expire after the specified number of nanoseconds.
+    pub fn after_nanos(nanos: u64) -> Self {
+        Self::after(core::time::Duration::from_nanos(nanos))
+    }
+
+    /// Expire after the specified number of microseconds.
+    pub fn after_micros(micros: u64) -> Self {
+        Self::after(core::time::Duration::from_micros(micros))
+    }
+}
+
+impl Unpin for HighResTimer {}
+
+impl Future for HighResTimer {
+    type Output = ();
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        if self.yielded_once && self.expires_at <= HighResInstant::now() {
+            Poll::Ready(())
+        } else {
+            embassy_time_driver::high_res::schedule_wake(self.expires_at.as_ticks(), cx.waker());
+            self.yielded_once = true;
+            Poll::Pending
+        }
+    }
+}
+
+/// Type implementing async and blocking `embedded-hal` delays in the high-resolution time base.
+///
+/// Like [`Delay`](crate::Delay), delays last at least the amount provided, but it is precise to a
+/// tick of the high-resolution time base instead of the global one.
+#[derive(Clone, Debug)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct HighResDelay;
+
+/// Blocks for at least `duration`, in the high-resolution time base.
+pub fn block_for_high_res(duration: core::time::Duration) {
+    let expires_at = HighResInstant::now() + duration;
+    while HighResInstant::now() < expires_at {}
+}
+
+impl embedded_hal_1::delay::DelayNs for HighResDelay {
+    fn delay_ns(&mut self, ns: u32) {
+        block_for_high_res(core::time::Duration::from_nanos(ns as u64))
+    }
+
+    fn delay_us(&mut self, us: u32) {
+        block_for_high_res(core::time::Duration::from_micros(us as u64))
+    }
+
+    fn delay_ms(&mut self, ms: u32) {
+        block_for_high_res(core::time::Duration::from_millis(ms as u64))
+    }
+}
+
+impl embedded_hal_async::delay::DelayNs for HighResDelay {
+    fn delay_ns(&mut self, ns: u32) -> impl Future<Output = ()> {
+        HighResTimer::after_nanos(ns as _)
+    }
+
+    fn delay_us(&mut self, us: u32) -> impl Future<Output = ()> {
+        HighResTimer::after_micros(us as _)
+    }
+
+    fn delay_ms(&mut self, ms: u32) -> impl Future<Output = ()> {
+        HighResTimer::after(core::time::Duration::from_millis(ms as _))
+    }
+}
+
+#[cfg(all(test, feature = "mock-driver"))]
+mod tests {
+    use core::task::Waker;
+
+    use serial_test::serial;
+
+    use super::*;
+    use crate::{Duration, MockDriver};
+
+    /// A high-resolution driver ticking 1000 times faster than the mock driver, and waking
+    /// through its queue.
+    struct MockHighResDriver;
+
+    impl embassy_time_driver::Driver for MockHighResDriver {
+        fn now(&self) -> u64 {
+            embassy_time_driver::now() * 1000
+        }
+
+        fn schedule_wake(&self, at: u64, waker: &Waker) {
+            embassy_time_driver::schedule_wake(at.div_ceil(1000), waker)
+        }
+    }
+
+    embassy_time_driver::high_res_time_driver_impl!(
+        static HIGH_RES_DRIVER: MockHighResDriver = MockHighResDriver,
+        tick_hz = TICK_HZ * 1000
+    );
+
+    #[test]
+    #[serial]
+    fn test_high_res_instant() {
+        let driver = MockDriver::get();
+        driver.reset();
+        driver.advance(Duration::from_secs(1));
+
+        let now = HighResInstant::now();
+        assert_eq!(now.as_nanos(), 1_000_000_000);
+        let later = now + core::time::Duration::from_nanos(1500);
+        assert_eq!(later.as_ticks() - now.as_ticks(), 1500);
+        assert_eq!(later - now, core::time::Duration::from_nanos(1500));
+
+        assert_eq!(later.to_instant(), Instant::from_micros(1_000_001));
+        let instant = Instant::from_millis(1500);
+        assert_eq!(HighResInstant::from_instant(instant).to_instant(), instant);
+        assert_eq!(HighResInstant::from_instant(instant).as_nanos(), 1_500_000_000);
+    }
+
+    #[test]
+    #[serial]
+    fn test_high_res_timer() {
+        let driver = MockDriver::get();
+        driver.reset();
+        let mut cx = Context::from_waker(Waker::noop());
+
+        let mut timer = HighResTimer::after_nanos(2500);
+        assert_eq!(Pin::new(&mut timer).poll(&mut cx), Poll::Pending);
+        driver.advance(Duration::from_micros(2));
+        assert_eq!(Pin::new(&mut timer).poll(&mut cx), Poll::Pending);
+        driver.advance(Duration::from_micros(1));
+        assert_eq!(Pin::new(&mut timer).poll(&mut cx), Poll::Ready(()));
+    }
+
+    #[test]
+    #[serial]
+    fn test_high_res_timer_after_max_ticks() {
+        let driver = MockDriver::get();
+        driver.reset();
+        driver.advance(Duration::from_secs(1));
+        let mut cx = Context::from_waker(Waker::noop());
+
+        let mut timer = HighResTimer::after_ticks(u64::MAX);
+        assert_eq!(timer.expires_at.as_ticks(), u64::MAX);
+        assert_eq!(Pin::new(&mut timer).poll(&mut cx), Poll::Pending);
+        driver.advance(Duration::from_secs(1));
+        assert_eq!(Pin::new(&mut timer).poll(&mut cx), Poll::Pending);
+    }
+}
@@ -1,4 +1,4 @@
-#![cfg_attr(not(any(feature = "std", feature = "wasm", test)), no_std)]
+#![cfg_attr(not(any(feature = "std", feature = "wasm", feature = "sim-driver", test)), no_std)]
 #![allow(async_fn_in_trait)]
 #![allow(unsafe_op_in_unsafe_fn)]
 #![doc = include_str!("../README.md")]
@@ -14,8 +14,12 @@ pub(crate) mod fmt;
 
 mod delay;
 mod duration;
+#[cfg(feature = "high-res")]
+mod high_res;
 mod instant;
+mod schedule;
 mod timer;
+mod utc;
 
 #[cfg(feature = "mock-driver")]
 mod driver_mock;
@@ -23,6 +27,12 @@ mod driver_mock;
 #[cfg(feature = "mock-driver")]
 pub use driver_mock::MockDriver;
 
+#[cfg(feature = "sim-driver")]
+mod driver_sim;
+
+#[cfg(feature = "sim-driver")]
+pub use driver_sim::{SimDriver, SimWake};
+
 #[cfg(feature = "std")]
 mod driver_std;
 #[cfg(feature = "wasm")]
@@ -31,8 +41,15 @@ mod driver_wasm;
 pub use delay::{Delay, block_for};
 pub use duration::Duration;
 pub use embassy_time_driver::TICK_HZ;
+#[cfg(feature = "high-res")]
+pub use high_res::{HighResDelay, HighResInstant, HighResTimer, block_for_high_res};
 pub use instant::Instant;
-pub use timer::{Ticker, TimeoutError, Timer, WithTimeout, with_deadline, with_timeout};
+pub use schedule::{Schedule, ScheduleError, Scheduler, Window};
+pub use timer::{
+    Backoff, Jitter, MissedTickBehavior, Retry, RetryError, Ticker, TimeoutError, Timer, WithTimeout, with_deadline,
+    with_timeout,
+};
+pub use utc::{DateTime, DateTimeError, UtcTime, WallClock, Weekday, days_in_month, is_leap_year};
 
 const fn gcd(a: u64, b: u64) -> u64 {
     if b == 0 { a } else { gcd(b, a % b) }
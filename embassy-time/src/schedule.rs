@@ -0,0 +1,744 @@
+use core::fmt;
+use core::str::FromStr;
+
+use crate::{DateTime, Duration, Instant, Timer, UtcTime, WallClock, Weekday, days_in_month};
+
+/// Clock steps up to this size are treated like cron does: occurrences skipped by stepping the
+/// clock forward are still delivered, and the ones repeated by stepping it back are not.
+const MAX_CLOCK_STEP: Duration = Duration::from_secs(3 * 3600);
+/// How often a [`Scheduler`] checks the wall clock for adjustments by default.
+const DEFAULT_RECHECK_INTERVAL: Duration = Duration::from_secs(60);
+
+const MONTH_NAMES: [&str; 12] = [
+    "JAN", "FEB", "MAR", "APR", "MAY", "JUN", "JUL", "AUG", "SEP", "OCT", "NOV", "DEC",
+];
+const WEEKDAY_NAMES: [&str; 7] = ["SUN", "MON", "TUE", "WED", "THU", "FRI", "SAT"];
+
+const ALL_DAYS: u64 = 0xffff_fffe;
+const ALL_WEEKDAYS: u64 = 0x7f;
+
+/// A set of wall-clock times, with a resolution of a minute, like a cron expression.
+///
+/// A schedule matches the minutes whose month, day, hour and minute are all in the schedule.
+/// As with cron, when both the days of the month and the days of the week are restricted (their
+/// fields don't start with `*`), a day matches if it is in either of them.
+///
+/// Schedules can be parsed from the usual 5 fields of a cron expression, or built:
+///
+/// ```rust
+/// use embassy_time::{Schedule, Weekday};
+///
+/// // Every day at 02:00 UTC.
+/// let nightly: Schedule = "0 2 * * *".parse().unwrap();
+/// assert_eq!(nightly, Schedule::daily_at(2, 0));
+///
+/// // Every 15 minutes, aligned to the hour, on weekdays.
+/// let polling = Schedule::parse("*/15 * * * MON-FRI").unwrap();
+/// let weekdays = [
+///     Weekday::Monday,
+///     Weekday::Tuesday,
+///     Weekday::Wednesday,
+///     Weekday::Thursday,
+///     Weekday::Friday,
+/// ];
+/// assert_eq!(polling, Schedule::every_minutes(15).on_weekdays(&weekdays));
+/// ```
+///
+/// Wait for the next occurrence of a schedule with a [`Scheduler`].
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct Schedule {
+    /// Bit `n` set for minute `n`.
+    minutes: u64,
+    /// Bit `n` set for hour `n`.
+    hours: u64,
+    /// Bit `n` set for day of the month `n`, starting at 1.
+    days: u64,
+    /// Bit `n` set for month `n`, starting at 1.
+    months: u64,
+    /// Bit `n` set for `n` days since Monday.
+    weekdays: u64,
+    /// Whether the days of the month were restricted, rather than `*`.
+    days_restricted: bool,
+    /// Whether the days of the week were restricted, rather than `*`.
+    weekdays_restricted: bool,
+}
+
+/// Error returned when parsing an invalid [`Schedule`].
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum ScheduleError {
+    /// The expression doesn't have exactly 5 fields.
+    FieldCount,
+    /// A value is neither a number nor a month or day name.
+    InvalidValue,
+    /// A value is out of the range of its field.
+    OutOfRange,
+    /// A range ends before it starts.
+    InvalidRange,
+    /// A step is zero.
+    InvalidStep,
+}
+
+impl fmt::Display for ScheduleError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let msg = match self {
+            ScheduleError::FieldCount => "expected 5 fields",
+            ScheduleError::InvalidValue => "invalid value",
+            ScheduleError::OutOfRange => "value out of range",
+            ScheduleError::InvalidRange => "invalid range",
+            ScheduleError::InvalidStep => "invalid step",
+        };
+        f.write_str(msg)
+    }
+}
+
+impl core::error::Error for ScheduleError {}
+
+impl Schedule {
+    /// A schedule matching every minute, `* * * * *`.
+    pub const fn every_minute() -> Self {
+        Self {
+            minutes: (1 << 60) - 1,
+            hours: (1 << 24) - 1,
+            days: ALL_DAYS,
+            months: 0x1ffe,
+            weekdays: ALL_WEEKDAYS,
+            days_restricted: false,
+            weekdays_restricted: false,
+        }
+    }
+
+    /// A schedule matching every `step` minutes, aligned to the hour, `*/step * * * *`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `step` is zero.
+    pub fn every_minutes(step: u8) -> Self {
+        assert!(step > 0, "step must not be zero");
+        Self {
+            minutes: bits(0, 59, step),
+            ..Self::every_minute()
+        }
+    }
+
+    /// A schedule matching every day at `hour:minute`, `minute hour * * *`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `hour` or `minute` is out of range.
+    pub fn daily_at(hour: u8, minute: u8) -> Self {
+        Self::every_minute().at_hours(&[hour]).at_minutes(&[minute])
+    }
+
+    /// Parse a cron expression.
+    ///
+    /// The expression has 5 fields separated by whitespace: minute (0-59), hour (0-23), day of the
+    /// month (1-31), month (1-12 or `JAN`-`DEC`) and day of the week (0-7 or `SUN`-`SAT`, 0 and 7
+    /// both being Sunday). Each field is a comma-separated list of `*`, values, ranges `a-b`,
+    /// and steps `*/s`, `a-b/s` or `a/s`. The shorthands `@yearly`, `@annually`, `@monthly`,
+    /// `@weekly`, `@daily`, `@midnight` and `@hourly` are accepted too.
+    pub fn parse(expr: &str) -> Result<Self, ScheduleError> {
+        let expr = match expr.trim() {
+            "@yearly" | "@annually" => "0 0 1 1 *",
+            "@monthly" => "0 0 1 * *",
+            "@weekly" => "0 0 * * 0",
+            "@daily" | "@midnight" => "0 0 * * *",
+            "@hourly" => "0 * * * *",
+            expr => expr,
+        };
+        let mut fields = expr.split_whitespace();
+        let mut field = |min, max, names: &[&str]| {
+            let field = fields.next().ok_or(ScheduleError::FieldCount)?;
+            // Like cron, a field starting with `*` is unrestricted, even with a step.
+            Ok::<_, ScheduleError>((parse_field(field, min, max, names)?, !field.starts_with('*')))
+        };
+        let (minutes, _) = field(0, 59, &[])?;
+        let (hours, _) = field(0, 23, &[])?;
+        let (days, days_restricted) = field(1, 31, &[])?;
+        let (months, _) = field(1, 12, &MONTH_NAMES)?;
+        let (weekdays, weekdays_restricted) = field(0, 7, &WEEKDAY_NAMES)?;
+        if fields.next().is_some() {
+            return Err(ScheduleError::FieldCount);
+        }
+
+        // Cron counts weekdays from Sunday, as 0 or 7.
+        let weekdays = (weekdays >> 1) | (weekdays & 1) << 6;
+        Ok(Self {
+            minutes,
+            hours,
+            days,
+            months,
+            weekdays,
+            days_restricted,
+            weekdays_restricted,
+        })
+    }
+
+    /// Restrict the schedule to the given minutes, from 0 to 59.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `minutes` is empty or a minute is out of range.
+    pub fn at_minutes(self, minutes: &[u8]) -> Self {
+        Self {
+            minutes: mask(minutes, 0, 59),
+            ..self
+        }
+    }
+
+    /// Restrict the schedule to the given hours, from 0 to 23.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `hours` is empty or an hour is out of range.
+    pub fn at_hours(self, hours: &[u8]) -> Self {
+        Self {
+            hours: mask(hours, 0, 23),
+            ..self
+        }
+    }
+
+    /// Restrict the schedule to the given days of the month, from 1 to 31.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `days` is empty or a day is out of range.
+    pub fn on_days(self, days: &[u8]) -> Self {
+        Self {
+            days: mask(days, 1, 31),
+            days_restricted: true,
+            ..self
+        }
+    }
+
+    /// Restrict the schedule to the given months, from 1 to 12.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `months` is empty or a month is out of range.
+    pub fn in_months(self, months: &[u8]) -> Self {
+        Self {
+            months: mask(months, 1, 12),
+            ..self
+        }
+    }
+
+    /// Restrict the schedule to the given days of the week.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `weekdays` is empty.
+    pub fn on_weekdays(self, weekdays: &[Weekday]) -> Self {
+        assert!(!weekdays.is_empty(), "no weekdays given");
+        let weekdays = weekdays.iter().fold(0, |mask, w| mask | 1 << w.days_from_monday());
+        Self {
+            weekdays,
+            weekdays_restricted: true,
+            ..self
+        }
+    }
+
+    /// Returns whether the minute containing `time` is in the schedule.
+    pub fn matches(&self, time: UtcTime) -> bool {
+        let datetime = time.to_datetime();
+        self.matches_day(&datetime)
+            && has(self.months, datetime.month)
+            && has(self.hours, datetime.hour)
+            && has(self.minutes, datetime.minute)
+    }
+
+    /// Returns the first time in the schedule strictly after `time`, at the start of a minute, or
+    /// `None` if the schedule never matches (e.g. on February 30th).
+    pub fn next_after(&self, time: UtcTime) -> Option<UtcTime> {
+        let start = UtcTime::from_unix_secs((time.as_unix_secs() / 60 + 1) * 60);
+        let mut datetime = start.to_datetime();
+        // The calendar repeats every 400 years.
+        let last_year = datetime.year + 400;
+
+        while datetime.year <= last_year {
+            if !has(self.months, datetime.month) {
+                next_month(&mut datetime);
+            } else if !self.matches_day(&datetime) {
+                next_day(&mut datetime);
+            } else if !has(self.hours, datetime.hour) {
+                match next_bit(self.hours, datetime.hour) {
+                    Some(hour) => (datetime.hour, datetime.minute) = (hour, 0),
+                    None => next_day(&mut datetime),
+                }
+            } else {
+                match next_bit(self.minutes, datetime.minute) {
+                    Some(minute) => {
+                        datetime.minute = minute;
+                        return UtcTime::from_datetime(&datetime).ok();
+                    }
+                    None if datetime.hour == 23 => next_day(&mut datetime),
+                    None => (datetime.hour, datetime.minute) = (datetime.hour + 1, 0),
+                }
+            }
+        }
+        None
+    }
+
+    fn matches_day(&self, datetime: &DateTime) -> bool {
+        let day = has(self.days, datetime.day);
+        let weekday = has(self.weekdays, datetime.weekday().days_from_monday());
+        if self.days_restricted && self.weekdays_restricted {
+            day || weekday
+        } else {
+            day && weekday
+        }
+    }
+}
+
+impl FromStr for Schedule {
+    type Err = ScheduleError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::parse(s)
+    }
+}
+
+fn has(mask: u64, value: u8) -> bool {
+    mask & 1 << value != 0
+}
+
+/// The first value at or after `from` in `mask`.
+fn next_bit(mask: u64, from: u8) -> Option<u8> {
+    let rest = mask >> from;
+    (rest != 0).then(|| from + rest.trailing_zeros() as u8)
+}
+
+fn bits(min: u8, max: u8, step: u8) -> u64 {
+    (min..=max).step_by(step as usize).fold(0, |mask, v| mask | 1 << v)
+}
+
+fn mask(values: &[u8], min: u8, max: u8) -> u64 {
+    assert!(!values.is_empty(), "no values given");
+    values.iter().fold(0, |mask, &v| {
+        assert!((min..=max).contains(&v), "value out of range");
+        mask | 1 << v
+    })
+}
+
+fn next_month(datetime: &mut DateTime) {
+    if datetime.month == 12 {
+        datetime.year += 1;
+        datetime.month = 1;
+    } else {
+        datetime.month += 1;
+    }
+    (datetime.day, datetime.hour, datetime.minute) = (1, 0, 0);
+}
+
+fn next_day(datetime: &mut DateTime) {
+    if datetime.day == days_in_month(datetime.year, datetime.month) {
+        next_month(datetime);
+    } else {
+        datetime.day += 1;
+        (datetime.hour, datetime.minute) = (0, 0);
+    }
+}
+
+fn parse_field(field: &str, min: u8, max: u8, names: &[&str]) -> Result<u64, ScheduleError> {
+    let mut mask = 0;
+    for part in field.split(',') {
+        let (range, step) = match part.split_once('/') {
+            Some((range, step)) => match step.parse::<u8>() {
+                Ok(0) => return Err(ScheduleError::InvalidStep),
+                Ok(step) => (range, Some(step)),
+                Err(_) => return Err(ScheduleError::InvalidValue),
+            },
+            None => (part, None),
+        };
+        let (start, end) = match range.split_once('-') {
+            _ if range == "*" => (min, max),
+            Some((start, end)) => (parse_value(start, min, max, names)?, parse_value(end, min, max, names)?),
+            // `a/s` goes from `a` to the end of the field.
+            None if step.is_some() => (parse_value(range, min, max, names)?, max),
+            None => {
+                let value = parse_value(range, min, max, names)?;
+                (value, value)
+            }
+        };
+        if start > end {
+            return Err(ScheduleError::InvalidRange);
+        }
+        mask |= bits(start, end, step.unwrap_or(1));
+    }
+    Ok(mask)
+}
+
+fn parse_value(value: &str, min: u8, max: u8, names: &[&str]) -> Result<u8, ScheduleError> {
+    // Names start at the first value of the field, January or Sunday.
+    if let Some(i) = names.iter().position(|name| name.eq_ignore_ascii_case(value)) {
+        return Ok(min + i as u8);
+    }
+    let value: u8 = value.parse().map_err(|_| ScheduleError::InvalidValue)?;
+    if !(min..=max).contains(&value) {
+        return Err(ScheduleError::OutOfRange);
+    }
+    Ok(value)
+}
+
+/// Waits for the occurrences of a [`Schedule`], according to a [`WallClock`].
+///
+/// Occurrences are waited for with [`Timer::at()`], at the [`Instant`] the clock says they happen.
+/// Since the clock may be adjusted in the meantime, the scheduler wakes up at least every minute
+/// to check it again. This can be changed with
+/// [`set_recheck_interval()`](Self::set_recheck_interval).
+///
+/// Like cron, when the clock is stepped forward by up to 3 hours, the occurrences skipped over
+/// are delivered at once, as a single occurrence at the time of the first one, and when it is
+/// stepped back by up to 3 hours, occurrences already delivered are not repeated. After bigger
+/// steps, the scheduler starts over from the new time.
+///
+/// ```rust,no_run
+/// use embassy_time::{Schedule, Scheduler};
+///
+/// # async fn run_backup() {}
+/// async fn nightly_backup() {
+///     let mut scheduler = Scheduler::new(Schedule::daily_at(2, 0));
+///     loop {
+///         scheduler.next().await;
+///         run_backup().await;
+///     }
+/// }
+/// ```
+#[derive(Debug)]
+pub struct Scheduler<'a> {
+    schedule: Schedule,
+    clock: &'a WallClock,
+    last: Option<UtcTime>,
+    /// The occurrence being waited for, and the time it was found from.
+    pending: Option<(UtcTime, UtcTime)>,
+    recheck_interval: Duration,
+}
+
+impl Scheduler<'static> {
+    /// Create a scheduler following the [system clock](WallClock::system).
+    pub fn new(schedule: Schedule) -> Self {
+        Self::with_clock(schedule, WallClock::system())
+    }
+}
+
+impl<'a> Scheduler<'a> {
+    /// Create a scheduler following `clock`.
+    pub fn with_clock(schedule: Schedule, clock: &'a WallClock) -> Self {
+        Self {
+            schedule,
+            clock,
+            last: None,
+            pending: None,
+            recheck_interval: DEFAULT_RECHECK_INTERVAL,
+        }
+    }
+
+    /// Returns the schedule.
+    pub fn schedule(&self) -> &Schedule {
+        &self.schedule
+    }
+
+    /// Set how often to check the clock for adjustments while waiting for an occurrence, and for
+    /// the clock to be set.
+    pub fn set_recheck_interval(&mut self, interval: Duration) {
+        self.recheck_interval = interval;
+    }
+
+    /// Waits for the next occurrence of the schedule, and returns its time.
+    ///
+    /// This waits for the clock to be set first if it isn't, and forever if the schedule never
+    /// matches.
+    ///
+    /// ## Cancel safety
+    /// The produced Future is cancel safe: the occurrence it was waiting for is kept, and waited
+    /// for by the next call.
+    pub async fn next(&mut self) -> UtcTime {
+        'schedule: loop {
+            let (from, target) = match self.pending {
+                Some(pending) => pending,
+                None => {
+                    let now = self.now().await;
+                    // Don't repeat an occurrence if the clock was stepped back a little.
+                    let from = match self.last {
+                        Some(last) if last > now && last - now <= MAX_CLOCK_STEP => last,
+                        _ => now,
+                    };
+                    let Some(target) = self.schedule.next_after(from) else {
+                        core::future::pending::<()>().await;
+                        continue;
+                    };
+                    self.pending = Some((from, target));
+                    (from, target)
+                }
+            };
+
+            loop {
+                let now = self.now().await;
+                if now >= target {
+                    self.pending = None;
+                    if now - target > MAX_CLOCK_STEP {
+                        // The clock was stepped far past the occurrence, start over.
+                        self.last = None;
+                        continue 'schedule;
+                    }
+                    self.last = Some(target);
+                    return target;
+                }
+                if now < from && from - now > MAX_CLOCK_STEP {
+                    // The clock was stepped far back, start over.
+                    self.last = None;
+                    self.pending = None;
+                    continue 'schedule;
+                }
+
+                let recheck = Instant::now().saturating_add(self.recheck_interval);
+                let at = self.clock.instant_at(target).map_or(recheck, |at| at.min(recheck));
+                Timer::at(at).await;
+            }
+        }
+    }
+
+    /// Returns the current time, waiting for the clock to be set if needed.
+    async fn now(&self) -> UtcTime {
+        loop {
+            match self.clock.now() {
+                Some(now) => return now,
+                None => Timer::after(self.recheck_interval).await,
+            }
+        }
+    }
+}
+
+/// Periods of time starting at the occurrences of a [`Schedule`], e.g. maintenance windows.
+///
+/// ```rust
+/// use embassy_time::{Duration, Schedule, UtcTime, Weekday, Window};
+///
+/// // Sundays from 02:00 to 04:00 UTC.
+/// let maintenance = Window::new(
+///     Schedule::daily_at(2, 0).on_weekdays(&[Weekday::Sunday]),
+///     Duration::from_secs(2 * 3600),
+/// );
+/// // 2024-03-03 was a Sunday.
+/// assert!(maintenance.contains(UtcTime::from_unix_secs(1_709_434_800)));
+/// ```
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct Window {
+    schedule: Schedule,
+    length: Duration,
+}
+
+impl Window {
+    /// Create windows of `length` starting at each occurrence of `schedule`.
+    pub const fn new(schedule: Schedule, length: Duration) -> Self {
+        Self { schedule, length }
+    }
+
+    /// Returns the schedule of the window starts.
+    pub fn schedule(&self) -> &Schedule {
+        &self.schedule
+    }
+
+    /// Returns the length of the windows.
+    pub fn length(&self) -> Duration {
+        self.length
+    }
+
+    /// Returns whether `time` is in a window.
+    pub fn contains(&self, time: UtcTime) -> bool {
+        self.current_or_next(time).is_some_and(|(start, _)| start <= time)
+    }
+
+    /// Returns the start and end of the window containing `time`, or else of the next window.
+    ///
+    /// Returns `None` if the schedule never matches.
+    pub fn current_or_next(&self, time: UtcTime) -> Option<(UtcTime, UtcTime)> {
+        let from = time.checked_sub(self.length).unwrap_or(UtcTime::UNIX_EPOCH);
+        let start = self.schedule.next_after(from)?;
+        Some((start, start + self.length))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn time(year: u32, month: u8, day: u8, hour: u8, minute: u8) -> UtcTime {
+        let datetime = DateTime::from_ymd(year, month, day)
+            .unwrap()
+            .with_hms(hour, minute, 0)
+            .unwrap();
+        UtcTime::from_datetime(&datetime).unwrap()
+    }
+
+    #[test]
+    fn test_parse() {
+        assert_eq!(Schedule::parse("* * * * *"), Ok(Schedule::every_minute()));
+        assert_eq!(Schedule::parse("@daily"), Ok(Schedule::daily_at(0, 0)));
+        assert_eq!(
+            Schedule::parse(" 0,30 1-3 * * * "),
+            Ok(Schedule::every_minute().at_minutes(&[0, 30]).at_hours(&[1, 2, 3]))
+        );
+        assert_eq!(
+            Schedule::parse("0 0 1 jan-mar/2 sun,7"),
+            Ok(Schedule::daily_at(0, 0)
+                .on_days(&[1])
+                .in_months(&[1, 3])
+                .on_weekdays(&[Weekday::Sunday]))
+        );
+        assert_eq!(
+            Schedule::parse("50/5 * * * *"),
+            Ok(Schedule::every_minute().at_minutes(&[50, 55]))
+        );
+
+        assert_eq!(Schedule::parse("* * * *"), Err(ScheduleError::FieldCount));
+        assert_eq!(Schedule::parse("* * * * * *"), Err(ScheduleError::FieldCount));
+        assert_eq!(Schedule::parse("60 * * * *"), Err(ScheduleError::OutOfRange));
+        assert_eq!(Schedule::parse("* * 0 * *"), Err(ScheduleError::OutOfRange));
+        assert_eq!(Schedule::parse("* * * * FOO"), Err(ScheduleError::InvalidValue));
+        assert_eq!(Schedule::parse("5-1 * * * *"), Err(ScheduleError::InvalidRange));
+        assert_eq!(Schedule::parse("*/0 * * * *"), Err(ScheduleError::InvalidStep));
+    }
+
+    #[test]
+    fn test_next_after() {
+        let schedule = Schedule::daily_at(2, 0);
+        assert_eq!(
+            schedule.next_after(time(2024, 2, 28, 13, 5)),
+            Some(time(2024, 2, 29, 2, 0))
+        );
+        assert_eq!(
+            schedule.next_after(time(2024, 12, 31, 2, 0)),
+            Some(time(2025, 1, 1, 2, 0))
+        );
+
+        let schedule = Schedule::every_minutes(15);
+        let start = time(2024, 3, 1, 23, 52) + Duration::from_secs(30);
+        assert_eq!(schedule.next_after(start), Some(time(2024, 3, 2, 0, 0)));
+        assert!(schedule.matches(time(2024, 3, 2, 0, 0) + Duration::from_secs(30)));
+        assert!(!schedule.matches(start));
+
+        // Day of the month or day of the week, when both are restricted.
+        let schedule = Schedule::parse("0 12 13 * FRI").unwrap();
+        assert_eq!(
+            schedule.next_after(time(2024, 3, 1, 12, 0)),
+            Some(time(2024, 3, 8, 12, 0))
+        );
+        assert_eq!(
+            schedule.next_after(time(2024, 3, 8, 12, 0)),
+            Some(time(2024, 3, 13, 12, 0))
+        );
+        // A field listing every day is still restricted, unlike `*`, even with a step.
+        let schedule = Schedule::parse("0 12 1-31 * FRI").unwrap();
+        assert_eq!(
+            schedule.next_after(time(2024, 3, 1, 12, 0)),
+            Some(time(2024, 3, 2, 12, 0))
+        );
+        assert_eq!(
+            schedule,
+            Schedule::daily_at(12, 0)
+                .on_days(&core::array::from_fn::<u8, 31, _>(|i| i as u8 + 1))
+                .on_weekdays(&[Weekday::Friday])
+        );
+        let schedule = Schedule::parse("0 12 */2 * FRI").unwrap();
+        assert_eq!(
+            schedule.next_after(time(2024, 3, 1, 12, 0)),
+            Some(time(2024, 3, 15, 12, 0))
+        );
+
+        assert_eq!(
+            Schedule::parse("0 0 29 2 *")
+                .unwrap()
+                .next_after(time(2097, 3, 1, 0, 0)),
+            Some(time(2104, 2, 29, 0, 0))
+        );
+        assert_eq!(
+            Schedule::parse("0 0 30 2 *")
+                .unwrap()
+                .next_after(time(2024, 1, 1, 0, 0)),
+            None
+        );
+    }
+
+    #[test]
+    fn test_window() {
+        let window = Window::new(Schedule::daily_at(2, 0), Duration::from_secs(2 * 3600));
+        let start = time(2024, 3, 3, 2, 0);
+        let end = time(2024, 3, 3, 4, 0);
+        assert!(window.contains(start));
+        assert!(window.contains(time(2024, 3, 3, 3, 59)));
+        assert!(!window.contains(end));
+        assert_eq!(window.current_or_next(time(2024, 3, 3, 3, 0)), Some((start, end)));
+        assert_eq!(window.current_or_next(time(2024, 3, 2, 23, 0)), Some((start, end)));
+    }
+
+    #[cfg(feature = "mock-driver")]
+    #[test]
+    #[serial_test::serial]
+    fn test_scheduler() {
+        use core::pin::pin;
+        use core::task::{Context, Poll, Waker};
+
+        let driver = crate::MockDriver::get();
+        driver.reset();
+        let mut cx = Context::from_waker(Waker::noop());
+        let clock = WallClock::new();
+        let mut scheduler = Scheduler::with_clock(Schedule::every_minutes(15), &clock);
+
+        {
+            let mut next = pin!(scheduler.next());
+            assert_eq!(next.as_mut().poll(&mut cx), Poll::Pending);
+            // The clock is set while waiting.
+            clock.set(time(2024, 3, 1, 11, 50));
+            driver.advance(Duration::from_secs(60));
+            assert_eq!(next.as_mut().poll(&mut cx), Poll::Pending);
+            driver.advance(Duration::from_secs(9 * 60));
+            assert_eq!(next.as_mut().poll(&mut cx), Poll::Ready(time(2024, 3, 1, 12, 0)));
+        }
+
+        {
+            // The clock is stepped forward past the next occurrence.
+            let mut next = pin!(scheduler.next());
+            assert_eq!(next.as_mut().poll(&mut cx), Poll::Pending);
+            clock.set(time(2024, 3, 1, 12, 20));
+            driver.advance(Duration::from_secs(60));
+            assert_eq!(next.as_mut().poll(&mut cx), Poll::Ready(time(2024, 3, 1, 12, 15)));
+        }
+
+        {
+            // The clock is stepped forward past several occurrences, only the first is delivered.
+            let mut next = pin!(scheduler.next());
+            assert_eq!(next.as_mut().poll(&mut cx), Poll::Pending);
+            clock.set(time(2024, 3, 1, 13, 5));
+            driver.advance(Duration::from_secs(60));
+            assert_eq!(next.as_mut().poll(&mut cx), Poll::Ready(time(2024, 3, 1, 12, 30)));
+        }
+        {
+            let mut next = pin!(scheduler.next());
+            assert_eq!(next.as_mut().poll(&mut cx), Poll::Pending);
+            driver.advance(Duration::from_secs(9 * 60));
+            assert_eq!(next.as_mut().poll(&mut cx), Poll::Ready(time(2024, 3, 1, 13, 15)));
+        }
+
+        {
+            // The occurrence waited for by a dropped future is delivered by the next one.
+            let mut next = pin!(scheduler.next());
+            assert_eq!(next.as_mut().poll(&mut cx), Poll::Pending);
+        }
+        driver.advance(Duration::from_secs(20 * 60));
+        assert_eq!(
+            pin!(scheduler.next()).poll(&mut cx),
+            Poll::Ready(time(2024, 3, 1, 13, 30))
+        );
+
+        // The clock is stepped back, occurrences are not repeated.
+        clock.set(time(2024, 3, 1, 13, 25));
+        let mut next = pin!(scheduler.next());
+        driver.advance(Duration::from_secs(10 * 60));
+        assert_eq!(next.as_mut().poll(&mut cx), Poll::Pending);
+        driver.advance(Duration::from_secs(10 * 60));
+        assert_eq!(next.as_mut().poll(&mut cx), Poll::Ready(time(2024, 3, 1, 13, 45)));
+    }
+}
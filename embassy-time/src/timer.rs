@@ -92,6 +92,255 @@ impl<F: Future> Future for TimeoutFuture<F> {
     }
 }
 
+/// Delay between the attempts of a [`Retry`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum Backoff {
+    /// The same delay before every retry.
+    Constant(Duration),
+    /// A delay starting at `initial`, growing by `step` before every retry, up to `max`.
+    Linear {
+        /// Delay before the first retry.
+        initial: Duration,
+        /// Increase of the delay before every following retry.
+        step: Duration,
+        /// Maximum delay.
+        max: Duration,
+    },
+    /// A delay starting at `initial`, multiplied by `factor` before every retry, up to `max`.
+    Exponential {
+        /// Delay before the first retry.
+        initial: Duration,
+        /// Factor by which the delay grows before every following retry.
+        factor: u32,
+        /// Maximum delay.
+        max: Duration,
+    },
+}
+
+impl Backoff {
+    /// Delay before retry number `retry`, starting from 0 for the retry after the first attempt.
+    pub fn delay(&self, retry: u32) -> Duration {
+        match *self {
+            Backoff::Constant(delay) => delay,
+            Backoff::Linear { initial, step, max } => step
+                .checked_mul(retry)
+                .and_then(|increase| initial.checked_add(increase))
+                .map_or(max, |delay| delay.min(max)),
+            Backoff::Exponential { initial, factor, max } => {
+                let growth = (factor as u64).checked_pow(retry).unwrap_or(u64::MAX);
+                Duration::from_ticks(initial.as_ticks().saturating_mul(growth)).min(max)
+            }
+        }
+    }
+}
+
+/// Randomization of the [`Backoff`] delays of a [`Retry`], so that tasks failing at the same
+/// time don't all retry at the same time.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum Jitter {
+    /// Use the backoff delay as is.
+    #[default]
+    None,
+    /// Wait a random delay between zero and the backoff delay.
+    Full,
+    /// Wait half the backoff delay, plus a random delay between zero and the other half.
+    Equal,
+}
+
+impl Jitter {
+    fn apply(self, delay: Duration, random: u32) -> Duration {
+        // Scale `ticks` by a random fraction between 0 and 1.
+        let scale = |ticks: u64| Duration::from_ticks(((ticks as u128 * random as u128) >> 32) as u64);
+        match self {
+            Jitter::None => delay,
+            Jitter::Full => scale(delay.as_ticks()),
+            Jitter::Equal => {
+                let half = delay.as_ticks() / 2;
+                Duration::from_ticks(delay.as_ticks() - half) + scale(half)
+            }
+        }
+    }
+}
+
+/// Error returned by [`Retry::run`] and [`Retry::run_if`] when the operation didn't succeed.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum RetryError<E> {
+    /// The last attempt failed with an error that isn't retryable, or after which no attempts
+    /// were left.
+    Failed(E),
+    /// The last attempt timed out, or the deadline was reached during it.
+    Timeout,
+}
+
+/// Runs a fallible async operation until it succeeds, waiting between attempts according to a
+/// [`Backoff`] strategy.
+///
+/// By default the operation is retried until it succeeds. The number of attempts and the time
+/// spent retrying can be limited with [`max_attempts()`](Self::max_attempts),
+/// [`timeout()`](Self::timeout) and [`deadline()`](Self::deadline), and each attempt can be
+/// limited with [`attempt_timeout()`](Self::attempt_timeout). An attempt that times out is
+/// retried like a failed one.
+///
+/// Example:
+/// ``` no_run
+/// use embassy_time::{Backoff, Duration, Jitter, Retry, RetryError};
+/// # struct Socket;
+/// # #[derive(Debug)]
+/// # enum Error { Refused, InvalidAddress }
+/// # async fn connect() -> Result<Socket, Error> { Err(Error::Refused) }
+/// # fn random() -> u32 { 4 }
+///
+/// #[embassy_executor::task]
+/// async fn connect_task() {
+///     let mut retry = Retry::new(Backoff::Exponential {
+///         initial: Duration::from_millis(100),
+///         factor: 2,
+///         max: Duration::from_secs(10),
+///     })
+///     .max_attempts(8)
+///     .attempt_timeout(Duration::from_secs(5))
+///     .jitter(Jitter::Full, random);
+///
+///     match retry.run_if(|e| !matches!(e, Error::InvalidAddress), |_attempt| connect()).await {
+///         Ok(_socket) => {}
+///         Err(RetryError::Failed(_e)) => {}
+///         Err(RetryError::Timeout) => {}
+///     }
+/// }
+/// ```
+#[derive(Debug, Clone)]
+pub struct Retry<R = fn() -> u32> {
+    backoff: Backoff,
+    jitter: Jitter,
+    rng: R,
+    max_attempts: Option<u32>,
+    timeout: Option<Duration>,
+    deadline: Option<Instant>,
+    attempt_timeout: Option<Duration>,
+}
+
+impl Retry {
+    /// Retry with the given backoff strategy, without jitter nor limits.
+    pub fn new(backoff: Backoff) -> Self {
+        Self {
+            backoff,
+            jitter: Jitter::None,
+            rng: || 0,
+            max_attempts: None,
+            timeout: None,
+            deadline: None,
+            attempt_timeout: None,
+        }
+    }
+}
+
+impl<R: FnMut() -> u32> Retry<R> {
+    /// Randomize the backoff delays, using `rng` to generate random numbers.
+    pub fn jitter<R2: FnMut() -> u32>(self, jitter: Jitter, rng: R2) -> Retry<R2> {
+        Retry {
+            backoff: self.backoff,
+            jitter,
+            rng,
+            max_attempts: self.max_attempts,
+            timeout: self.timeout,
+            deadline: self.deadline,
+            attempt_timeout: self.attempt_timeout,
+        }
+    }
+
+    /// Give up after `attempts` attempts, including the first one.
+    pub fn max_attempts(mut self, attempts: u32) -> Self {
+        self.max_attempts = Some(attempts);
+        self
+    }
+
+    /// Give up once `timeout` has elapsed since the start of a run.
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = Some(timeout);
+        self
+    }
+
+    /// Give up at the `at` deadline.
+    pub fn deadline(mut self, at: Instant) -> Self {
+        self.deadline = Some(at);
+        self
+    }
+
+    /// Stop each attempt after `timeout`, and retry.
+    pub fn attempt_timeout(mut self, timeout: Duration) -> Self {
+        self.attempt_timeout = Some(timeout);
+        self
+    }
+
+    /// Run `op` until it succeeds, retrying on any error.
+    ///
+    /// `op` is called with the attempt number, starting from 1.
+    pub async fn run<T, E, F, Fut>(&mut self, op: F) -> Result<T, RetryError<E>>
+    where
+        F: FnMut(u32) -> Fut,
+        Fut: Future<Output = Result<T, E>>,
+    {
+        self.run_if(|_| true, op).await
+    }
+
+    /// Run `op` until it succeeds, retrying on the errors for which `retryable` returns `true`.
+    ///
+    /// `op` is called with the attempt number, starting from 1.
+    pub async fn run_if<T, E, P, F, Fut>(&mut self, mut retryable: P, mut op: F) -> Result<T, RetryError<E>>
+    where
+        P: FnMut(&E) -> bool,
+        F: FnMut(u32) -> Fut,
+        Fut: Future<Output = Result<T, E>>,
+    {
+        let timeout_deadline = self.timeout.and_then(|timeout| Instant::now().checked_add(timeout));
+        let deadline = match (self.deadline, timeout_deadline) {
+            (Some(a), Some(b)) => Some(a.min(b)),
+            (a, b) => a.or(b),
+        };
+
+        let mut attempt = 0;
+        loop {
+            attempt += 1;
+            let attempt_deadline = self
+                .attempt_timeout
+                .and_then(|timeout| Instant::now().checked_add(timeout));
+            let attempt_deadline = match (attempt_deadline, deadline) {
+                (Some(a), Some(b)) => Some(a.min(b)),
+                (a, b) => a.or(b),
+            };
+
+            let result = match attempt_deadline {
+                Some(at) => with_deadline(at, op(attempt)).await,
+                None => Ok(op(attempt).await),
+            };
+            let error = match result {
+                Ok(Ok(value)) => return Ok(value),
+                Ok(Err(e)) if !retryable(&e) => return Err(RetryError::Failed(e)),
+                Ok(Err(e)) => RetryError::Failed(e),
+                Err(TimeoutError) => RetryError::Timeout,
+            };
+
+            if self.max_attempts.is_some_and(|max| attempt >= max) {
+                return Err(error);
+            }
+            let delay = self.backoff.delay(attempt - 1);
+            let delay = match self.jitter {
+                Jitter::None => delay,
+                jitter => jitter.apply(delay, (self.rng)()),
+            };
+            let retry_at = Instant::now().checked_add(delay);
+            match (retry_at, deadline) {
+                (Some(retry_at), Some(deadline)) if retry_at >= deadline => return Err(error),
+                (Some(retry_at), _) => Timer::at(retry_at).await,
+                (None, _) => return Err(error),
+            }
+        }
+    }
+}
+
 /// A future that completes at a specified [Instant](struct.Instant.html).
 #[must_use = "futures do nothing unless you `.await` or poll them"]
 #[derive(Debug)]
@@ -229,6 +478,13 @@ impl Future for Timer {
 /// }
 /// ```
 ///
+/// ## Missed ticks
+/// If the ticker is polled late, for example because a tick took longer to process than the
+/// tick period, the ticks that were due in the meantime are handled according to its
+/// [`MissedTickBehavior`], [`Burst`](MissedTickBehavior::Burst) by default. The ticker counts
+/// missed ticks and measures how late ticks are delivered, see [`Ticker::missed_ticks()`] and
+/// [`Ticker::max_jitter()`].
+///
 /// ## Cancel safety
 /// It is safe to cancel waiting for the next tick,
 /// meaning no tick is lost if the Future is dropped.
@@ -237,13 +493,38 @@ impl Future for Timer {
 pub struct Ticker {
     expires_at: Instant,
     duration: Duration,
+    behavior: MissedTickBehavior,
+    missed_ticks: u64,
+    last_jitter: Duration,
+    max_jitter: Duration,
+}
+
+/// What a [`Ticker`] does with the ticks that were due while it wasn't polled.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum MissedTickBehavior {
+    /// Deliver all missed ticks immediately, one after the other, until caught up with the
+    /// original schedule.
+    #[default]
+    Burst,
+    /// Deliver a single tick, and skip the missed ones, staying aligned with the original schedule.
+    Skip,
+    /// Deliver a single tick, and schedule the next one a period after it.
+    Delay,
 }
 
 impl Ticker {
     /// Creates a new ticker that ticks at the specified duration interval.
     pub fn every(duration: Duration) -> Self {
         let expires_at = Instant::now() + duration;
-        Self { expires_at, duration }
+        Self {
+            expires_at,
+            duration,
+            behavior: MissedTickBehavior::Burst,
+            missed_ticks: 0,
+            last_jitter: Duration::MIN,
+            max_jitter: Duration::MIN,
+        }
     }
 
     /// Resets the ticker back to its original state.
@@ -264,21 +545,86 @@ impl Ticker {
         self.expires_at = Instant::now() + after + self.duration;
     }
 
+    /// Returns the behavior of the ticker when ticks are missed.
+    pub fn missed_tick_behavior(&self) -> MissedTickBehavior {
+        self.behavior
+    }
+
+    /// Sets the behavior of the ticker when ticks are missed.
+    pub fn set_missed_tick_behavior(&mut self, behavior: MissedTickBehavior) {
+        self.behavior = behavior;
+    }
+
+    /// Returns the number of missed ticks since the ticker was created or its statistics were reset.
+    ///
+    /// A tick is missed if it is skipped, or delivered a whole period or more after it was due.
+    pub fn missed_ticks(&self) -> u64 {
+        self.missed_ticks
+    }
+
+    /// Returns how late the last tick was delivered, compared to when it was due.
+    pub fn last_jitter(&self) -> Duration {
+        self.last_jitter
+    }
+
+    /// Returns how late the latest tick was delivered since the ticker was created or its
+    /// statistics were reset.
+    pub fn max_jitter(&self) -> Duration {
+        self.max_jitter
+    }
+
+    /// Resets the missed tick count and the jitter statistics.
+    pub fn reset_stats(&mut self) {
+        self.missed_ticks = 0;
+        self.last_jitter = Duration::MIN;
+        self.max_jitter = Duration::MIN;
+    }
+
+    /// Polls for the next tick.
+    ///
+    /// This is the same as awaiting [`next()`](Self::next), for use in hand-written futures or
+    /// in `poll_fn`, e.g. to wait for the next tick in a `select` along with other events.
+    pub fn poll_next_tick(&mut self, cx: &mut Context<'_>) -> Poll<()> {
+        let now = Instant::now();
+        if self.expires_at > now {
+            embassy_time_driver::schedule_wake(self.expires_at.as_ticks(), cx.waker());
+            return Poll::Pending;
+        }
+
+        let jitter = now - self.expires_at;
+        self.last_jitter = jitter;
+        self.max_jitter = self.max_jitter.max(jitter);
+
+        // Number of ticks after this one that are already due.
+        let late_ticks = match self.duration.as_ticks() {
+            0 => 0,
+            period => jitter.as_ticks() / period,
+        };
+        match self.behavior {
+            MissedTickBehavior::Burst => {
+                if late_ticks > 0 {
+                    self.missed_ticks += 1;
+                }
+                self.expires_at += self.duration;
+            }
+            MissedTickBehavior::Skip => {
+                self.missed_ticks += late_ticks;
+                self.expires_at += Duration::from_ticks(self.duration.as_ticks() * (late_ticks + 1));
+            }
+            MissedTickBehavior::Delay => {
+                self.missed_ticks += late_ticks;
+                self.expires_at = now + self.duration;
+            }
+        }
+        Poll::Ready(())
+    }
+
     /// Waits for the next tick.
     ///
     /// ## Cancel safety
     /// The produced Future is cancel safe, meaning no tick is lost if the Future is dropped.
     pub fn next(&mut self) -> impl Future<Output = ()> + Send + Sync + '_ {
-        poll_fn(|cx| {
-            if self.expires_at <= Instant::now() {
-                let dur = self.duration;
-                self.expires_at += dur;
-                Poll::Ready(())
-            } else {
-                embassy_time_driver::schedule_wake(self.expires_at.as_ticks(), cx.waker());
-                Poll::Pending
-            }
-        })
+        poll_fn(|cx| self.poll_next_tick(cx))
     }
 }
 
@@ -287,14 +633,7 @@ impl Unpin for Ticker {}
 impl Stream for Ticker {
     type Item = ();
     fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
-        if self.expires_at <= Instant::now() {
-            let dur = self.duration;
-            self.expires_at += dur;
-            Poll::Ready(Some(()))
-        } else {
-            embassy_time_driver::schedule_wake(self.expires_at.as_ticks(), cx.waker());
-            Poll::Pending
-        }
+        self.poll_next_tick(cx).map(Some)
     }
 }
 
@@ -304,3 +643,215 @@ impl FusedStream for Ticker {
         false
     }
 }
+
+#[cfg(all(test, feature = "mock-driver"))]
+mod tests {
+    use core::task::Waker;
+
+    use serial_test::serial;
+
+    use super::*;
+    use crate::MockDriver;
+
+    fn setup() -> &'static MockDriver {
+        let driver = MockDriver::get();
+        driver.reset();
+        driver
+    }
+
+    /// Poll `ticker` until it's pending, returning the number of ticks delivered.
+    fn drain(ticker: &mut Ticker) -> u32 {
+        let mut cx = Context::from_waker(Waker::noop());
+        let mut ticks = 0;
+        while ticker.poll_next_tick(&mut cx).is_ready() {
+            ticks += 1;
+        }
+        ticks
+    }
+
+    #[test]
+    #[serial]
+    fn test_ticker_on_time() {
+        let driver = setup();
+        let mut ticker = Ticker::every(Duration::from_millis(10));
+
+        assert_eq!(drain(&mut ticker), 0);
+        for _ in 0..3 {
+            driver.advance(Duration::from_millis(10));
+            assert_eq!(drain(&mut ticker), 1);
+        }
+        assert_eq!(ticker.missed_ticks(), 0);
+        assert_eq!(ticker.max_jitter(), Duration::from_ticks(0));
+    }
+
+    #[test]
+    #[serial]
+    fn test_ticker_missed_burst() {
+        let driver = setup();
+        let mut ticker = Ticker::every(Duration::from_millis(10));
+
+        driver.advance(Duration::from_millis(35));
+        assert_eq!(drain(&mut ticker), 3);
+        assert_eq!(ticker.missed_ticks(), 2);
+        assert_eq!(ticker.last_jitter(), Duration::from_millis(5));
+        assert_eq!(ticker.max_jitter(), Duration::from_millis(25));
+
+        driver.advance(Duration::from_millis(5));
+        assert_eq!(drain(&mut ticker), 1);
+        assert_eq!(ticker.last_jitter(), Duration::from_ticks(0));
+    }
+
+    #[test]
+    #[serial]
+    fn test_ticker_missed_skip() {
+        let driver = setup();
+        let mut ticker = Ticker::every(Duration::from_millis(10));
+        ticker.set_missed_tick_behavior(MissedTickBehavior::Skip);
+
+        driver.advance(Duration::from_millis(35));
+        assert_eq!(drain(&mut ticker), 1);
+        assert_eq!(ticker.missed_ticks(), 2);
+        assert_eq!(ticker.max_jitter(), Duration::from_millis(25));
+
+        // Still aligned with the original schedule.
+        driver.advance(Duration::from_millis(4));
+        assert_eq!(drain(&mut ticker), 0);
+        driver.advance(Duration::from_millis(1));
+        assert_eq!(drain(&mut ticker), 1);
+
+        ticker.reset_stats();
+        assert_eq!(ticker.missed_ticks(), 0);
+        assert_eq!(ticker.max_jitter(), Duration::from_ticks(0));
+    }
+
+    #[test]
+    #[serial]
+    fn test_ticker_missed_delay() {
+        let driver = setup();
+        let mut ticker = Ticker::every(Duration::from_millis(10));
+        ticker.set_missed_tick_behavior(MissedTickBehavior::Delay);
+
+        driver.advance(Duration::from_millis(35));
+        assert_eq!(drain(&mut ticker), 1);
+        assert_eq!(ticker.missed_ticks(), 2);
+
+        // The next tick is a period after the late one.
+        driver.advance(Duration::from_millis(9));
+        assert_eq!(drain(&mut ticker), 0);
+        driver.advance(Duration::from_millis(1));
+        assert_eq!(drain(&mut ticker), 1);
+        assert_eq!(ticker.missed_ticks(), 2);
+    }
+
+    /// Run `fut` to completion, advancing the time by a millisecond whenever it's pending.
+    fn run_advancing<F: Future>(driver: &MockDriver, fut: F) -> F::Output {
+        let mut fut = core::pin::pin!(fut);
+        let mut cx = Context::from_waker(Waker::noop());
+        loop {
+            if let Poll::Ready(output) = fut.as_mut().poll(&mut cx) {
+                return output;
+            }
+            driver.advance(Duration::from_millis(1));
+        }
+    }
+
+    #[test]
+    fn test_backoff_delay() {
+        let ms = Duration::from_millis;
+        let constant = Backoff::Constant(ms(10));
+        assert_eq!(constant.delay(0), ms(10));
+        assert_eq!(constant.delay(100), ms(10));
+
+        let linear = Backoff::Linear {
+            initial: ms(10),
+            step: ms(5),
+            max: ms(30),
+        };
+        assert_eq!(linear.delay(0), ms(10));
+        assert_eq!(linear.delay(2), ms(20));
+        assert_eq!(linear.delay(u32::MAX), ms(30));
+
+        let exponential = Backoff::Exponential {
+            initial: ms(10),
+            factor: 3,
+            max: ms(500),
+        };
+        assert_eq!(exponential.delay(0), ms(10));
+        assert_eq!(exponential.delay(3), ms(270));
+        assert_eq!(exponential.delay(4), ms(500));
+        assert_eq!(exponential.delay(u32::MAX), ms(500));
+
+        let zero = Backoff::Exponential {
+            initial: ms(0),
+            factor: 2,
+            max: ms(500),
+        };
+        assert_eq!(zero.delay(u32::MAX), ms(0));
+        let flat = Backoff::Exponential {
+            initial: ms(10),
+            factor: 1,
+            max: ms(500),
+        };
+        assert_eq!(flat.delay(u32::MAX), ms(10));
+    }
+
+    #[test]
+    fn test_jitter() {
+        let delay = Duration::from_ticks(1000);
+        assert_eq!(Jitter::None.apply(delay, u32::MAX), delay);
+        assert_eq!(Jitter::Full.apply(delay, 0), Duration::from_ticks(0));
+        assert_eq!(Jitter::Full.apply(delay, 1 << 31), Duration::from_ticks(500));
+        assert_eq!(Jitter::Equal.apply(delay, 0), Duration::from_ticks(500));
+        assert_eq!(Jitter::Equal.apply(delay, u32::MAX), Duration::from_ticks(999));
+    }
+
+    #[test]
+    #[serial]
+    fn test_retry_until_success() {
+        let driver = setup();
+        let mut retry = Retry::new(Backoff::Exponential {
+            initial: Duration::from_millis(10),
+            factor: 2,
+            max: Duration::from_secs(1),
+        });
+
+        let result = run_advancing(
+            driver,
+            retry.run(|attempt| async move { if attempt < 4 { Err(attempt) } else { Ok(attempt) } }),
+        );
+        assert_eq!(result, Ok(4));
+        // Waited 10 + 20 + 40 ms between the attempts.
+        assert_eq!(Instant::now(), Instant::from_millis(70));
+    }
+
+    #[test]
+    #[serial]
+    fn test_retry_gives_up() {
+        let driver = setup();
+        let mut retry = Retry::new(Backoff::Constant(Duration::from_millis(10))).max_attempts(3);
+        let result: Result<(), _> = run_advancing(driver, retry.run(|attempt| async move { Err(attempt) }));
+        assert_eq!(result, Err(RetryError::Failed(3)));
+
+        // Errors that aren't retryable are returned right away.
+        let result: Result<(), _> =
+            run_advancing(driver, retry.run_if(|e| *e != 2, |attempt| async move { Err(attempt) }));
+        assert_eq!(result, Err(RetryError::Failed(2)));
+
+        // Attempts that time out are retried, until the deadline.
+        let start = Instant::now();
+        let mut retry = Retry::new(Backoff::Constant(Duration::from_millis(10)))
+            .attempt_timeout(Duration::from_millis(20))
+            .timeout(Duration::from_millis(100));
+        let mut attempts = 0;
+        let result: Result<(), RetryError<()>> = run_advancing(
+            driver,
+            retry.run(|attempt| {
+                attempts = attempt;
+                core::future::pending()
+            }),
+        );
+        assert_eq!(result, Err(RetryError::Timeout));
+        assert_eq!(attempts, 4);
+        assert_eq!(Instant::now() - start, Duration::from_millis(100));
+    }
+}
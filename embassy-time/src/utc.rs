@@ -0,0 +1,719 @@
+use core::cell::Cell;
+use core::fmt;
+use core::ops::{Add, AddAssign, Sub, SubAssign};
+
+use critical_section::Mutex;
+
+use crate::{Duration, Instant, TICK_HZ};
+
+const MICROS_PER_SEC: u64 = 1_000_000;
+const SECS_PER_DAY: u64 = 86_400;
+/// Days from 0000-03-01 to 1970-01-01, in the proleptic Gregorian calendar.
+const DAYS_TO_UNIX_EPOCH: i64 = 719_468;
+
+/// A point in wall-clock time, in microseconds since the Unix epoch (1970-01-01T00:00:00Z).
+///
+/// Like Unix time, leap seconds are not counted: every day is 86400 seconds long.
+///
+/// Get the current time with [`UtcTime::now()`] once the system [`WallClock`] has been set,
+/// and convert it to and from calendar date and time with [`DateTime`].
+#[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct UtcTime {
+    micros: u64,
+}
+
+impl UtcTime {
+    /// The Unix epoch, 1970-01-01T00:00:00Z.
+    pub const UNIX_EPOCH: UtcTime = UtcTime { micros: 0 };
+
+    /// Returns the current time according to the system [`WallClock`], or `None` if it hasn't
+    /// been set.
+    pub fn now() -> Option<UtcTime> {
+        WallClock::system().now()
+    }
+
+    /// Create a time from a number of seconds since the Unix epoch.
+    ///
+    /// Saturates at the latest representable time, about 584000 years after the epoch.
+    pub const fn from_unix_secs(secs: u64) -> Self {
+        Self {
+            micros: secs.saturating_mul(MICROS_PER_SEC),
+        }
+    }
+
+    /// Create a time from a number of milliseconds since the Unix epoch.
+    ///
+    /// Saturates at the latest representable time, about 584000 years after the epoch.
+    pub const fn from_unix_millis(millis: u64) -> Self {
+        Self {
+            micros: millis.saturating_mul(1000),
+        }
+    }
+
+    /// Create a time from a number of microseconds since the Unix epoch.
+    pub const fn from_unix_micros(micros: u64) -> Self {
+        Self { micros }
+    }
+
+    /// Seconds since the Unix epoch, rounding down.
+    pub const fn as_unix_secs(&self) -> u64 {
+        self.micros / MICROS_PER_SEC
+    }
+
+    /// Milliseconds since the Unix epoch, rounding down.
+    pub const fn as_unix_millis(&self) -> u64 {
+        self.micros / 1000
+    }
+
+    /// Microseconds since the Unix epoch.
+    pub const fn as_unix_micros(&self) -> u64 {
+        self.micros
+    }
+
+    /// Convert a calendar date and time to a `UtcTime`.
+    pub fn from_datetime(datetime: &DateTime) -> Result<Self, DateTimeError> {
+        datetime.validate()?;
+        let days = days_from_civil(datetime.year as i64, datetime.month as i64, datetime.day as i64) as u64;
+        let secs =
+            days * SECS_PER_DAY + datetime.hour as u64 * 3600 + datetime.minute as u64 * 60 + datetime.second as u64;
+        Ok(Self {
+            micros: secs * MICROS_PER_SEC + datetime.microsecond as u64,
+        })
+    }
+
+    /// Convert to a calendar date and time.
+    pub fn to_datetime(&self) -> DateTime {
+        let secs = self.micros / MICROS_PER_SEC;
+        let days = secs / SECS_PER_DAY;
+        let secs_of_day = secs % SECS_PER_DAY;
+        let (year, month, day) = civil_from_days(days as i64);
+        DateTime {
+            year: year as u32,
+            month: month as u8,
+            day: day as u8,
+            hour: (secs_of_day / 3600) as u8,
+            minute: (secs_of_day / 60 % 60) as u8,
+            second: (secs_of_day % 60) as u8,
+            microsecond: (self.micros % MICROS_PER_SEC) as u32,
+        }
+    }
+
+    /// Day of the week.
+    pub fn weekday(&self) -> Weekday {
+        // 1970-01-01 was a Thursday.
+        Weekday::from_days_from_monday(((self.micros / MICROS_PER_SEC / SECS_PER_DAY + 3) % 7) as u8)
+    }
+
+    /// Duration between this time and an earlier one. Panics if `earlier` is later than `self`.
+    pub fn duration_since(&self, earlier: UtcTime) -> Duration {
+        unwrap!(self.checked_duration_since(earlier))
+    }
+
+    /// Duration between this time and an earlier one, or `None` if `earlier` is later than `self`.
+    pub fn checked_duration_since(&self, earlier: UtcTime) -> Option<Duration> {
+        self.micros.checked_sub(earlier.micros).map(Duration::from_micros_floor)
+    }
+
+    /// Adds a duration to this time, returning `None` on overflow.
+    pub fn checked_add(&self, duration: Duration) -> Option<UtcTime> {
+        self.micros
+            .checked_add(duration.as_micros())
+            .map(|micros| UtcTime { micros })
+    }
+
+    /// Subtracts a duration from this time, returning `None` if it would be before the Unix epoch.
+    pub fn checked_sub(&self, duration: Duration) -> Option<UtcTime> {
+        self.micros
+            .checked_sub(duration.as_micros())
+            .map(|micros| UtcTime { micros })
+    }
+}
+
+impl Add<Duration> for UtcTime {
+    type Output = UtcTime;
+
+    fn add(self, other: Duration) -> UtcTime {
+        self.checked_add(other)
+            .expect("overflow when adding duration to UtcTime")
+    }
+}
+
+impl AddAssign<Duration> for UtcTime {
+    fn add_assign(&mut self, other: Duration) {
+        *self = *self + other;
+    }
+}
+
+impl Sub<Duration> for UtcTime {
+    type Output = UtcTime;
+
+    fn sub(self, other: Duration) -> UtcTime {
+        self.checked_sub(other)
+            .expect("overflow when subtracting duration from UtcTime")
+    }
+}
+
+impl SubAssign<Duration> for UtcTime {
+    fn sub_assign(&mut self, other: Duration) {
+        *self = *self - other;
+    }
+}
+
+impl Sub<UtcTime> for UtcTime {
+    type Output = Duration;
+
+    fn sub(self, other: UtcTime) -> Duration {
+        self.duration_since(other)
+    }
+}
+
+impl fmt::Display for UtcTime {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Display::fmt(&self.to_datetime(), f)
+    }
+}
+
+#[cfg(feature = "std")]
+impl From<UtcTime> for std::time::SystemTime {
+    fn from(time: UtcTime) -> Self {
+        std::time::UNIX_EPOCH + std::time::Duration::from_micros(time.micros)
+    }
+}
+
+#[cfg(feature = "std")]
+impl TryFrom<std::time::SystemTime> for UtcTime {
+    type Error = std::time::SystemTimeError;
+
+    /// Fails if `time` is before the Unix epoch.
+    fn try_from(time: std::time::SystemTime) -> Result<Self, Self::Error> {
+        let since_epoch = time.duration_since(std::time::UNIX_EPOCH)?;
+        Ok(Self {
+            micros: since_epoch.as_micros() as u64,
+        })
+    }
+}
+
+/// A calendar date and time of day, in UTC.
+///
+/// This is the common representation to convert to and from the date and time types of RTC
+/// peripherals, GNSS receivers, etc.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct DateTime {
+    /// Year, 1970 or later.
+    pub year: u32,
+    /// Month, 1 (January) to 12 (December).
+    pub month: u8,
+    /// Day of the month, starting at 1.
+    pub day: u8,
+    /// Hour, 0 to 23.
+    pub hour: u8,
+    /// Minute, 0 to 59.
+    pub minute: u8,
+    /// Second, 0 to 59.
+    pub second: u8,
+    /// Microsecond, 0 to 999_999.
+    pub microsecond: u32,
+}
+
+/// Error returned when a [`DateTime`] is not a valid date and time.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum DateTimeError {
+    /// The year is before 1970, or too far in the future.
+    InvalidYear,
+    /// The month is not in `1..=12`.
+    InvalidMonth,
+    /// The day is not in the month.
+    InvalidDay,
+    /// The hour, minute, second or microsecond is out of range.
+    InvalidTime,
+}
+
+impl fmt::Display for DateTimeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let msg = match self {
+            DateTimeError::InvalidYear => "invalid year",
+            DateTimeError::InvalidMonth => "invalid month",
+            DateTimeError::InvalidDay => "invalid day",
+            DateTimeError::InvalidTime => "invalid time of day",
+        };
+        f.write_str(msg)
+    }
+}
+
+impl core::error::Error for DateTimeError {}
+
+impl DateTime {
+    /// Create a date at midnight, checking that it is valid.
+    pub fn from_ymd(year: u32, month: u8, day: u8) -> Result<Self, DateTimeError> {
+        let datetime = Self {
+            year,
+            month,
+            day,
+            hour: 0,
+            minute: 0,
+            second: 0,
+            microsecond: 0,
+        };
+        datetime.validate()?;
+        Ok(datetime)
+    }
+
+    /// Set the time of day, checking that it is valid.
+    pub fn with_hms(self, hour: u8, minute: u8, second: u8) -> Result<Self, DateTimeError> {
+        let datetime = Self {
+            hour,
+            minute,
+            second,
+            ..self
+        };
+        datetime.validate()?;
+        Ok(datetime)
+    }
+
+    /// Check that this is a valid date and time.
+    pub fn validate(&self) -> Result<(), DateTimeError> {
+        // `u64` microseconds since 1970 last for about 584000 years.
+        if !(1970..=500_000).contains(&self.year) {
+            return Err(DateTimeError::InvalidYear);
+        }
+        if !(1..=12).contains(&self.month) {
+            return Err(DateTimeError::InvalidMonth);
+        }
+        if self.day == 0 || self.day > days_in_month(self.year, self.month) {
+            return Err(DateTimeError::InvalidDay);
+        }
+        if self.hour > 23 || self.minute > 59 || self.second > 59 || self.microsecond > 999_999 {
+            return Err(DateTimeError::InvalidTime);
+        }
+        Ok(())
+    }
+
+    /// Day of the week.
+    pub fn weekday(&self) -> Weekday {
+        let days = days_from_civil(self.year as i64, self.month as i64, self.day as i64);
+        // 1970-01-01 was a Thursday.
+        Weekday::from_days_from_monday((days + 3).rem_euclid(7) as u8)
+    }
+}
+
+impl fmt::Display for DateTime {
+    /// Formats as ISO 8601, e.g. `2024-02-29T13:05:09.250000Z`.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{:04}-{:02}-{:02}T{:02}:{:02}:{:02}.{:06}Z",
+            self.year, self.month, self.day, self.hour, self.minute, self.second, self.microsecond
+        )
+    }
+}
+
+#[cfg(feature = "chrono")]
+impl TryFrom<chrono::NaiveDateTime> for DateTime {
+    type Error = DateTimeError;
+
+    /// The nanoseconds are rounded down to microseconds.
+    fn try_from(date_time: chrono::NaiveDateTime) -> Result<Self, DateTimeError> {
+        use chrono::{Datelike, Timelike};
+
+        let date_time = Self {
+            year: u32::try_from(date_time.year()).map_err(|_| DateTimeError::InvalidYear)?,
+            month: date_time.month() as u8,
+            day: date_time.day() as u8,
+            hour: date_time.hour() as u8,
+            minute: date_time.minute() as u8,
+            second: date_time.second() as u8,
+            microsecond: date_time.nanosecond() / 1000,
+        };
+        date_time.validate()?;
+        Ok(date_time)
+    }
+}
+
+#[cfg(feature = "chrono")]
+impl TryFrom<DateTime> for chrono::NaiveDateTime {
+    type Error = DateTimeError;
+
+    fn try_from(date_time: DateTime) -> Result<Self, DateTimeError> {
+        date_time.validate()?;
+        let year = i32::try_from(date_time.year).map_err(|_| DateTimeError::InvalidYear)?;
+        let date = chrono::NaiveDate::from_ymd_opt(year, date_time.month as u32, date_time.day as u32)
+            .ok_or(DateTimeError::InvalidYear)?;
+        let time = chrono::NaiveTime::from_hms_micro_opt(
+            date_time.hour as u32,
+            date_time.minute as u32,
+            date_time.second as u32,
+            date_time.microsecond,
+        )
+        .ok_or(DateTimeError::InvalidTime)?;
+        Ok(Self::new(date, time))
+    }
+}
+
+/// Day of the week.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[allow(missing_docs)]
+pub enum Weekday {
+    Monday,
+    Tuesday,
+    Wednesday,
+    Thursday,
+    Friday,
+    Saturday,
+    Sunday,
+}
+
+impl Weekday {
+    /// Number of days since Monday, from 0 (Monday) to 6 (Sunday).
+    pub const fn days_from_monday(self) -> u8 {
+        self as u8
+    }
+
+    /// Weekday from a number of days since Monday, modulo 7.
+    pub const fn from_days_from_monday(days: u8) -> Self {
+        match days % 7 {
+            0 => Weekday::Monday,
+            1 => Weekday::Tuesday,
+            2 => Weekday::Wednesday,
+            3 => Weekday::Thursday,
+            4 => Weekday::Friday,
+            5 => Weekday::Saturday,
+            _ => Weekday::Sunday,
+        }
+    }
+}
+
+#[cfg(feature = "chrono")]
+impl From<chrono::Weekday> for Weekday {
+    fn from(weekday: chrono::Weekday) -> Self {
+        Self::from_days_from_monday(weekday.num_days_from_monday() as u8)
+    }
+}
+
+#[cfg(feature = "chrono")]
+impl From<Weekday> for chrono::Weekday {
+    fn from(weekday: Weekday) -> Self {
+        match weekday {
+            Weekday::Monday => chrono::Weekday::Mon,
+            Weekday::Tuesday => chrono::Weekday::Tue,
+            Weekday::Wednesday => chrono::Weekday::Wed,
+            Weekday::Thursday => chrono::Weekday::Thu,
+            Weekday::Friday => chrono::Weekday::Fri,
+            Weekday::Saturday => chrono::Weekday::Sat,
+            Weekday::Sunday => chrono::Weekday::Sun,
+        }
+    }
+}
+
+/// Whether `year` is a leap year in the Gregorian calendar.
+pub const fn is_leap_year(year: u32) -> bool {
+    year.is_multiple_of(4) && (!year.is_multiple_of(100) || year.is_multiple_of(400))
+}
+
+/// Number of days in `month` (1 to 12) of `year`.
+pub const fn days_in_month(year: u32, month: u8) -> u8 {
+    match month {
+        2 if is_leap_year(year) => 29,
+        2 => 28,
+        4 | 6 | 9 | 11 => 30,
+        _ => 31,
+    }
+}
+
+// Conversions between days since the Unix epoch and proleptic Gregorian dates, counting years
+// from March so that leap days come last. See http://howardhinnant.github.io/date_algorithms.html
+
+fn days_from_civil(year: i64, month: i64, day: i64) -> i64 {
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = year.div_euclid(400);
+    let year_of_era = year - era * 400;
+    let day_of_year = (153 * ((month + 9) % 12) + 2) / 5 + day - 1;
+    let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+    era * 146_097 + day_of_era - DAYS_TO_UNIX_EPOCH
+}
+
+fn civil_from_days(days: i64) -> (i64, i64, i64) {
+    let days = days + DAYS_TO_UNIX_EPOCH;
+    let era = days.div_euclid(146_097);
+    let day_of_era = days - era * 146_097;
+    let year_of_era = (day_of_era - day_of_era / 1460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let month_from_march = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * month_from_march + 2) / 5 + 1;
+    let month = if month_from_march < 10 {
+        month_from_march + 3
+    } else {
+        month_from_march - 9
+    };
+    let year = year_of_era + era * 400 + (month <= 2) as i64;
+    (year, month, day)
+}
+
+/// Largest drift correction, in parts per billion. Bigger differences between the clock and
+/// the time it is set to are considered steps, not drift.
+const MAX_DRIFT_PPB: i64 = 500_000;
+/// Minimum time between two settings of the clock to estimate its drift.
+const MIN_DRIFT_INTERVAL: Duration = Duration::from_secs(60);
+
+/// A wall clock, anchoring [`Instant`]s to [`UtcTime`].
+///
+/// The clock is not running until it is [`set()`](Self::set) from a time source: an RTC at
+/// boot, SNTP, GNSS, the host... From then on, it extrapolates the current time from the
+/// monotonic [`Instant`] clock.
+///
+/// The time driver's clock is never perfectly accurate. Each time the clock is set again, at least
+/// a minute after the previous time, the difference between the extrapolated time and the new time
+/// is used to estimate the drift of the time driver's clock, which corrects later
+/// extrapolations. Differences bigger than 500 ppm are considered steps of the time, and don't
+/// affect the drift.
+///
+/// [`UtcTime::now()`] uses the [system clock](Self::system). Other clocks may be created with
+/// [`WallClock::new()`], e.g. to track a time source separately.
+#[derive(Debug)]
+pub struct WallClock {
+    anchor: Mutex<Cell<Option<Anchor>>>,
+    drift_ppb: Mutex<Cell<i64>>,
+}
+
+#[derive(Debug, Copy, Clone)]
+struct Anchor {
+    instant: Instant,
+    time: UtcTime,
+}
+
+static SYSTEM_CLOCK: WallClock = WallClock::new();
+
+impl WallClock {
+    /// Create a clock that is not set.
+    pub const fn new() -> Self {
+        Self {
+            anchor: Mutex::new(Cell::new(None)),
+            drift_ppb: Mutex::new(Cell::new(0)),
+        }
+    }
+
+    /// The system clock, used by [`UtcTime::now()`].
+    pub fn system() -> &'static WallClock {
+        &SYSTEM_CLOCK
+    }
+
+    /// Set the clock to `time` now.
+    pub fn set(&self, time: UtcTime) {
+        self.set_at(time, Instant::now())
+    }
+
+    /// Set the clock so that it was `time` at `instant`.
+    ///
+    /// Use this when the time was captured earlier than it's set, for example on a GNSS
+    /// pulse-per-second interrupt, or when an SNTP response was received.
+    pub fn set_at(&self, time: UtcTime, instant: Instant) {
+        critical_section::with(|cs| {
+            let drift = self.drift_ppb.borrow(cs);
+            let prev = self.anchor.borrow(cs).get();
+            let elapsed = prev.and_then(|prev| instant.checked_duration_since(prev.instant));
+            if let (Some(prev), Some(elapsed)) = (prev, elapsed.filter(|&e| e >= MIN_DRIFT_INTERVAL)) {
+                let elapsed = ticks_to_micros(elapsed.as_ticks() as i128);
+                let error = time.micros as i128 - extrapolate(prev, drift.get(), instant);
+                let correction = error * 1_000_000_000 / elapsed;
+                let corrected = drift.get() as i128 + correction;
+                if correction.abs() <= MAX_DRIFT_PPB as i128 && corrected.abs() <= MAX_DRIFT_PPB as i128 {
+                    drift.set(corrected as i64);
+                }
+            }
+            self.anchor.borrow(cs).set(Some(Anchor { instant, time }));
+        })
+    }
+
+    /// Set the clock from a calendar date and time, now.
+    pub fn set_datetime(&self, datetime: &DateTime) -> Result<(), DateTimeError> {
+        self.set(UtcTime::from_datetime(datetime)?);
+        Ok(())
+    }
+
+    /// Forget the time, and the estimated drift.
+    pub fn clear(&self) {
+        critical_section::with(|cs| {
+            self.anchor.borrow(cs).set(None);
+            self.drift_ppb.borrow(cs).set(0);
+        })
+    }
+
+    /// Returns whether the clock has been set.
+    pub fn is_set(&self) -> bool {
+        critical_section::with(|cs| self.anchor.borrow(cs).get().is_some())
+    }
+
+    /// Returns the current time, or `None` if the clock hasn't been set.
+    pub fn now(&self) -> Option<UtcTime> {
+        self.time_at(Instant::now())
+    }
+
+    /// Returns the time at `instant`, or `None` if the clock hasn't been set or `instant` is
+    /// before the Unix epoch.
+    pub fn time_at(&self, instant: Instant) -> Option<UtcTime> {
+        critical_section::with(|cs| {
+            let anchor = self.anchor.borrow(cs).get()?;
+            let micros = extrapolate(anchor, self.drift_ppb.borrow(cs).get(), instant);
+            u64::try_from(micros).ok().map(UtcTime::from_unix_micros)
+        })
+    }
+
+    /// Returns the instant at which it will be (or was) `time`, or `None` if the clock hasn't been
+    /// set or `time` is out of the range of [`Instant`].
+    ///
+    /// This can be used to wait until a wall-clock time, with [`Timer::at()`](crate::Timer::at).
+    pub fn instant_at(&self, time: UtcTime) -> Option<Instant> {
+        critical_section::with(|cs| {
+            let anchor = self.anchor.borrow(cs).get()?;
+            let drift = self.drift_ppb.borrow(cs).get() as i128;
+            let corrected = time.micros as i128 - anchor.time.micros as i128;
+            let micros = corrected * 1_000_000_000 / (1_000_000_000 + drift);
+            let ticks = anchor.instant.as_ticks() as i128 + micros * TICK_HZ as i128 / MICROS_PER_SEC as i128;
+            u64::try_from(ticks).ok().map(Instant::from_ticks)
+        })
+    }
+
+    /// Estimated drift of the time driver's clock, in parts per billion. Positive if it runs slow.
+    pub fn drift_ppb(&self) -> i64 {
+        critical_section::with(|cs| self.drift_ppb.borrow(cs).get())
+    }
+
+    /// Set the drift of the time driver's clock, in parts per billion, e.g. from a value measured
+    /// in production and stored in flash. Positive if it runs slow.
+    ///
+    /// Clamped to ±500 ppm.
+    pub fn set_drift_ppb(&self, drift_ppb: i64) {
+        critical_section::with(|cs| {
+            self.drift_ppb
+                .borrow(cs)
+                .set(drift_ppb.clamp(-MAX_DRIFT_PPB, MAX_DRIFT_PPB))
+        })
+    }
+}
+
+fn ticks_to_micros(ticks: i128) -> i128 {
+    ticks * MICROS_PER_SEC as i128 / TICK_HZ as i128
+}
+
+/// Microseconds since the Unix epoch at `instant`, extrapolated from `anchor`.
+fn extrapolate(anchor: Anchor, drift_ppb: i64, instant: Instant) -> i128 {
+    let elapsed = ticks_to_micros(instant.as_ticks() as i128 - anchor.instant.as_ticks() as i128);
+    anchor.time.micros as i128 + elapsed + elapsed * drift_ppb as i128 / 1_000_000_000
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn datetime(year: u32, month: u8, day: u8, hour: u8, minute: u8, second: u8) -> DateTime {
+        DateTime::from_ymd(year, month, day)
+            .unwrap()
+            .with_hms(hour, minute, second)
+            .unwrap()
+    }
+
+    #[test]
+    fn test_calendar_conversion() {
+        let cases = [
+            (0, datetime(1970, 1, 1, 0, 0, 0)),
+            (951_782_400, datetime(2000, 2, 29, 0, 0, 0)),
+            (1_709_211_909, datetime(2024, 2, 29, 13, 5, 9)),
+            (4_107_542_399, datetime(2100, 2, 28, 23, 59, 59)),
+            (4_107_542_400, datetime(2100, 3, 1, 0, 0, 0)),
+        ];
+        for (secs, dt) in cases {
+            let time = UtcTime::from_unix_secs(secs);
+            assert_eq!(time.to_datetime(), dt);
+            assert_eq!(UtcTime::from_datetime(&dt), Ok(time));
+        }
+
+        // Every day of a few 400-year cycles round-trips.
+        for days in 0..400 * 366 * 3 {
+            let time = UtcTime::from_unix_secs(days * SECS_PER_DAY + 12345);
+            assert_eq!(UtcTime::from_datetime(&time.to_datetime()), Ok(time));
+        }
+    }
+
+    #[test]
+    fn test_invalid_datetime() {
+        assert_eq!(DateTime::from_ymd(2023, 2, 29), Err(DateTimeError::InvalidDay));
+        assert_eq!(DateTime::from_ymd(1900, 2, 28), Err(DateTimeError::InvalidYear));
+        assert_eq!(DateTime::from_ymd(2024, 13, 1), Err(DateTimeError::InvalidMonth));
+        assert_eq!(DateTime::from_ymd(2024, 4, 31), Err(DateTimeError::InvalidDay));
+        assert_eq!(
+            DateTime::from_ymd(2024, 4, 30).unwrap().with_hms(24, 0, 0),
+            Err(DateTimeError::InvalidTime)
+        );
+        assert!(is_leap_year(2000) && is_leap_year(2024) && !is_leap_year(2100));
+    }
+
+    #[test]
+    fn test_weekday_and_display() {
+        assert_eq!(UtcTime::UNIX_EPOCH.weekday(), Weekday::Thursday);
+        assert_eq!(datetime(2024, 2, 29, 0, 0, 0).weekday(), Weekday::Thursday);
+        assert_eq!(datetime(2000, 1, 2, 0, 0, 0).weekday(), Weekday::Sunday);
+
+        let time = UtcTime::from_unix_micros(1_709_211_909_250_000);
+        assert_eq!(format!("{}", time), "2024-02-29T13:05:09.250000Z");
+    }
+
+    #[test]
+    fn test_from_unix_saturates() {
+        assert_eq!(UtcTime::from_unix_secs(u64::MAX).as_unix_micros(), u64::MAX);
+        assert_eq!(UtcTime::from_unix_millis(u64::MAX).as_unix_micros(), u64::MAX);
+    }
+
+    #[cfg(feature = "chrono")]
+    #[test]
+    fn test_chrono_conversion() {
+        let dt = DateTime {
+            microsecond: 250_000,
+            ..datetime(2024, 2, 29, 13, 5, 9)
+        };
+        let naive = chrono::NaiveDateTime::try_from(dt).unwrap();
+        assert_eq!(naive.to_string(), "2024-02-29 13:05:09.250");
+        assert_eq!(DateTime::try_from(naive), Ok(dt));
+        assert_eq!(chrono::Weekday::from(dt.weekday()), chrono::Weekday::Thu);
+        assert_eq!(Weekday::from(chrono::Weekday::Sun), Weekday::Sunday);
+
+        let before_epoch = chrono::NaiveDate::from_ymd_opt(1969, 12, 31)
+            .unwrap()
+            .and_hms_opt(0, 0, 0)
+            .unwrap();
+        assert_eq!(DateTime::try_from(before_epoch), Err(DateTimeError::InvalidYear));
+    }
+
+    #[cfg(feature = "mock-driver")]
+    #[test]
+    #[serial_test::serial]
+    fn test_wall_clock() {
+        let driver = crate::MockDriver::get();
+        driver.reset();
+        let clock = WallClock::new();
+        assert_eq!(clock.now(), None);
+
+        let start = UtcTime::from_unix_secs(1_700_000_000);
+        clock.set(start);
+        driver.advance(Duration::from_secs(10));
+        assert_eq!(clock.now(), Some(start + Duration::from_secs(10)));
+
+        // The time driver ran 100 ppm slow over 1000 s.
+        driver.advance(Duration::from_secs(990));
+        clock.set(start + Duration::from_millis(1_000_100));
+        assert_eq!(clock.drift_ppb(), 100_000);
+        driver.advance(Duration::from_secs(1000));
+        assert_eq!(clock.now(), Some(start + Duration::from_millis(2_000_200)));
+        assert_eq!(
+            clock.instant_at(start + Duration::from_millis(2_000_200)),
+            Some(Instant::now())
+        );
+
+        // A big change is a step, not drift.
+        clock.set(UtcTime::from_unix_secs(1_800_000_000));
+        assert_eq!(clock.drift_ppb(), 100_000);
+        assert_eq!(clock.now(), Some(UtcTime::from_unix_secs(1_800_000_000)));
+    }
+}
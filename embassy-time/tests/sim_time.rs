@@ -0,0 +1,42 @@
+#![cfg(feature = "sim-driver")]
+
+use std::sync::atomic::{AtomicU64, Ordering};
+
+use embassy_executor::Spawner;
+use embassy_time::{Duration, Instant, SimDriver, Timer};
+
+static SHORT: AtomicU64 = AtomicU64::new(0);
+static LONG: AtomicU64 = AtomicU64::new(0);
+
+#[embassy_executor::task(pool_size = 2)]
+async fn sleeper(duration: Duration, woken_at: &'static AtomicU64) {
+    Timer::after(duration).await;
+    woken_at.store(Instant::now().as_secs(), Ordering::Relaxed);
+}
+
+#[embassy_executor::test(sim_time)]
+async fn tasks_sleep_in_simulated_time(spawner: Spawner) {
+    spawner.spawn(sleeper(Duration::from_secs(24 * 3600), &LONG).unwrap());
+    spawner.spawn(sleeper(Duration::from_secs(3600), &SHORT).unwrap());
+
+    Timer::after(Duration::from_secs(2 * 3600)).await;
+    assert_eq!(SHORT.load(Ordering::Relaxed), 3600);
+    assert_eq!(LONG.load(Ordering::Relaxed), 0);
+
+    Timer::after(Duration::from_secs(24 * 3600)).await;
+    assert_eq!(LONG.load(Ordering::Relaxed), 24 * 3600);
+    assert_eq!(Instant::now().as_secs(), 26 * 3600);
+
+    // Time only jumped to the scheduled wakes.
+    let woken_at: Vec<u64> = SimDriver::get()
+        .timeline()
+        .iter()
+        .map(|wake| wake.woken_at.as_secs())
+        .collect();
+    assert_eq!(woken_at, [3600, 2 * 3600, 24 * 3600, 26 * 3600]);
+}
+
+#[embassy_executor::test(sim_time)]
+async fn sim_time_starts_at_zero() {
+    assert_eq!(Instant::now(), Instant::from_ticks(0));
+}
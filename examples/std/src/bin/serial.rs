@@ -1,8 +1,8 @@
 #[path = "../serial_port.rs"]
 mod serial_port;
 
-use async_io::Async;
 use embassy_executor::Executor;
+use embassy_executor::reactor::Async;
 use embassy_time as _;
 use embedded_io_async::Read;
 use log::*;
@@ -18,17 +18,10 @@ async fn run() {
     let port = SerialPort::new("/dev/ttyACM0", baudrate).unwrap();
     //let port = Spy::new(port);
 
-    // Use async_io's reactor for async IO.
-    // This demonstrates how embassy's executor can drive futures from another IO library.
-    // Essentially, async_io::Async converts from AsRawFd+Read+Write to futures's AsyncRead+AsyncWrite
-    let port = Async::new(port).unwrap();
-
-    // We can then use FromStdIo to convert from futures's AsyncRead+AsyncWrite
-    // to embedded_io's async Read+Write.
-    //
-    // This is not really needed, you could write the code below using futures::io directly.
-    // It's useful if you want to have portable code across embedded and std.
-    let mut port = embedded_io_adapters::futures_03::FromFutures::new(port);
+    // Use the executor's reactor for async IO.
+    // Async implements embedded_io's async Read+Write for any AsRawFd+Read+Write,
+    // so this code is portable across embedded and std.
+    let mut port = Async::new(port).unwrap();
 
     info!("Serial opened!");
 
@@ -44,7 +37,6 @@ static EXECUTOR: StaticCell<Executor> = StaticCell::new();
 fn main() {
     env_logger::builder()
         .filter_level(log::LevelFilter::Debug)
-        .filter_module("async_io", log::LevelFilter::Info)
         .format_timestamp_nanos()
         .init();
 